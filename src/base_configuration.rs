@@ -2,9 +2,18 @@ use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
 use uuid::Uuid;
 
-#[derive(Debug, Clone)]
-// Placeholder for TransformScriptConfig (since details are missing)
-pub struct TransformScriptConfig;
+#[derive(Debug, Clone, Default)]
+// Script of a Transformer; an empty target writes the result to the payload, otherwise to that flow variable
+pub struct TransformScriptConfig {
+    pub(crate) target: String,
+    pub(crate) script: String,
+}
+
+impl TransformScriptConfig {
+    pub(crate) fn new(target: &str, script: &str) -> Self {
+        Self { target: target.to_string(), script: script.to_string() }
+    }
+}
 
 #[derive(Debug, Clone)]
 pub struct DefaultWidgetFactory;
@@ -14,6 +23,8 @@ pub struct DefaultWidgetFactory;
 pub(crate) enum ComponentLevel {
     ParentOnly,
     ChildOnly,
+    SourceOnly, // Trigger of a flow, only allowed as the first child of a MainFlow
+    Global, // Project-level configuration that components reference by name
     Other,
 }
 
@@ -22,6 +33,8 @@ impl ComponentLevel {
         match self {
             ComponentLevel::ParentOnly => "ParentOnly",
             ComponentLevel::ChildOnly => "ChildOnly",
+            ComponentLevel::SourceOnly => "SourceOnly",
+            ComponentLevel::Global => "Global",
             ComponentLevel::Other => "Other",
         }
     }
@@ -30,6 +43,8 @@ impl ComponentLevel {
         match s {
             "ParentOnly" => Some(ComponentLevel::ParentOnly),
             "ChildOnly" => Some(ComponentLevel::ChildOnly),
+            "SourceOnly" => Some(ComponentLevel::SourceOnly),
+            "Global" => Some(ComponentLevel::Global),
             "Other" => Some(ComponentLevel::Other),
             _ => Some(ComponentLevel::Other),
         }
@@ -95,7 +110,7 @@ pub(crate) struct BaseConfiguration {
     pub(crate) is_expanded: bool,
     pub(crate) is_selected: bool,
     pub(crate) error_handling_expanded: bool,
-    pub(crate) variable_changed: String,
+    pub(crate) variable_changed: String, // Variables this component writes, refreshed by the variable analysis
     pub(crate) debug: bool,
     pub(crate) is_deleted: bool,
     pub(crate) transform_script_configs: Vec<Arc<Mutex<TransformScriptConfig>>>,
@@ -103,6 +118,7 @@ pub(crate) struct BaseConfiguration {
     pub(crate) initialize_field_setters: bool,
     pub(crate) selected_script_index: SelectedScriptIndex,
     pub(crate) script_index: i32,
+    pub(crate) properties: HashMap<String, String>, // Component specific settings, keyed by property name
 }
 
 impl BaseConfiguration {
@@ -145,6 +161,7 @@ impl BaseConfiguration {
             initialize_field_setters: false,
             selected_script_index: SelectedScriptIndex::new(0),
             script_index: 0,
+            properties: HashMap::new(),
         }
     }
 
@@ -153,6 +170,68 @@ impl BaseConfiguration {
     pub(crate) fn box_initial_width(mut self, width: f64) -> Self { self.box_initial_width = width;self }
     pub(crate) fn is_expanded(mut self, expanded: bool) -> Self { self.is_expanded = expanded;self }
     pub(crate) fn name(mut self, name: String) -> Self { self.name = name;self }
+    pub(crate) fn component_level(mut self, level: ComponentLevel) -> Self { self.component_level = level;self }
+    pub(crate) fn property(mut self, key: &str, value: &str) -> Self { self.properties.insert(key.to_string(), value.to_string());self }
+    pub(crate) fn transform_script(mut self, script: TransformScriptConfig) -> Self { self.transform_script_configs.push(Arc::new(Mutex::new(script)));self }
+    pub(crate) fn child(mut self, child: BaseConfiguration) -> Self { self.children.push(Arc::new(Mutex::new(child)));self }
+
+    // Returns the property value, or an empty string when it was never set
+    pub(crate) fn get_property(&self, key: &str) -> &str {
+        self.properties.get(key).map(|value| value.as_str()).unwrap_or("")
+    }
+
+    // Deep copy of the component tree without locks, safe to hand over to the runtime or the code generator
+    pub(crate) fn snapshot(&self) -> ComponentSnapshot {
+        ComponentSnapshot {
+            uuid: self.uuid.clone(),
+            widget_type: self.widget_type.clone(),
+            component_level: self.component_level.clone(),
+            name: self.name.clone(),
+            initial_state: self.initial_state.clone(),
+            max_concurrency: self.max_concurrency,
+            is_tracking_enabled: self.is_tracking_enabled,
+            properties: self.properties.clone(),
+            transform_scripts: self.transform_script_configs.iter()
+                .filter_map(|script| script.lock().ok().map(|script| script.clone()))
+                .collect(),
+            children: self.children.iter()
+                .filter_map(|child| child.lock().ok())
+                .filter(|child| !child.is_deleted)
+                .map(|child| child.snapshot())
+                .collect(),
+        }
+    }
+}
+
+// Immutable copy of a component and its children, produced by `BaseConfiguration::snapshot`
+#[derive(Debug, Clone)]
+pub(crate) struct ComponentSnapshot {
+    pub(crate) uuid: String,
+    pub(crate) widget_type: String,
+    pub(crate) component_level: ComponentLevel,
+    pub(crate) name: String,
+    pub(crate) initial_state: String,
+    pub(crate) max_concurrency: i32,
+    pub(crate) is_tracking_enabled: bool,
+    pub(crate) properties: HashMap<String, String>,
+    pub(crate) transform_scripts: Vec<TransformScriptConfig>,
+    pub(crate) children: Vec<ComponentSnapshot>,
+}
+
+impl ComponentSnapshot {
+    pub(crate) fn get_property(&self, key: &str) -> &str {
+        self.properties.get(key).map(|value| value.as_str()).unwrap_or("")
+    }
+
+    // Flows start unless their initial state says otherwise
+    pub(crate) fn is_initially_stopped(&self) -> bool {
+        self.initial_state.eq_ignore_ascii_case("stopped")
+    }
+
+    // The source triggering this flow, if its first child is one
+    pub(crate) fn source(&self) -> Option<&ComponentSnapshot> {
+        self.children.first().filter(|child| child.component_level == ComponentLevel::SourceOnly)
+    }
 }
 
 
@@ -0,0 +1,427 @@
+use std::time::Duration;
+use crate::base_configuration::ComponentSnapshot;
+use crate::codegen::writer::CodeWriter;
+use crate::codegen::CodegenError;
+use crate::components::inspector::PropertyKind;
+use crate::components::property_fields;
+use crate::expression::{self, rust::to_rust};
+use crate::runtime::{retry_policy_of, route_timeout};
+use crate::support::object_store;
+
+// Text settings translated with `compile_text`; with expressions, the only settings that may hold
+// `${secure::name}` placeholders, because only they are evaluated when the service runs
+const SECURE_TEXT_PROPERTIES: &[&str] = &["url", "message"];
+
+// Rejects secure placeholders in settings the generator bakes into the service
+fn check_secure_placeholders(component: &ComponentSnapshot) -> Result<(), CodegenError> {
+    for (key, value) in &component.properties {
+        if !value.contains("${secure::") {
+            continue;
+        }
+        let is_expression = property_fields(&component.widget_type).iter()
+            .any(|field| field.key == key && field.kind == PropertyKind::Expression);
+        let is_condition = component.widget_type == "When" && key == "expression";
+        if !is_expression && !is_condition && !SECURE_TEXT_PROPERTIES.contains(&key.as_str()) {
+            return Err(CodegenError {
+                component: component.name.clone(),
+                message: format!("Secure properties cannot be used in '{}' of a generated service", key),
+            });
+        }
+    }
+    component.children.iter().try_for_each(check_secure_placeholders)
+}
+
+// Translates a text property that may embed an expression as `#[...]`
+fn compile_text(component: &ComponentSnapshot, value: &str) -> Result<String, CodegenError> {
+    let expr = expression::parse_text(value).map_err(|error| CodegenError {
+        component: component.name.clone(),
+        message: format!("Invalid expression in '{}': {}", value, error),
+    })?;
+    Ok(to_rust(&expr))
+}
+
+// Translates an expression property, reporting parse errors against the owning component
+fn compile_expression(component: &ComponentSnapshot, source: &str) -> Result<String, CodegenError> {
+    let expr = expression::parse(source).map_err(|error| CodegenError {
+        component: component.name.clone(),
+        message: format!("Invalid expression '{}': {}", source, error),
+    })?;
+    Ok(to_rust(&expr))
+}
+
+// Emits one `async fn` per flow; `function_names` holds the identifier chosen for each flow
+pub(crate) fn generate_flows(flows: &[ComponentSnapshot], function_names: &[String]) -> Result<String, CodegenError> {
+    let mut writer = CodeWriter::new();
+    writer.line("// Generated by Integration Builder. Changes are overwritten on the next generation.");
+    writer.line("#![allow(dead_code, unused_mut, unused_variables, unused_imports, unreachable_code, unused_parens, clippy::all)]");
+    writer.line("");
+    writer.line("use std::sync::{Arc, OnceLock};");
+    writer.line("use integration_support::supervisor::{Backpressure, FlowControl};");
+    writer.line("use integration_support::{broker, database, executor, files, http, logging, object_store, ops, retry, scopes, secure, FlowError, Message};");
+    writer.line("use serde_json::Value;");
+    writer.line("");
+    writer.line("// Retries go to the log like the runtime's own entries");
+    writer.open("fn log_retry(retry: String) {");
+    writer.line("logging::log(&logging::LogRecord::new(\"WARN\", \"runtime\", retry));");
+    writer.close("}");
+    for (flow, function_name) in flows.iter().zip(function_names) {
+        writer.line("");
+        check_secure_placeholders(flow)?;
+        writer.line(&format!("// Flow '{}' ({})", flow.name, flow.uuid));
+        generate_control(&mut writer, flow, function_name);
+        writer.line("");
+        writer.open(&format!("pub async fn {}(mut msg: Message) -> Result<Message, FlowError> {{", function_name));
+        writer.line(&format!("let _permit = {}_control().acquire()?;", function_name));
+        generate_components(&mut writer, &flow.children)?;
+        writer.line("Ok(msg)");
+        writer.close("}");
+        generate_response(&mut writer, flow, function_name)?;
+    }
+    Ok(writer.finish())
+}
+
+// Emits `<flow>_control`, admitting runs as the editor's runtime does: none while the flow is
+// stopped, at most `max_concurrency` at a time
+fn generate_control(writer: &mut CodeWriter, flow: &ComponentSnapshot, function_name: &str) {
+    writer.open(&format!("pub fn {}_control() -> &'static Arc<FlowControl> {{", function_name));
+    writer.line("static CONTROL: OnceLock<Arc<FlowControl>> = OnceLock::new();");
+    writer.line(&format!(
+        "CONTROL.get_or_init(|| FlowControl::new({:?}, {}, Backpressure::from_name({:?}), {}))",
+        flow.name,
+        flow.max_concurrency.max(1),
+        flow.get_property("backpressure"),
+        !flow.is_initially_stopped()
+    ));
+    writer.close("}");
+}
+
+// Emits `<flow>_response`, evaluating the listener's response status and headers on the flow result
+fn generate_response(writer: &mut CodeWriter, flow: &ComponentSnapshot, function_name: &str) -> Result<(), CodegenError> {
+    writer.line("");
+    writer.open(&format!("pub fn {}_response(msg: &Message) -> Result<(Value, Value), FlowError> {{", function_name));
+    match flow.source().filter(|source| source.widget_type == "HttpListener") {
+        Some(listener) => {
+            writer.line(&format!("let status = {};", compile_expression(listener, listener.get_property("response_status"))?));
+            writer.line(&format!("let headers = {};", compile_expression(listener, listener.get_property("response_headers"))?));
+            writer.line("Ok((status, headers))");
+        }
+        None => writer.line("Ok((Value::from(200), Value::Null))"),
+    }
+    writer.close("}");
+    Ok(())
+}
+
+// An optional route timeout as a Rust `Option<Duration>` expression
+fn timeout_expression(timeout: Option<Duration>) -> String {
+    match timeout {
+        Some(timeout) => format!("Some(std::time::Duration::from_millis({}))", timeout.as_millis()),
+        None => "None".to_string(),
+    }
+}
+
+fn generate_components(writer: &mut CodeWriter, components: &[ComponentSnapshot]) -> Result<(), CodegenError> {
+    for component in components {
+        generate_component(writer, component)?;
+    }
+    Ok(())
+}
+
+// Emits the code of one component; an operation with a retry policy runs again on a copy of the
+// message it received, as the runtime does
+fn generate_component(writer: &mut CodeWriter, component: &ComponentSnapshot) -> Result<(), CodegenError> {
+    writer.line(&format!("// {} '{}'", component.widget_type, component.name));
+    let policy = match retry_policy_of(component) {
+        Some(policy) => policy,
+        None => return generate_operation(writer, component),
+    };
+    writer.line(&format!(
+        "let policy = retry::RetryPolicy::new({:?}, {}, {}, {}, {:?}, {:?}, {:?});",
+        policy.backoff.name(),
+        policy.max_retries,
+        policy.delay.as_millis(),
+        policy.max_delay.as_millis(),
+        policy.multiplier,
+        policy.jitter,
+        policy.retryable_errors.join(", ")
+    ));
+    generate_retried(writer, "retry::with_policy(&policy, log_retry, ", |writer| generate_operation(writer, component))
+}
+
+// Emits `msg = <call>|| ...)?;` where the closure runs `body` on a copy of the message
+fn generate_retried(
+    writer: &mut CodeWriter,
+    call: &str,
+    body: impl FnOnce(&mut CodeWriter) -> Result<(), CodegenError>,
+) -> Result<(), CodegenError> {
+    writer.open(&format!("msg = {}|| {{", call));
+    writer.line("let mut msg = msg.clone();");
+    writer.open("executor::block_on(async move {");
+    body(writer)?;
+    writer.line("Ok::<Message, FlowError>(msg)");
+    writer.close("})");
+    writer.close("})?;");
+    Ok(())
+}
+
+// Expression opening the object store a component references, or its own in-memory store
+fn object_store_expression(component: &ComponentSnapshot) -> String {
+    let name = component.get_property("object_store").trim();
+    if name.is_empty() {
+        return format!("object_store::shared_store({:?}, object_store::StoreSettings::default())?", component.uuid);
+    }
+    let number = |key: &str| component.get_property(key).trim().parse::<f64>().unwrap_or(0.0).max(0.0);
+    format!(
+        "object_store::shared_store({:?}, object_store::StoreSettings::new({:?}, {}, {}, files::resolve_path(&files::project_folder(), {:?})))?",
+        name,
+        component.get_property("store_persistence"),
+        number("store_ttl") as u64,
+        number("store_max_entries") as usize,
+        object_store::store_file(name, component.get_property("store_file"))
+    )
+}
+
+fn generate_operation(writer: &mut CodeWriter, component: &ComponentSnapshot) -> Result<(), CodegenError> {
+    match component.widget_type.as_str() {
+        "HttpListener" => writer.line("// Source: requests are dispatched to this flow by main.rs"),
+        "Scheduler" => writer.line("// Source: runs are started by the scheduler threads in main.rs"),
+        "OnNewFile" => writer.line("// Source: runs are started by the file watcher threads in main.rs"),
+        "QueueListener" => writer.line("// Source: runs are started by the queue listener threads in main.rs"),
+        "HttpRequest" => {
+            let timeout: u64 = component.get_property("timeout").trim().parse().unwrap_or(30000);
+            writer.line(&format!("let url = ops::to_text(&{});", compile_text(component, component.get_property("url"))?));
+            if !component.get_property("base_url").trim().is_empty() {
+                writer.line(&format!("let url = http::join_url({:?}, &url);", component.get_property("base_url").trim()));
+            }
+            writer.line(&format!("let headers = {};", compile_expression(component, component.get_property("headers"))?));
+            writer.line(&format!("let body = {};", compile_expression(component, component.get_property("body"))?));
+            writer.line(&format!(
+                "msg = http::execute_request(&msg, {:?}, &url, &headers, &body, {})?;",
+                component.get_property("method"),
+                timeout
+            ));
+        }
+        "Transformer" => {
+            // Evaluate every script against the incoming message before assigning, as the runtime does
+            writer.open("{");
+            for (index, script) in component.transform_scripts.iter().enumerate() {
+                writer.line(&format!("let result_{} = {};", index, compile_expression(component, &script.script)?));
+            }
+            for (index, script) in component.transform_scripts.iter().enumerate() {
+                if script.target.is_empty() {
+                    writer.line(&format!("msg.payload = result_{};", index));
+                } else {
+                    writer.line(&format!("msg.variables.insert({:?}.to_string(), result_{});", script.target, index));
+                }
+            }
+            writer.close("}");
+        }
+        "Choice" => {
+            let mut first = true;
+            for route in &component.children {
+                let header = match route.widget_type.as_str() {
+                    "When" => format!(
+                        "if ops::truthy(&{}) {{",
+                        compile_expression(component, route.get_property("expression"))?
+                    ),
+                    _ => "{".to_string(),
+                };
+                if first {
+                    writer.open(&header);
+                } else {
+                    writer.reopen(&format!("}} else {}", header));
+                }
+                generate_components(writer, &route.children)?;
+                first = false;
+                if route.widget_type != "When" {
+                    // Otherwise catches everything, later routes are unreachable
+                    break;
+                }
+            }
+            if !first {
+                writer.close("}");
+            }
+        }
+        "SetVariable" => writer.line(&format!(
+            "msg.variables.insert({:?}.to_string(), {});",
+            component.get_property("variable_name").trim(),
+            compile_expression(component, component.get_property("value"))?
+        )),
+        "RemoveVariable" => writer.line(&format!("msg.variables.remove({:?});", component.get_property("variable_name").trim())),
+        "Database" => {
+            writer.line(&format!("let parameters = {};", compile_expression(component, component.get_property("input_parameters"))?));
+            writer.line(&format!(
+                "msg = database::execute(&msg, {:?}, {:?}, {:?}, &parameters)?;",
+                component.get_property("database_file").trim(),
+                component.get_property("operation"),
+                component.get_property("sql")
+            ));
+        }
+        "File" => {
+            writer.line(&format!("let path = ops::to_text(&{});", compile_text(component, component.get_property("path"))?));
+            match component.get_property("operation") {
+                "Write" => {
+                    writer.line(&format!("let content = {};", compile_expression(component, component.get_property("content"))?));
+                    writer.line(&format!(
+                        "files::write(&files::project_folder(), &path, &content, {:?})?;",
+                        component.get_property("write_mode")
+                    ));
+                }
+                "List" => writer.line(&format!(
+                    "msg = files::list(&msg, &files::project_folder(), &path, {:?})?;",
+                    component.get_property("pattern")
+                )),
+                "Move" => {
+                    writer.line(&format!("let target = ops::to_text(&{});", compile_text(component, component.get_property("target_path"))?));
+                    writer.line(&format!(
+                        "files::move_file(&files::project_folder(), &path, &target, {})?;",
+                        component.get_property("overwrite") == "true"
+                    ));
+                }
+                _ => writer.line("msg = files::read(&msg, &files::project_folder(), &path)?;"),
+            }
+        }
+        "Publish" => {
+            writer.line(&format!("let body = {};", compile_expression(component, component.get_property("body"))?));
+            writer.line(&format!("let properties = {};", compile_expression(component, component.get_property("properties"))?));
+            writer.line(&format!(
+                "let destination = broker::Destination::new({:?}, {:?})?;",
+                component.get_property("destination_type"),
+                component.get_property("destination")
+            ));
+            writer.line(&format!(
+                "broker::publish(&*broker::shared_broker({:?}, {:?})?, &destination, &msg, body, &properties)?;",
+                component.get_property("config"),
+                component.get_property("broker")
+            ));
+        }
+        "Consume" => writer.line(&format!(
+            "msg = broker::consume(&*broker::shared_broker({:?}, {:?})?, {:?}, {}, &msg)?;",
+            component.get_property("config"),
+            component.get_property("broker"),
+            component.get_property("queue"),
+            component.get_property("timeout").trim().parse::<u64>().unwrap_or(10000)
+        )),
+        "SetPayload" => {
+            writer.line(&format!("msg.payload = {};", compile_expression(component, component.get_property("value"))?));
+            if !component.get_property("mime_type").trim().is_empty() {
+                writer.line(&format!("msg.mime_type = {:?}.to_string();", component.get_property("mime_type").trim()));
+            }
+        }
+        "Logger" => {
+            let text = match component.get_property("message") {
+                "" => "ops::to_text(&msg.payload)".to_string(),
+                source => format!("ops::to_text(&{})", compile_text(component, source)?),
+            };
+            writer.line(&format!(
+                "logging::log(&logging::LogRecord::new({:?}, {:?}, {}));",
+                component.get_property("level"),
+                component.get_property("category"),
+                text
+            ));
+        }
+        "ForEach" => {
+            let batch_size: usize = component.get_property("batch_size").trim().parse().unwrap_or(1);
+            let counter = match component.get_property("counter_variable").trim() {
+                "" => "counter",
+                counter => counter,
+            };
+            writer.open("{");
+            writer.line(&format!("let collection = {};", compile_expression(component, component.get_property("collection"))?));
+            writer.line("let original_payload = msg.payload.clone();");
+            writer.open(&format!(
+                "for (index, item) in scopes::batches(scopes::collection_items(&collection), {}).into_iter().enumerate() {{",
+                batch_size
+            ));
+            writer.line("msg.payload = item;");
+            writer.line(&format!("msg.variables.insert({:?}.to_string(), Value::from(index + 1));", counter));
+            generate_components(writer, &component.children)?;
+            writer.close("}");
+            writer.line("msg.payload = original_payload;");
+            writer.close("}");
+        }
+        "ParallelForEach" => {
+            let max_concurrency: usize = component.get_property("max_concurrency").trim().parse().unwrap_or(4);
+            writer.open("{");
+            writer.line(&format!("let collection = {};", compile_expression(component, component.get_property("collection"))?));
+            writer.line("let routes: Vec<Message> = scopes::collection_items(&collection).into_iter().map(|item| Message { payload: item, ..msg.clone() }).collect();");
+            let timeout = component.get_property("timeout").trim().parse().ok().map(Duration::from_millis);
+            writer.line(&format!("let timeouts = vec![{}; routes.len()];", timeout_expression(timeout)));
+            writer.open(&format!(
+                "let results = scopes::run_parallel(routes, {}, timeouts, |_, mut msg: Message| executor::block_on(async move {{",
+                max_concurrency
+            ));
+            generate_components(writer, &component.children)?;
+            writer.line("Ok::<Message, FlowError>(msg)");
+            writer.close("}))?;");
+            writer.line("msg.payload = Value::Array(results.into_iter().map(|result| result.payload).collect());");
+            writer.close("}");
+        }
+        "Async" => {
+            // The copy of the message moves to the scope's queue; the flow carries on with `msg`
+            let max_concurrency: usize = component.get_property("max_concurrency").trim().parse().unwrap_or(4);
+            writer.open("{");
+            writer.line("static QUEUE: OnceLock<Arc<scopes::AsyncQueue>> = OnceLock::new();");
+            writer.line("let mut msg = msg.clone();");
+            writer.open(&format!("QUEUE.get_or_init(|| scopes::AsyncQueue::new({})).submit(Box::new(move || {{", max_concurrency));
+            writer.open("let result = executor::block_on(async move {");
+            generate_components(writer, &component.children)?;
+            writer.line("Ok::<Message, FlowError>(msg)");
+            writer.close("});");
+            writer.open("if let Err(error) = result {");
+            writer.line(&format!("println!(\"Async scope '{{}}' failed: {{}}\", {:?}, error);", component.name));
+            writer.close("}");
+            writer.close("}));");
+            writer.close("}");
+        }
+        "UntilSuccessful" => {
+            let max_retries: u32 = component.get_property("max_retries").trim().parse().unwrap_or(5);
+            let delay: u64 = component.get_property("milliseconds_between_retries").trim().parse().unwrap_or(1000);
+            generate_retried(writer, &format!("retry::until_successful({}, {}, log_retry, ", max_retries, delay), |writer| {
+                generate_components(writer, &component.children)
+            })?;
+        }
+        "Cache" => {
+            writer.open("{");
+            writer.line(&format!("let key = ops::to_text(&{});", compile_expression(component, component.get_property("key"))?));
+            writer.line(&format!("let store = {};", object_store_expression(component)));
+            writer.open("msg = object_store::cached(&*store, &key, msg, |mut msg| executor::block_on(async move {");
+            generate_components(writer, &component.children)?;
+            writer.line("Ok::<Message, FlowError>(msg)");
+            writer.close("}))?;");
+            writer.close("}");
+        }
+        "IdempotentMessageValidator" => {
+            writer.line(&format!("let id = ops::to_text(&{});", compile_expression(component, component.get_property("id_expression"))?));
+            writer.line(&format!("object_store::validate_unique(&*{}, &id)?;", object_store_expression(component)));
+        }
+        "ScatterGather" => {
+            let max_concurrency: usize = component.get_property("max_concurrency").trim().parse().unwrap_or(4);
+            writer.open("{");
+            writer.line(&format!("let routes = vec![msg.clone(); {}];", component.children.len()));
+            let timeouts: Vec<String> = component.children.iter().map(|route| timeout_expression(route_timeout(component, route))).collect();
+            writer.line(&format!("let timeouts = vec![{}];", timeouts.join(", ")));
+            writer.open(&format!(
+                "let results = scopes::run_parallel(routes, {}, timeouts, |index, mut msg: Message| executor::block_on(async move {{",
+                max_concurrency
+            ));
+            writer.open("match index {");
+            for (index, route) in component.children.iter().enumerate() {
+                writer.open(&format!("{} => {{", index));
+                writer.line(&format!("// {}", route.name));
+                generate_components(writer, &route.children)?;
+                writer.close("}");
+            }
+            writer.line("_ => {}");
+            writer.close("}");
+            writer.line("Ok::<Message, FlowError>(msg)");
+            writer.close("}))?;");
+            writer.line("msg = scopes::gather(msg, results);");
+            writer.close("}");
+        }
+        other => writer.line(&format!(
+            "return Err(FlowError::new(\"RUNTIME:UNSUPPORTED\", {:?}));",
+            format!("Component '{}' ({}) is not supported by the runtime", component.name, other)
+        )),
+    }
+    Ok(())
+}
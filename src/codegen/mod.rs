@@ -0,0 +1,495 @@
+// Generates a standalone Cargo project from the flows of a project, for deployments without
+// the editor. The output is a service crate with one `async fn` per flow and an HTTP entry
+// point, plus a `support` crate built from the same sources the built-in runtime uses.
+pub(crate) mod flows;
+pub(crate) mod writer;
+
+use std::collections::{BTreeMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+use crate::base_configuration::ComponentSnapshot;
+use crate::codegen::writer::CodeWriter;
+use crate::runtime::http::listener_address;
+use crate::runtime::scheduler::schedule_of;
+use crate::support::{secure, DEPENDENCIES, SHARED_SOURCES};
+
+pub(crate) const SUPPORT_CRATE: &str = "integration-support";
+// Address of flows without a source when `LISTEN_ADDRESS` is not set
+const DEFAULT_ADDRESS: &str = "0.0.0.0:8081";
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CodegenError {
+    pub(crate) component: String,
+    pub(crate) message: String,
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.component, self.message)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct GeneratedFile {
+    pub(crate) path: String,
+    pub(crate) contents: String,
+}
+
+impl GeneratedFile {
+    fn new(path: &str, contents: String) -> Self {
+        Self { path: path.to_string(), contents }
+    }
+}
+
+// A flow served over HTTP, on the path and methods of its listener
+struct HttpRoute<'a> {
+    flow: &'a ComponentSnapshot,
+    function_name: &'a String,
+    path: String,
+    allowed_methods: String, // Comma separated; empty allows every method
+}
+
+// Turns a flow name into a unique snake_case function name
+pub(crate) fn function_name(name: &str, taken: &mut HashSet<String>) -> String {
+    let mut identifier = String::new();
+    for c in name.chars() {
+        if c.is_ascii_alphanumeric() {
+            if c.is_ascii_uppercase() && !identifier.is_empty() && !identifier.ends_with('_') {
+                identifier.push('_');
+            }
+            identifier.push(c.to_ascii_lowercase());
+        } else if !identifier.is_empty() && !identifier.ends_with('_') {
+            identifier.push('_');
+        }
+    }
+    let mut identifier = identifier.trim_end_matches('_').to_string();
+    if identifier.is_empty() || identifier.starts_with(|c: char| c.is_ascii_digit()) {
+        identifier = format!("flow_{}", identifier);
+    }
+    let mut unique = identifier.clone();
+    let mut counter = 2;
+    while !taken.insert(unique.clone()) {
+        unique = format!("{}_{}", identifier, counter);
+        counter += 1;
+    }
+    unique
+}
+
+// `secure_properties` is the encrypted secure property file of the selected environment, copied
+// to where the service reads it
+pub(crate) fn generate_project(
+    package_name: &str,
+    flows: &[ComponentSnapshot],
+    secure_properties: Option<&str>,
+) -> Result<Vec<GeneratedFile>, CodegenError> {
+    let mut taken = HashSet::new();
+    let function_names: Vec<String> = flows.iter().map(|flow| function_name(&flow.name, &mut taken)).collect();
+
+    let mut files = vec![
+        GeneratedFile::new("Cargo.toml", service_manifest(package_name)),
+        GeneratedFile::new("src/main.rs", service_main(flows, &function_names)?),
+        GeneratedFile::new("src/flows.rs", flows::generate_flows(flows, &function_names)?),
+        GeneratedFile::new("support/Cargo.toml", support_manifest()),
+        GeneratedFile::new("support/src/lib.rs", support_lib()),
+    ];
+    for (file_name, contents) in SHARED_SOURCES {
+        files.push(GeneratedFile::new(&format!("support/src/{}", file_name), contents.to_string()));
+    }
+    if let Some(secure_properties) = secure_properties {
+        files.push(GeneratedFile::new(secure::DEFAULT_FILE, secure_properties.to_string()));
+    }
+    Ok(files)
+}
+
+pub(crate) fn write_project(output_folder: &Path, files: &[GeneratedFile]) -> io::Result<()> {
+    for file in files {
+        let path = output_folder.join(&file.path);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        fs::write(path, &file.contents)?;
+    }
+    Ok(())
+}
+
+fn service_manifest(package_name: &str) -> String {
+    format!(
+        "[package]\nname = {:?}\nversion = \"0.1.0\"\nedition = \"2021\"\npublish = false\n\n\
+         [dependencies]\n{} = {{ path = \"support\" }}\nserde_json = \"1\"\n\n\
+         # Secure property keys take many PBKDF2 rounds, too slow unoptimized\n[profile.dev.package.sha2]\nopt-level = 3\n\n\
+         # Keep the generated project independent of any surrounding workspace\n[workspace]\n",
+        package_name, SUPPORT_CRATE
+    )
+}
+
+fn support_manifest() -> String {
+    let mut manifest = format!(
+        "[package]\nname = {:?}\nversion = \"0.1.0\"\nedition = \"2021\"\npublish = false\n\n[dependencies]\n",
+        SUPPORT_CRATE
+    );
+    for (name, requirement) in DEPENDENCIES {
+        if requirement.starts_with('{') {
+            manifest.push_str(&format!("{} = {}\n", name, requirement));
+        } else {
+            manifest.push_str(&format!("{} = {:?}\n", name, requirement));
+        }
+    }
+    manifest
+}
+
+fn support_lib() -> String {
+    let mut writer = CodeWriter::new();
+    writer.line("// Generated by Integration Builder: runtime support shared with the editor's built-in runtime.");
+    for (file_name, _) in SHARED_SOURCES {
+        writer.line(&format!("pub mod {};", file_name.trim_end_matches(".rs")));
+    }
+    writer.line("");
+    writer.line("pub use message::{FlowError, Message};");
+    writer.finish()
+}
+
+fn port_of(address: &str) -> &str {
+    address.rsplit(':').next().unwrap_or_default()
+}
+
+// Service entry point: one server per listener address and one thread per scheduler, file
+// watcher or queue listener. Flows with an HTTP Listener are served on its host, port and path;
+// flows with a Scheduler run on its timer; flows with On New File run for each new file in its
+// directory; flows with a Queue Listener run for each message of its queue or topic; other flows
+// are served under `/<function name>` on `LISTEN_ADDRESS`, or else on the default address, together
+// with the listeners bound to exactly that address. A listener on the default port with another host
+// is an error, as the two servers would compete for one socket. Sub-flows are not served.
+fn service_main(flows: &[ComponentSnapshot], function_names: &[String]) -> Result<String, CodegenError> {
+    // Listener address, None for flows without a source, and the routes it serves
+    let mut servers: BTreeMap<Option<String>, Vec<HttpRoute>> = BTreeMap::new();
+    let mut schedulers = Vec::new();
+    let mut watchers = Vec::new();
+    let mut queue_listeners = Vec::new();
+    for (flow, function_name) in flows.iter().zip(function_names) {
+        match flow.source() {
+            Some(listener) if listener.widget_type == "HttpListener" => servers.entry(Some(listener_address(listener))).or_default().push(HttpRoute {
+                flow,
+                function_name,
+                path: listener.get_property("path").to_string(),
+                allowed_methods: listener.get_property("allowed_methods").to_string(),
+            }),
+            Some(scheduler) if scheduler.widget_type == "Scheduler" => {
+                schedule_of(&scheduler.properties).map_err(|message| CodegenError {
+                    component: scheduler.name.clone(),
+                    message,
+                })?;
+                if !flow.is_initially_stopped() {
+                    schedulers.push((flow, function_name, scheduler));
+                }
+            }
+            Some(watcher) if watcher.widget_type == "OnNewFile" => {
+                if !flow.is_initially_stopped() {
+                    watchers.push((flow, function_name, watcher));
+                }
+            }
+            Some(listener) if listener.widget_type == "QueueListener" => {
+                if !flow.is_initially_stopped() {
+                    queue_listeners.push((flow, function_name, listener));
+                }
+            }
+            _ if flow.widget_type == "SubFlow" => {}
+            _ => servers.entry(None).or_default().push(HttpRoute { flow, function_name, path: format!("/{}", function_name), allowed_methods: String::new() }),
+        }
+    }
+    // Flows without a source share a listener bound to exactly the default address, unless
+    // `LISTEN_ADDRESS` moves them; any other listener on the default port would compete for it
+    let default_address = Some(DEFAULT_ADDRESS.to_string());
+    let shared = servers.contains_key(&None) && servers.contains_key(&default_address);
+    if servers.contains_key(&None) {
+        let conflict = servers.iter()
+            .find(|(address, _)| address.as_deref().is_some_and(|address| address != DEFAULT_ADDRESS && port_of(address) == port_of(DEFAULT_ADDRESS)));
+        if let Some((Some(address), routes)) = conflict {
+            return Err(CodegenError {
+                component: routes[0].flow.name.clone(),
+                message: format!(
+                    "its HTTP Listener binds {}, the port of {} where flows without a source are served; use another port or {}",
+                    address, DEFAULT_ADDRESS, DEFAULT_ADDRESS
+                ),
+            });
+        }
+    }
+
+    let mut writer = CodeWriter::new();
+    writer.line("// Generated by Integration Builder. Changes are overwritten on the next generation.");
+    writer.line("mod flows;");
+    writer.line("");
+    if !schedulers.is_empty() || !watchers.is_empty() || !queue_listeners.is_empty() {
+        writer.line("use std::sync::atomic::AtomicBool;");
+        writer.line("use integration_support::Message;");
+    }
+    if !schedulers.is_empty() {
+        writer.line("use std::sync::Arc;");
+        writer.line("use integration_support::scheduler::{run_schedule, OverlapPolicy, RunLimiter, Schedule};");
+    }
+    if !watchers.is_empty() {
+        writer.line("use std::time::Duration;");
+        writer.line("use integration_support::files::{self, run_watcher, FileWatcher};");
+    }
+    if !queue_listeners.is_empty() {
+        writer.line("use integration_support::broker::{self, Destination, ListenerSettings};");
+        writer.line("use integration_support::FlowError;");
+    }
+    if !servers.is_empty() {
+        writer.line("use integration_support::http::{self, HttpRequest, HttpResponse, HttpServer};");
+    }
+    writer.line("use integration_support::executor;");
+    writer.line("");
+    writer.open("fn main() {");
+    writer.line("let mut workers = Vec::new();");
+    for (index, address) in servers.keys().enumerate() {
+        let address = match address {
+            _ if shared && (address.is_none() || *address == default_address) => continue,
+            Some(address) => format!("{:?}.to_string()", address),
+            None => format!("std::env::var(\"LISTEN_ADDRESS\").unwrap_or_else(|_| {:?}.to_string())", DEFAULT_ADDRESS),
+        };
+        writer.line(&format!("workers.push(std::thread::spawn(|| listen({}, handle_{})));", address, index));
+    }
+    if shared {
+        let listener_index = servers.keys().position(|address| *address == default_address).unwrap_or_default();
+        writer.line(&format!("// Flows without a source are served with the HTTP Listeners on {} unless LISTEN_ADDRESS moves them", DEFAULT_ADDRESS));
+        writer.open(&format!("match std::env::var(\"LISTEN_ADDRESS\").ok().filter(|address| address != {:?}) {{", DEFAULT_ADDRESS));
+        writer.open("Some(address) => {");
+        writer.line("workers.push(std::thread::spawn(move || listen(address, handle_0)));");
+        writer.line(&format!("workers.push(std::thread::spawn(|| listen({:?}.to_string(), handle_{})));", DEFAULT_ADDRESS, listener_index));
+        writer.close("}");
+        writer.line(&format!("None => workers.push(std::thread::spawn(|| listen({:?}.to_string(), handle_shared))),", DEFAULT_ADDRESS));
+        writer.close("}");
+    }
+    for (flow, function_name, scheduler) in &schedulers {
+        writer.line(&format!("// Scheduler of flow '{}'", flow.name));
+        let schedule = if scheduler.get_property("mode") == "Cron" {
+            format!("Schedule::cron({:?}, {:?})", scheduler.get_property("expression"), scheduler.get_property("timezone"))
+        } else {
+            format!(
+                "Schedule::fixed_frequency({:?}, {:?}, {:?})",
+                scheduler.get_property("frequency"),
+                scheduler.get_property("start_delay"),
+                scheduler.get_property("time_unit")
+            )
+        };
+        writer.line(&format!(
+            "workers.push(std::thread::spawn(|| schedule({:?}, {}.unwrap(), {}, OverlapPolicy::from_name({:?}), run_{})));",
+            flow.name,
+            schedule,
+            flow.max_concurrency.max(1),
+            scheduler.get_property("overlap"),
+            function_name
+        ));
+    }
+    for (flow, function_name, watcher) in &watchers {
+        writer.line(&format!("// File watcher of flow '{}'", flow.name));
+        writer.line(&format!(
+            "let watcher = FileWatcher::new(files::resolve_path(&files::project_folder(), {:?}), {:?});",
+            watcher.get_property("directory"),
+            watcher.get_property("pattern")
+        ));
+        writer.line(&format!(
+            "workers.push(std::thread::spawn(move || watch({:?}, watcher, {}, run_{})));",
+            flow.name,
+            watcher.get_property("polling_frequency").trim().parse::<u64>().unwrap_or(1000),
+            function_name
+        ));
+    }
+    for (flow, function_name, listener) in &queue_listeners {
+        writer.line(&format!("// Queue listener of flow '{}'", flow.name));
+        writer.line(&format!(
+            "let destination = Destination::new({:?}, {:?}).unwrap();",
+            listener.get_property("destination_type"),
+            listener.get_property("destination")
+        ));
+        writer.line(&format!(
+            "let settings = ListenerSettings::new({:?}, {}, {:?});",
+            listener.get_property("ack_mode"),
+            listener.get_property("max_redelivery").trim().parse::<i64>().unwrap_or(3),
+            listener.get_property("dead_letter_queue")
+        ));
+        writer.line(&format!(
+            "workers.push(consume_queue({:?}, {:?}, {:?}, destination, settings, run_{}));",
+            flow.name,
+            listener.get_property("config"),
+            listener.get_property("broker"),
+            function_name
+        ));
+    }
+    writer.open("for worker in workers {");
+    writer.line("worker.join().ok();");
+    writer.close("}");
+    writer.close("}");
+
+    if !servers.is_empty() {
+        writer.line("");
+        writer.open("fn listen(address: String, handler: fn(HttpRequest) -> HttpResponse) {");
+        writer.line("let server = HttpServer::bind(&address).expect(\"Cannot bind the HTTP listener\");");
+        writer.line("println!(\"Listening on {}\", address);");
+        writer.line("server.serve(handler).expect(\"HTTP listener failed\");");
+        writer.close("}");
+    }
+
+    if !schedulers.is_empty() {
+        writer.line("");
+        writer.open("fn schedule(flow: &'static str, schedule: Schedule, max_concurrency: usize, policy: OverlapPolicy, run: fn()) {");
+        writer.line("let limiter = RunLimiter::new(max_concurrency, policy);");
+        writer.line("let job: Arc<dyn Fn() + Send + Sync> = Arc::new(run);");
+        writer.line("println!(\"Scheduling flow '{}'\", flow);");
+        writer.open("run_schedule(&schedule, &AtomicBool::new(true), || {");
+        writer.open("if !limiter.submit(job.clone()) {");
+        writer.line("println!(\"Skipped a scheduled run of '{}', max concurrency reached\", flow);");
+        writer.close("}");
+        writer.close("});");
+        writer.close("}");
+    }
+    if !watchers.is_empty() {
+        writer.line("");
+        writer.open("fn watch(flow: &'static str, mut watcher: FileWatcher, interval_ms: u64, run: fn(Message)) {");
+        writer.line("println!(\"Watching {} for flow '{}'\", watcher.directory().display(), flow);");
+        writer.open("run_watcher(&mut watcher, Duration::from_millis(interval_ms), &AtomicBool::new(true), || true, |path| {");
+        writer.open("match files::read_message(&path) {");
+        writer.line("Ok(message) => run(message),");
+        writer.line("Err(error) => println!(\"Cannot read {}: {}\", path.display(), error),");
+        writer.close("}");
+        writer.close("});");
+        writer.close("}");
+    }
+    if !queue_listeners.is_empty() {
+        writer.line("");
+        writer.line("// Subscribes before returning, so topic messages published by other flows from now on are kept");
+        writer.open("fn consume_queue(");
+        writer.line("flow: &'static str,");
+        writer.line("config: &'static str,");
+        writer.line("kind: &'static str,");
+        writer.line("destination: Destination,");
+        writer.line("settings: ListenerSettings,");
+        writer.line("run: fn(Message) -> Result<Message, FlowError>,");
+        writer.reopen(") -> std::thread::JoinHandle<()> {");
+        writer.line("let broker = broker::shared_broker(config, kind).expect(\"Cannot create the broker\");");
+        writer.line("let queue = broker::listener_queue(&*broker, &destination, flow).expect(\"Cannot subscribe\");");
+        writer.line("println!(\"Receiving from {} for flow '{}'\", queue, flow);");
+        writer.open("std::thread::spawn(move || {");
+        writer.open("broker::run_listener(&*broker, &queue, &settings, &AtomicBool::new(true), || true, run, |_, error| {");
+        writer.line("println!(\"{}: {}\", flow, error);");
+        writer.close("});");
+        writer.close("})");
+        writer.close("}");
+    }
+    for (_, function_name, _) in &queue_listeners {
+        writer.line("");
+        writer.open(&format!("fn run_{}(message: Message) -> Result<Message, FlowError> {{", function_name));
+        writer.line(&format!("executor::block_on(flows::{}(message))", function_name));
+        writer.close("}");
+    }
+    for (flow, function_name, _) in &watchers {
+        writer.line("");
+        writer.open(&format!("fn run_{}(message: Message) {{", function_name));
+        writer.open(&format!("if let Err(error) = executor::block_on(flows::{}(message)) {{", function_name));
+        writer.line(&format!("println!(\"Run of '{{}}' failed: {{}}\", {:?}, error);", flow.name));
+        writer.close("}");
+        writer.close("}");
+    }
+    for (flow, function_name, _) in &schedulers {
+        writer.line("");
+        writer.open(&format!("fn run_{}() {{", function_name));
+        writer.open(&format!("if let Err(error) = executor::block_on(flows::{}(Message::default())) {{", function_name));
+        writer.line(&format!("println!(\"Scheduled run of '{{}}' failed: {{}}\", {:?}, error);", flow.name));
+        writer.close("}");
+        writer.close("}");
+    }
+
+    for (index, routes) in servers.values().enumerate() {
+        writer.line("");
+        writer.open(&format!("fn route_{}(request: &HttpRequest) -> Option<HttpResponse> {{", index));
+        for HttpRoute { flow, function_name, path, allowed_methods } in routes {
+            writer.line(&format!("// Flow '{}'", flow.name));
+            writer.open(&format!("if http::path_matches({:?}, &request.path) {{", path));
+            writer.open(&format!("if !http::method_allowed({:?}, &request.method) {{", allowed_methods));
+            writer.line("return Some(HttpResponse::new(405, \"text/plain\", b\"Method not allowed\".to_vec()));");
+            writer.close("}");
+            writer.line(&format!("let result = executor::block_on(flows::{}(http::message_from_request(request)));", function_name));
+            writer.line(&format!(
+                "let response = result.and_then(|msg| flows::{}_response(&msg).map(|(status, headers)| http::response_from_message(&msg, &status, &headers)));",
+                function_name
+            ));
+            writer.line("return Some(response.unwrap_or_else(|error| http::error_response(&error)));");
+            writer.close("}");
+        }
+        writer.line("None");
+        writer.close("}");
+        writer.line("");
+        writer.open(&format!("fn handle_{}(request: HttpRequest) -> HttpResponse {{", index));
+        writer.line(&format!("route_{}(&request).unwrap_or_else(not_found)", index));
+        writer.close("}");
+    }
+    if shared {
+        let listener_index = servers.keys().position(|address| *address == default_address).unwrap_or_default();
+        writer.line("");
+        writer.line("// Flows without a source go first so a listener path such as `/*` does not hide them");
+        writer.open("fn handle_shared(request: HttpRequest) -> HttpResponse {");
+        writer.line(&format!("route_0(&request).or_else(|| route_{}(&request)).unwrap_or_else(not_found)", listener_index));
+        writer.close("}");
+    }
+    if !servers.is_empty() {
+        writer.line("");
+        writer.open("fn not_found() -> HttpResponse {");
+        writer.line("HttpResponse::new(404, \"text/plain\", b\"No listener for this path\".to_vec())");
+        writer.close("}");
+    }
+    Ok(writer.finish())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::base_configuration::BaseConfiguration;
+    use crate::components::http_listener::http_listener;
+
+    fn flow(name: &str, source: Option<BaseConfiguration>) -> ComponentSnapshot {
+        let mut flow = BaseConfiguration::default().widget_type("MainFlow".to_string()).name(name.to_string()).snapshot();
+        flow.children.extend(source.map(|source| source.snapshot()));
+        flow
+    }
+
+    fn listener(host: &str) -> BaseConfiguration {
+        let mut listener = http_listener::new_config();
+        listener.properties.insert("host".to_string(), host.to_string());
+        listener
+    }
+
+    #[test]
+    fn plain_flows_share_the_listener_on_the_default_address() {
+        let flows = vec![flow("Orders", Some(listener("0.0.0.0"))), flow("Audit", None)];
+        let function_names = vec!["orders".to_string(), "audit".to_string()];
+        let main = service_main(&flows, &function_names).unwrap();
+        // One server, unless LISTEN_ADDRESS moves the plain flows to a second one
+        assert!(main.contains("None => workers.push(std::thread::spawn(|| listen(\"0.0.0.0:8081\".to_string(), handle_shared)))"), "{}", main);
+        assert!(main.contains("listen(address, handle_0)") && main.contains("listen(\"0.0.0.0:8081\".to_string(), handle_1)"));
+        assert!(main.contains("route_0(&request).or_else(|| route_1(&request))"));
+        assert!(main.contains("path_matches(\"/audit\"") && main.contains("path_matches(\"/\""));
+    }
+
+    #[test]
+    fn a_listener_on_the_default_port_with_another_host_is_a_conflict() {
+        let flows = vec![flow("Orders", Some(listener("127.0.0.1"))), flow("Audit", None)];
+        let function_names = vec!["orders".to_string(), "audit".to_string()];
+        let error = service_main(&flows, &function_names).unwrap_err();
+        assert_eq!(error.component, "Orders");
+        assert!(error.message.contains("127.0.0.1:8081"), "{}", error);
+    }
+
+    #[test]
+    fn listeners_on_other_ports_are_served_apart() {
+        let mut other = listener("127.0.0.1");
+        other.properties.insert("port".to_string(), "9090".to_string());
+        let flows = vec![flow("Orders", Some(other)), flow("Audit", None)];
+        let function_names = vec!["orders".to_string(), "audit".to_string()];
+        let main = service_main(&flows, &function_names).unwrap();
+        assert!(main.contains("std::env::var(\"LISTEN_ADDRESS\").unwrap_or_else(|_| \"0.0.0.0:8081\".to_string()), handle_0)"), "{}", main);
+        assert!(main.contains("listen(\"127.0.0.1:9090\".to_string(), handle_1)"));
+        assert!(!main.contains("handle_shared"));
+    }
+}
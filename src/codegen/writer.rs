@@ -0,0 +1,44 @@
+// Accumulates generated source with consistent indentation
+pub(crate) struct CodeWriter {
+    out: String,
+    indent: usize,
+}
+
+impl CodeWriter {
+    pub(crate) fn new() -> Self {
+        Self { out: String::new(), indent: 0 }
+    }
+
+    pub(crate) fn line(&mut self, line: &str) {
+        if line.is_empty() {
+            self.out.push('\n');
+            return;
+        }
+        for _ in 0..self.indent {
+            self.out.push_str("    ");
+        }
+        self.out.push_str(line);
+        self.out.push('\n');
+    }
+
+    // Writes a line ending in `{` and indents what follows
+    pub(crate) fn open(&mut self, line: &str) {
+        self.line(line);
+        self.indent += 1;
+    }
+
+    // Dedents and writes a closing line such as `}` or `} else {`
+    pub(crate) fn close(&mut self, line: &str) {
+        self.indent = self.indent.saturating_sub(1);
+        self.line(line);
+    }
+
+    pub(crate) fn reopen(&mut self, line: &str) {
+        self.close(line);
+        self.indent += 1;
+    }
+
+    pub(crate) fn finish(self) -> String {
+        self.out
+    }
+}
@@ -0,0 +1,18 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Scope that runs its components on a separate task with a copy of the message, while the flow
+// carries on at once with the message unchanged. Errors inside stay inside; at most
+// `max_concurrency` copies run at a time and the rest wait their turn.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("max_concurrency", "Max concurrency", PropertyKind::Number, "4"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Async".to_string())
+        .name("Async".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true);
+    with_defaults(config, PROPERTIES)
+}
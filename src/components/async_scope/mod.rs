@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod async_scope;
\ No newline at end of file
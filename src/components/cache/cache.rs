@@ -0,0 +1,43 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::support::object_store::PERSISTENCE;
+
+// Scope that keeps the result of its components per key. When the key is in the object store,
+// the stored payload and MIME type replace the message's and the components are skipped;
+// otherwise they run and their result is stored. Variables they set are not cached.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("key", "Key", PropertyKind::Expression, "payload"),
+    OBJECT_STORE,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Cache".to_string())
+        .name("Cache".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true);
+    with_defaults(config, PROPERTIES)
+}
+
+// Lets a component keep its entries in a shared object store; without one it keeps its own,
+// in memory and without expiry
+pub(crate) const OBJECT_STORE: PropertyField =
+    PropertyField::new("object_store", "Object store", PropertyKind::GlobalRef("ObjectStore"), "");
+
+// Global element holding keyed entries for Cache scopes and Idempotent Message Validators. A time
+// to live or maximum of 0 means no limit; a file-backed store without a file keeps its entries in
+// `data/object-stores/<name>.json` of the project.
+pub(crate) const STORE_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("store_persistence", "Persistence", PropertyKind::Options(PERSISTENCE), "InMemory"),
+    PropertyField::new("store_ttl", "Entry time to live (ms)", PropertyKind::Number, "0"),
+    PropertyField::new("store_max_entries", "Max entries", PropertyKind::Number, "1000"),
+    PropertyField::new("store_file", "File", PropertyKind::Text, ""),
+];
+
+pub(crate) fn new_object_store(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("ObjectStore".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, STORE_PROPERTIES)
+}
@@ -0,0 +1,28 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+
+// A Choice routes the message to the first `When` branch whose expression holds,
+// falling back to the `Otherwise` branch. Each branch is a lane holding its own components.
+pub(crate) fn new_config() -> BaseConfiguration {
+    BaseConfiguration::default()
+        .widget_type("Choice".to_string())
+        .name("Choice".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true)
+        .child(new_when("true"))
+        .child(new_otherwise())
+}
+
+pub(crate) fn new_when(expression: &str) -> BaseConfiguration {
+    BaseConfiguration::default()
+        .widget_type("When".to_string())
+        .name("When".to_string())
+        .component_level(ComponentLevel::Other)
+        .property("expression", expression)
+}
+
+pub(crate) fn new_otherwise() -> BaseConfiguration {
+    BaseConfiguration::default()
+        .widget_type("Otherwise".to_string())
+        .name("Otherwise".to_string())
+        .component_level(ComponentLevel::Other)
+}
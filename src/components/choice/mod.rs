@@ -1 +1,2 @@
+pub(crate) mod choice;
 pub(crate) mod image;
\ No newline at end of file
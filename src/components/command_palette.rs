@@ -0,0 +1,359 @@
+use std::collections::BTreeMap;
+use egui::{Align2, Color32, Context, Key, KeyboardShortcut, Modifiers, RichText, ScrollArea, vec2};
+
+// Something the editor can do from the command palette or a keyboard shortcut
+#[derive(Clone, PartialEq)]
+pub(crate) enum Command {
+    ShowPalette,
+    KeyboardShortcuts,
+    Find,
+    Save,
+    SaveAll,
+    Undo,
+    Redo,
+    ToggleSplit,
+    Run,
+    Stop,
+    StartFlow(String), // Flow name
+    StopFlow(String),
+    GenerateRustProject,
+    ToggleLeftPanel,
+    ToggleRightPanel,
+    ToggleBottomPanel,
+    ToggleMinimap,
+    ResetLayout,
+    NewFlow,
+    NewSubFlow,
+    OpenFlow(String, String), // uuid, name
+    AddComponent(String), // Palette name, inserted after the selected component
+    DeleteSelection,
+}
+
+impl Command {
+    // Key of the command in the shortcut settings
+    pub(crate) fn id(&self) -> String {
+        match self {
+            Command::ShowPalette => "command_palette".to_string(),
+            Command::KeyboardShortcuts => "keyboard_shortcuts".to_string(),
+            Command::Find => "find".to_string(),
+            Command::Save => "save".to_string(),
+            Command::SaveAll => "save_all".to_string(),
+            Command::Undo => "undo".to_string(),
+            Command::Redo => "redo".to_string(),
+            Command::ToggleSplit => "toggle_split".to_string(),
+            Command::Run => "run".to_string(),
+            Command::Stop => "stop".to_string(),
+            Command::StartFlow(name) => format!("start:{}", name),
+            Command::StopFlow(name) => format!("stop:{}", name),
+            Command::GenerateRustProject => "generate_rust_project".to_string(),
+            Command::ToggleLeftPanel => "toggle_left_panel".to_string(),
+            Command::ToggleRightPanel => "toggle_right_panel".to_string(),
+            Command::ToggleBottomPanel => "toggle_bottom_panel".to_string(),
+            Command::ToggleMinimap => "toggle_minimap".to_string(),
+            Command::ResetLayout => "reset_layout".to_string(),
+            Command::NewFlow => "new_flow".to_string(),
+            Command::NewSubFlow => "new_sub_flow".to_string(),
+            Command::OpenFlow(uuid, _) => format!("open:{}", uuid),
+            Command::AddComponent(name) => format!("add:{}", name),
+            Command::DeleteSelection => "delete_selection".to_string(),
+        }
+    }
+
+    pub(crate) fn label(&self) -> String {
+        match self {
+            Command::ShowPalette => "Show all commands".to_string(),
+            Command::KeyboardShortcuts => "Keyboard shortcuts".to_string(),
+            Command::Find => "Find and replace in the project".to_string(),
+            Command::Save => "Save".to_string(),
+            Command::SaveAll => "Save all".to_string(),
+            Command::Undo => "Undo".to_string(),
+            Command::Redo => "Redo".to_string(),
+            Command::ToggleSplit => "Toggle split view".to_string(),
+            Command::Run => "Run flows".to_string(),
+            Command::Stop => "Stop running flows".to_string(),
+            Command::StartFlow(name) => format!("Start flow: {}", name),
+            Command::StopFlow(name) => format!("Stop flow: {}", name),
+            Command::GenerateRustProject => "Export: generate Rust project".to_string(),
+            Command::ToggleLeftPanel => "Toggle palette and project explorer".to_string(),
+            Command::ToggleRightPanel => "Toggle inspector".to_string(),
+            Command::ToggleBottomPanel => "Toggle log, runs and object stores".to_string(),
+            Command::ToggleMinimap => "Toggle minimap".to_string(),
+            Command::ResetLayout => "Reset layout and preferences".to_string(),
+            Command::NewFlow => "New flow".to_string(),
+            Command::NewSubFlow => "New sub-flow".to_string(),
+            Command::OpenFlow(_, name) => format!("Open flow: {}", name),
+            Command::AddComponent(name) => format!("Add component: {}", name),
+            Command::DeleteSelection => "Delete selected component".to_string(),
+        }
+    }
+
+    // Commands about one flow come and go with it and take no shortcut
+    pub(crate) fn is_bindable(&self) -> bool {
+        !matches!(self, Command::StartFlow(_) | Command::StopFlow(_) | Command::OpenFlow(..))
+    }
+
+    // Commands that edit the canvas or undo; text fields handle these keys themselves
+    fn is_editing(&self) -> bool {
+        matches!(self, Command::Undo | Command::Redo | Command::DeleteSelection)
+    }
+}
+
+pub(crate) fn default_shortcut(command: &Command) -> Option<KeyboardShortcut> {
+    let shortcut = |modifiers, key| Some(KeyboardShortcut::new(modifiers, key));
+    match command {
+        Command::ShowPalette => shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::P),
+        Command::Find => shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::F),
+        Command::Save => shortcut(Modifiers::COMMAND, Key::S),
+        Command::SaveAll => shortcut(Modifiers::COMMAND | Modifiers::SHIFT, Key::S),
+        Command::Undo => shortcut(Modifiers::COMMAND, Key::Z),
+        Command::Redo => shortcut(Modifiers::COMMAND, Key::Y),
+        Command::Run => shortcut(Modifiers::NONE, Key::F5),
+        Command::Stop => shortcut(Modifiers::SHIFT, Key::F5),
+        Command::DeleteSelection => shortcut(Modifiers::NONE, Key::Delete),
+        _ => None,
+    }
+}
+
+// Shortcuts as written in the settings, like "Ctrl+Shift+P"; Ctrl is Cmd on macOS
+pub(crate) fn format_shortcut(shortcut: &KeyboardShortcut) -> String {
+    let mut parts = Vec::new();
+    if shortcut.modifiers.command || shortcut.modifiers.ctrl {
+        parts.push("Ctrl");
+    }
+    if shortcut.modifiers.alt {
+        parts.push("Alt");
+    }
+    if shortcut.modifiers.shift {
+        parts.push("Shift");
+    }
+    parts.push(shortcut.logical_key.name());
+    parts.join("+")
+}
+
+pub(crate) fn parse_shortcut(text: &str) -> Option<KeyboardShortcut> {
+    let text = text.trim();
+    // The last part is the key, which may itself be "+"
+    let (modifiers_text, key) = match text.rsplit_once('+') {
+        Some((rest, "")) => (rest.strip_suffix('+').unwrap_or(rest), "+"),
+        Some((rest, key)) => (rest, key),
+        None => ("", text),
+    };
+    let mut modifiers = Modifiers::NONE;
+    for part in modifiers_text.split('+').map(str::trim).filter(|part| !part.is_empty()) {
+        match part.to_ascii_lowercase().as_str() {
+            "ctrl" | "cmd" | "command" => modifiers = modifiers | Modifiers::COMMAND,
+            "shift" => modifiers = modifiers | Modifiers::SHIFT,
+            "alt" | "option" => modifiers = modifiers | Modifiers::ALT,
+            _ => return None,
+        }
+    }
+    let key = Key::from_name(key.trim()).or_else(|| Key::from_name(&key.trim().to_ascii_uppercase()))?;
+    Some(KeyboardShortcut::new(modifiers, key))
+}
+
+// The shortcut of a command: its setting if there is one (empty for none), else its default
+pub(crate) fn shortcut(command: &Command, settings: &BTreeMap<String, String>) -> Option<KeyboardShortcut> {
+    match settings.get(&command.id()) {
+        Some(text) => parse_shortcut(text),
+        None => default_shortcut(command),
+    }
+}
+
+// Command whose shortcut was pressed this frame. Shortcuts with more modifiers are tried first,
+// so Ctrl+Shift+S does not also save; while a text field has focus only Ctrl shortcuts of
+// commands that do not edit apply.
+pub(crate) fn pressed_command(ctx: &Context, commands: &[Command], settings: &BTreeMap<String, String>) -> Option<Command> {
+    let text_focus = ctx.memory(|memory| memory.focused().is_some());
+    let mut bindings: Vec<(KeyboardShortcut, &Command)> = commands.iter()
+        .filter(|command| command.is_bindable())
+        .filter(|command| !text_focus || !command.is_editing())
+        .filter_map(|command| shortcut(command, settings).map(|shortcut| (shortcut, command)))
+        .filter(|(shortcut, _)| !text_focus || shortcut.modifiers.command)
+        .collect();
+    let modifier_count = |modifiers: &Modifiers| [modifiers.command, modifiers.shift, modifiers.alt].iter().filter(|set| **set).count();
+    bindings.sort_by_key(|(shortcut, _)| std::cmp::Reverse(modifier_count(&shortcut.modifiers)));
+    bindings.into_iter()
+        .find(|(shortcut, _)| ctx.input_mut(|input| input.consume_shortcut(shortcut)))
+        .map(|(_, command)| command.clone())
+}
+
+// Score of `text` for a query whose characters appear in it in order, case insensitive; runs
+// of consecutive characters and matches at word starts score higher
+pub(crate) fn fuzzy_score(query: &str, text: &str) -> Option<i32> {
+    let text: Vec<char> = text.to_lowercase().chars().collect();
+    let mut score = 0;
+    let mut position = 0;
+    let mut previous: Option<usize> = None;
+    for wanted in query.to_lowercase().chars().filter(|c| !c.is_whitespace()) {
+        let found = (position..text.len()).find(|&index| text[index] == wanted)?;
+        score += 1;
+        if previous.is_some_and(|previous| previous + 1 == found) {
+            score += 5;
+        }
+        if found == 0 || !text[found - 1].is_alphanumeric() {
+            score += 3;
+        }
+        score -= (found - position).min(10) as i32;
+        previous = Some(found);
+        position = found + 1;
+    }
+    Some(score)
+}
+
+// Command palette state: open with every command, or with the components to insert only
+#[derive(Default)]
+pub(crate) struct CommandPalette {
+    pub(crate) open: bool,
+    query: String,
+    highlighted: usize,
+    insert_only: bool,
+}
+
+impl CommandPalette {
+    pub(crate) fn show_all(&mut self) {
+        *self = Self { open: true, ..Self::default() };
+    }
+
+    // Opens on the components to insert after the selection, with the first typed characters
+    pub(crate) fn show_insert(&mut self, typed: String) {
+        *self = Self { open: true, query: typed, highlighted: 0, insert_only: true };
+    }
+}
+
+// Palette with fuzzy search over the commands; arrows pick, Enter runs and Escape closes
+pub(crate) fn show_command_palette(
+    ctx: &Context,
+    palette: &mut CommandPalette,
+    commands: &[Command],
+    settings: &BTreeMap<String, String>,
+) -> Option<Command> {
+    if !palette.open {
+        return None;
+    }
+    let mut matches: Vec<(i32, &Command)> = commands.iter()
+        .filter(|command| !palette.insert_only || matches!(command, Command::AddComponent(_)))
+        .filter_map(|command| fuzzy_score(&palette.query, &command.label()).map(|score| (score, command)))
+        .collect();
+    // Stable sort: equal scores keep the order of the command list
+    matches.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+
+    let (up, down, enter, escape) = ctx.input_mut(|input| (
+        input.consume_key(Modifiers::NONE, Key::ArrowUp),
+        input.consume_key(Modifiers::NONE, Key::ArrowDown),
+        input.consume_key(Modifiers::NONE, Key::Enter),
+        input.consume_key(Modifiers::NONE, Key::Escape),
+    ));
+    if escape {
+        palette.open = false;
+        return None;
+    }
+    if down {
+        palette.highlighted += 1;
+    }
+    if up {
+        palette.highlighted = palette.highlighted.saturating_sub(1);
+    }
+    palette.highlighted = palette.highlighted.min(matches.len().saturating_sub(1));
+
+    let mut chosen = None;
+    let title = if palette.insert_only { "Insert component" } else { "Commands" };
+    egui::Window::new(title)
+        .collapsible(false)
+        .resizable(false)
+        .title_bar(false)
+        .fixed_size(vec2(420.0, 0.0))
+        .anchor(Align2::CENTER_TOP, vec2(0.0, 60.0))
+        .show(ctx, |ui| {
+            let hint = if palette.insert_only { "Component to insert after the selection" } else { "Type a command" };
+            let response = ui.add(egui::TextEdit::singleline(&mut palette.query).hint_text(hint).desired_width(f32::INFINITY));
+            response.request_focus();
+            if response.changed() {
+                palette.highlighted = 0;
+            }
+            ui.separator();
+            if matches.is_empty() {
+                ui.label(RichText::new("No matching command").small().weak());
+            }
+            ScrollArea::vertical().max_height(320.0).show(ui, |ui| {
+                for (index, (_, command)) in matches.iter().enumerate() {
+                    let highlighted = index == palette.highlighted;
+                    let row = ui.horizontal(|ui| {
+                        let label = ui.selectable_label(highlighted, command.label());
+                        if let Some(shortcut) = shortcut(command, settings) {
+                            ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                ui.label(RichText::new(format_shortcut(&shortcut)).small().weak());
+                            });
+                        }
+                        label
+                    });
+                    if highlighted && (up || down) {
+                        row.response.scroll_to_me(None);
+                    }
+                    if row.inner.clicked() {
+                        chosen = Some((*command).clone());
+                    }
+                }
+            });
+        });
+    if enter {
+        chosen = matches.get(palette.highlighted).map(|(_, command)| (*command).clone());
+    }
+    if chosen.is_some() {
+        palette.open = false;
+    }
+    chosen
+}
+
+// Settings of the keyboard shortcuts: each command's shortcut, with conflicts marked
+pub(crate) fn show_shortcuts_window(ctx: &Context, open: &mut bool, commands: &[Command], settings: &mut BTreeMap<String, String>) {
+    let mut keep_open = *open;
+    egui::Window::new("Keyboard shortcuts")
+        .open(&mut keep_open)
+        .default_width(420.0)
+        .show(ctx, |ui| {
+            ui.label(RichText::new("Write shortcuts like Ctrl+Shift+P or F5; leave empty for none.").small().weak());
+            let bindable: Vec<&Command> = commands.iter().filter(|command| command.is_bindable()).collect();
+            let assigned: Vec<(String, Option<String>)> = bindable.iter()
+                .map(|command| (command.id(), shortcut(command, settings).map(|shortcut| format_shortcut(&shortcut))))
+                .collect();
+            ScrollArea::vertical().max_height(420.0).show(ui, |ui| {
+                egui::Grid::new("keyboard_shortcuts").num_columns(3).striped(true).show(ui, |ui| {
+                    for command in bindable {
+                        let id = command.id();
+                        ui.label(command.label());
+                        let default_text = default_shortcut(command).map(|shortcut| format_shortcut(&shortcut)).unwrap_or_default();
+                        let mut text = settings.get(&id).cloned().unwrap_or_else(|| default_text.clone());
+                        let valid = text.trim().is_empty() || parse_shortcut(&text).is_some();
+                        let current = shortcut(command, settings).map(|shortcut| format_shortcut(&shortcut));
+                        let conflict = current.is_some() && assigned.iter().any(|(other, formatted)| *other != id && *formatted == current);
+                        let mut edit = egui::TextEdit::singleline(&mut text).desired_width(120.0);
+                        if !valid || conflict {
+                            edit = edit.text_color(Color32::from_rgb(220, 60, 60));
+                        }
+                        let response = ui.add(edit);
+                        // Only shortcuts that differ from the default are stored
+                        if response.changed() {
+                            if text == default_text {
+                                settings.remove(&id);
+                            } else {
+                                settings.insert(id.clone(), text);
+                            }
+                        }
+                        if !valid {
+                            response.on_hover_text("Not a shortcut");
+                        } else if conflict {
+                            response.on_hover_text("Another command has this shortcut");
+                        }
+                        if settings.contains_key(&id) && ui.small_button("\u{27F2}").on_hover_text("Default shortcut").clicked() {
+                            settings.remove(&id);
+                        }
+                        ui.end_row();
+                    }
+                });
+            });
+            if ui.button("Reset all to defaults").clicked() {
+                settings.clear();
+            }
+        });
+    *open = keep_open;
+}
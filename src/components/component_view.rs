@@ -0,0 +1,283 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use egui::{Color32, Rect, RichText, Stroke, TextureHandle, Ui, vec2};
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::create_component;
+use crate::components::draggable_item::DraggableItem;
+use crate::components::variables::VariableKey;
+
+// Editor state the canvas reads while drawing and updates on interaction
+#[derive(Default)]
+pub struct CanvasState {
+    pub(crate) selected: Option<String>, // uuid of the component shown in the inspector
+    pub(crate) variable_writers: HashSet<String>, // Writers of the variables the selected component reads
+    pub(crate) variable_readers: HashSet<String>, // Readers of the variables the selected component writes
+    pub(crate) renaming: Option<(VariableKey, String)>, // Variable being renamed in the Variables view, and its new name
+    pub(crate) replay_component: Option<String>, // Component of the run history step being replayed
+    pub(crate) moved: Option<(String, String)>, // Component dragged by its handle and the container it was dropped on
+    pub(crate) refused: Option<String>, // Why the last palette drop was refused, for the status bar
+    pub(crate) reveal: Option<String>, // Component to scroll into view, selected with the keyboard
+    pub(crate) problems: HashMap<String, Vec<String>>, // Problems per component, refreshed after edits
+    pub(crate) blocks: Vec<(String, Rect)>, // Components and flows drawn in the current pane, for the minimap
+    pub(crate) icons: HashMap<String, TextureHandle>, // Palette icons by component type, for collapsed containers
+}
+
+// Payload of a component dragged by its handle to another container
+pub(crate) struct MovedComponent(pub(crate) String);
+
+const WRITER_COLOR: Color32 = Color32::from_rgb(230, 140, 0);
+const READER_COLOR: Color32 = Color32::from_rgb(40, 140, 230);
+pub(crate) const REPLAY_COLOR: Color32 = Color32::from_rgb(40, 180, 90);
+const RETRY_COLOR: Color32 = Color32::from_rgb(120, 100, 200);
+pub(crate) const PROBLEM_COLOR: Color32 = Color32::from_rgb(220, 60, 60);
+
+// Icons a collapsed container shows of its first components
+const SUMMARY_ICONS: usize = 4;
+
+// Whether a component of the given level may be dropped into `parent`; sources only go first
+// into a MainFlow
+pub(crate) fn accepts(parent: &BaseConfiguration, level: Option<ComponentLevel>) -> bool {
+    match level {
+        Some(ComponentLevel::ChildOnly) => true,
+        Some(ComponentLevel::SourceOnly) => {
+            parent.widget_type == "MainFlow"
+                && !parent.children.iter().any(|child| {
+                    child.lock().map(|child| child.component_level == ComponentLevel::SourceOnly).unwrap_or(false)
+                })
+        }
+        _ => false,
+    }
+}
+
+// Adds a component to a container if `accepts` allows it: a source goes first, anything else at
+// `position` or at the end. Every way of adding a component goes through here.
+pub(crate) fn place_child(parent: &mut BaseConfiguration, child: Arc<Mutex<BaseConfiguration>>, position: Option<usize>) -> bool {
+    let level = match child.lock() {
+        Ok(child) => child.component_level.clone(),
+        Err(_) => return false,
+    };
+    if !accepts(parent, Some(level.clone())) {
+        return false;
+    }
+    match position {
+        _ if level == ComponentLevel::SourceOnly => parent.children.insert(0, child),
+        Some(position) => parent.children.insert(position.min(parent.children.len()), child),
+        None => parent.children.push(child),
+    }
+    true
+}
+
+// Draws the children of a container inside a drop zone; palette items dropped here are appended
+// and components dropped by their handle are moved here once the canvas is drawn
+pub(crate) fn show_children(ui: &mut Ui, parent: &Arc<Mutex<BaseConfiguration>>, canvas: &mut CanvasState) {
+    let frame = egui::Frame {
+        fill: Color32::from_rgba_premultiplied(0, 0, 0, 0),
+        inner_margin: egui::Margin::same(4.0),
+        ..Default::default()
+    };
+
+    // Clone the child handles so no lock is held while the children draw themselves
+    let children = match parent.lock() {
+        Ok(config) => config.children.clone(),
+        Err(_) => {
+            println!("Failed to acquire lock on BaseConfiguration.");
+            return;
+        }
+    };
+
+    let (response, dropped_payload) = ui.dnd_drop_zone::<DraggableItem, ()>(frame, |ui| {
+        ui.set_min_height(24.0);
+        if children.is_empty() {
+            ui.label(RichText::new("Drop components here").small().weak());
+        }
+        for child in children.iter() {
+            show_component(ui, child, canvas);
+        }
+    });
+
+    if let Some(moved) = response.response.dnd_release_payload::<MovedComponent>() {
+        if let Ok(config) = parent.lock() {
+            canvas.moved = Some((moved.0.clone(), config.uuid.clone()));
+        }
+    }
+
+    if let Some(dropped_item) = dropped_payload {
+        if let Ok(mut config) = parent.lock() {
+            if let Some(new_child) = create_component(&dropped_item.name) {
+                if !place_child(&mut config, Arc::new(Mutex::new(new_child)), None) {
+                    canvas.refused = Some(format!("{} cannot be dropped into {}", dropped_item.name, config.widget_type));
+                }
+            }
+        }
+    }
+}
+
+// Components that hold a nested list of components
+fn is_scope(widget_type: &str) -> bool {
+    matches!(widget_type, "ForEach" | "ParallelForEach" | "UntilSuccessful" | "Async" | "Cache")
+}
+
+// Flows, Choice, Scatter-Gather and scopes can be collapsed to a summary line
+fn is_container(widget_type: &str) -> bool {
+    matches!(widget_type, "MainFlow" | "SubFlow" | "Choice" | "ScatterGather") || is_scope(widget_type)
+}
+
+// Chevron in the header of a container; returns whether it was clicked
+pub(crate) fn show_chevron(ui: &mut Ui, expanded: bool) -> bool {
+    let (icon, hover) = if expanded { ("\u{25BC}", "Collapse") } else { ("\u{25B6}", "Expand") };
+    ui.add(egui::Button::new(RichText::new(icon).small()).frame(false)).on_hover_text(hover).clicked()
+}
+
+// Components inside a container; those of Choice branches and Scatter-Gather routes come lane
+// after lane
+fn contents(config: &BaseConfiguration) -> Vec<Arc<Mutex<BaseConfiguration>>> {
+    if config.widget_type == "Choice" || config.widget_type == "ScatterGather" {
+        config.children.iter()
+            .filter_map(|route| route.lock().ok().map(|route| route.children.clone()))
+            .flatten()
+            .collect()
+    } else {
+        config.children.clone()
+    }
+}
+
+// Number of components below a container, and how many problems they have between them
+fn count_contents(config: &BaseConfiguration, problems: &HashMap<String, Vec<String>>) -> (usize, usize) {
+    let mut count = (0, 0);
+    for child in contents(config) {
+        if let Ok(child) = child.lock() {
+            if child.is_deleted {
+                continue;
+            }
+            let (components, child_problems) = count_contents(&child, problems);
+            count.0 += 1 + components;
+            count.1 += problems.get(&child.uuid).map(Vec::len).unwrap_or(0) + child_problems;
+        }
+    }
+    count
+}
+
+// The line a collapsed container shows instead of its components: how many it holds, the icons
+// of the first few and the problems hidden inside
+pub(crate) fn show_summary(ui: &mut Ui, container: &Arc<Mutex<BaseConfiguration>>, canvas: &CanvasState) {
+    let (first, (components, problems)) = match container.lock() {
+        Ok(config) => {
+            let first: Vec<String> = contents(&config).iter()
+                .filter_map(|child| child.lock().ok().filter(|child| !child.is_deleted).map(|child| child.widget_type.clone()))
+                .take(SUMMARY_ICONS)
+                .collect();
+            (first, count_contents(&config, &canvas.problems))
+        }
+        Err(_) => return,
+    };
+    ui.horizontal(|ui| {
+        for widget_type in &first {
+            match canvas.icons.get(widget_type) {
+                Some(texture) => ui.add(egui::Image::from_texture(egui::load::SizedTexture::from_handle(texture)).fit_to_exact_size(vec2(16.0, 16.0))),
+                None => ui.label(RichText::new(widget_type).small()),
+            }
+            .on_hover_text(widget_type);
+        }
+        if components > first.len() {
+            ui.label(RichText::new("\u{2026}").weak());
+        }
+        let text = if components == 1 { "1 component".to_string() } else { format!("{} components", components) };
+        ui.label(RichText::new(text).small().weak());
+        if problems > 0 {
+            ui.label(RichText::new(format!("\u{26A0} {}", problems)).small().color(PROBLEM_COLOR))
+                .on_hover_text("Problems in the collapsed components");
+        }
+    });
+}
+
+// Badge text and hover text for components the runtime retries
+fn retry_badge(config: &BaseConfiguration) -> Option<(String, String)> {
+    if config.widget_type == "UntilSuccessful" {
+        let retries = config.get_property("max_retries").trim();
+        return Some((format!("\u{21BB} {}", retries), format!("Retried up to {} times until successful", retries)));
+    }
+    match config.get_property("retry_policy").trim() {
+        "" => None,
+        policy => Some((format!("\u{21BB} {}", policy), format!("Retried by retry policy '{}'", policy))),
+    }
+}
+
+// Draws a single component; Choice branches and Scatter-Gather routes are drawn as lanes side
+// by side and scopes draw their components nested inside
+pub(crate) fn show_component(ui: &mut Ui, component: &Arc<Mutex<BaseConfiguration>>, canvas: &mut CanvasState) {
+    let (uuid, widget_type, name, routes, writes, retry, expanded) = match component.lock() {
+        Ok(config) if !config.is_deleted => (
+            config.uuid.clone(),
+            config.widget_type.clone(),
+            config.name.clone(),
+            config.children.clone(),
+            config.variable_changed.clone(),
+            retry_badge(&config),
+            config.is_expanded,
+        ),
+        _ => return,
+    };
+
+    // The replayed step and the writers and readers of the selected component's variables get a colored outline
+    let mut frame = egui::Frame::group(ui.style());
+    if canvas.replay_component.as_deref() == Some(uuid.as_str()) {
+        frame = frame.stroke(Stroke::new(3.0, REPLAY_COLOR));
+    } else if canvas.variable_writers.contains(&uuid) {
+        frame = frame.stroke(Stroke::new(2.0, WRITER_COLOR));
+    } else if canvas.variable_readers.contains(&uuid) {
+        frame = frame.stroke(Stroke::new(2.0, READER_COLOR));
+    }
+    let response = frame.show(ui, |ui| {
+        let selected = canvas.selected.as_deref() == Some(uuid.as_str());
+        let header = RichText::new(format!("{}: {}", widget_type, name)).strong();
+        ui.horizontal(|ui| {
+            if is_container(&widget_type) && show_chevron(ui, expanded) {
+                if let Ok(mut config) = component.lock() {
+                    config.is_expanded = !expanded;
+                }
+            }
+            ui.dnd_drag_source(egui::Id::new(("move_component", uuid.as_str())), MovedComponent(uuid.clone()), |ui| {
+                ui.label(RichText::new("\u{283F}").weak());
+            })
+            .response
+            .on_hover_text("Drag to move into another container or flow");
+            if ui.selectable_label(selected, header).clicked() {
+                canvas.selected = Some(uuid.clone());
+            }
+            if !writes.is_empty() {
+                ui.label(RichText::new(format!("\u{2192} {}", writes)).small().color(WRITER_COLOR));
+            }
+            if let Some((badge, hover)) = &retry {
+                ui.label(RichText::new(badge).small().color(RETRY_COLOR)).on_hover_text(hover);
+            }
+            if let Some(problems) = canvas.problems.get(&uuid) {
+                ui.label(RichText::new(format!("\u{26A0} {}", problems.len())).small().color(PROBLEM_COLOR))
+                    .on_hover_text(problems.join("\n"));
+            }
+        });
+        if is_container(&widget_type) && !expanded {
+            show_summary(ui, component, canvas);
+        } else if widget_type == "Choice" || widget_type == "ScatterGather" {
+            ui.horizontal_top(|ui| {
+                for route in routes.iter() {
+                    ui.vertical(|ui| {
+                        let label = match route.lock() {
+                            Ok(route) if route.widget_type == "When" => format!("When {}", route.get_property("expression")),
+                            Ok(route) => route.name.clone(),
+                            Err(_) => String::new(),
+                        };
+                        ui.label(RichText::new(label).italics());
+                        show_children(ui, route, canvas);
+                    });
+                }
+            });
+        } else if is_scope(&widget_type) {
+            ui.indent(uuid.clone(), |ui| show_children(ui, component, canvas));
+        }
+    });
+    canvas.blocks.push((uuid.clone(), response.response.rect));
+    if canvas.reveal.as_deref() == Some(uuid.as_str()) {
+        response.response.scroll_to_me(None);
+        canvas.reveal = None;
+    }
+}
@@ -0,0 +1,20 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::components::until_successful::until_successful::RETRY_POLICY;
+
+// Operation that takes the next message of a queue, acknowledging it at once. The received body
+// becomes the payload; without a message within the timeout the run fails with `MQ:TIMEOUT`.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Broker config", PropertyKind::GlobalRef("BrokerConfig"), ""),
+    PropertyField::new("queue", "Queue", PropertyKind::Text, "orders"),
+    PropertyField::new("timeout", "Timeout (ms)", PropertyKind::Number, "10000"),
+    RETRY_POLICY,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Consume".to_string())
+        .name("Consume".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,3 @@
+pub fn get_bytes() -> &'static [u8] {
+    include_bytes!("image_48.png")
+}
\ No newline at end of file
@@ -0,0 +1,35 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::components::until_successful::until_successful::RETRY_POLICY;
+use crate::support::database::OPERATIONS;
+
+// Operation that runs parameterized SQL against the database of its config. Select replaces the
+// payload with the rows; the other operations with the number of affected rows.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Database config", PropertyKind::GlobalRef("DatabaseConfig"), ""),
+    PropertyField::new("operation", "Operation", PropertyKind::Options(OPERATIONS), "Select"),
+    PropertyField::new("sql", "SQL (:name or ? parameters)", PropertyKind::Text, "SELECT * FROM items WHERE id = :id"),
+    PropertyField::new("input_parameters", "Input parameters (an array of them for Bulk)", PropertyKind::Expression, "{ id: 1 }"),
+    RETRY_POLICY,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Database".to_string())
+        .name("Database".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
+
+// Global element naming the SQLite database file, relative to the working directory
+pub(crate) const CONFIG_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("database_file", "Database file", PropertyKind::Text, "data/local.db"),
+];
+
+pub(crate) fn new_global_config(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("DatabaseConfig".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, CONFIG_PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod database;
\ No newline at end of file
@@ -7,9 +7,93 @@ mod main_flow_image {
     pub use crate::components::main_flow::image::get_bytes as get_main_flow_bytes;
 }
 
+mod async_image {
+    pub use crate::components::async_scope::image::get_bytes as get_async_bytes;
+}
+
+mod cache_image {
+    pub use crate::components::cache::image::get_bytes as get_cache_bytes;
+}
+
 mod choice_image {
     pub use crate::components::choice::image::get_bytes as get_choice_bytes;
 }
+
+mod consume_image {
+    pub use crate::components::consume::image::get_bytes as get_consume_bytes;
+}
+
+mod database_image {
+    pub use crate::components::database::image::get_bytes as get_database_bytes;
+}
+
+mod file_image {
+    pub use crate::components::file::image::get_bytes as get_file_bytes;
+}
+
+mod for_each_image {
+    pub use crate::components::for_each::image::get_bytes as get_for_each_bytes;
+}
+
+mod http_listener_image {
+    pub use crate::components::http_listener::image::get_bytes as get_http_listener_bytes;
+}
+
+mod http_request_image {
+    pub use crate::components::http_request::image::get_bytes as get_http_request_bytes;
+}
+
+mod idempotent_message_validator_image {
+    pub use crate::components::idempotent_message_validator::image::get_bytes as get_idempotent_message_validator_bytes;
+}
+
+mod logger_image {
+    pub use crate::components::logger::image::get_bytes as get_logger_bytes;
+}
+
+mod on_new_file_image {
+    pub use crate::components::on_new_file::image::get_bytes as get_on_new_file_bytes;
+}
+
+mod parallel_for_each_image {
+    pub use crate::components::parallel_for_each::image::get_bytes as get_parallel_for_each_bytes;
+}
+
+mod publish_image {
+    pub use crate::components::publish::image::get_bytes as get_publish_bytes;
+}
+
+mod queue_listener_image {
+    pub use crate::components::queue_listener::image::get_bytes as get_queue_listener_bytes;
+}
+
+mod remove_variable_image {
+    pub use crate::components::remove_variable::image::get_bytes as get_remove_variable_bytes;
+}
+
+mod scatter_gather_image {
+    pub use crate::components::scatter_gather::image::get_bytes as get_scatter_gather_bytes;
+}
+
+mod scheduler_image {
+    pub use crate::components::scheduler::image::get_bytes as get_scheduler_bytes;
+}
+
+mod set_payload_image {
+    pub use crate::components::set_payload::image::get_bytes as get_set_payload_bytes;
+}
+
+mod set_variable_image {
+    pub use crate::components::set_variable::image::get_bytes as get_set_variable_bytes;
+}
+
+mod transformer_image {
+    pub use crate::components::transformer::image::get_bytes as get_transformer_bytes;
+}
+
+mod until_successful_image {
+    pub use crate::components::until_successful::image::get_bytes as get_until_successful_bytes;
+}
 #[derive(Clone)]
 pub(crate) struct DraggableItem {
     pub(crate) texture: Option<TextureHandle>,
@@ -20,35 +104,40 @@ pub(crate) struct DraggableItem {
 impl DraggableItem {
     pub(crate) fn new(ctx: &Context, name: &str, component_level: &str) -> Self {
 
-        if name == "Choice" {
-            Self {
-                texture: Self::load_texture(ctx, name),
-                component_level: component_level.to_string(),
-                name: name.to_string(),
-            }
-        } else if name == "MainFlow" {
-            Self {
-                texture: Self::load_texture(ctx, name),
-                component_level: component_level.to_string(),
-                name: name.to_string(),
-            }
-        }
-        else {
-            Self {
-                texture: None,
-                component_level: component_level.to_string(),
-                name: name.to_string(),
-            }
+        // Unknown names get no texture and fall back to a text button in the palette
+        Self {
+            texture: Self::load_texture(ctx, name),
+            component_level: component_level.to_string(),
+            name: name.to_string(),
         }
-
-
     }
 
     fn load_texture(ctx: &Context, name: &str) -> Option<TextureHandle> {
         // Dynamically determine which image bytes to load
         let image_bytes = match name {
             "MainFlow" => main_flow_image::get_main_flow_bytes(),
+            "Async" => async_image::get_async_bytes(),
+            "Cache" => cache_image::get_cache_bytes(),
             "Choice" => choice_image::get_choice_bytes(),
+            "Consume" => consume_image::get_consume_bytes(),
+            "Database" => database_image::get_database_bytes(),
+            "File" => file_image::get_file_bytes(),
+            "ForEach" => for_each_image::get_for_each_bytes(),
+            "HttpListener" => http_listener_image::get_http_listener_bytes(),
+            "HttpRequest" => http_request_image::get_http_request_bytes(),
+            "IdempotentMessageValidator" => idempotent_message_validator_image::get_idempotent_message_validator_bytes(),
+            "Logger" => logger_image::get_logger_bytes(),
+            "OnNewFile" => on_new_file_image::get_on_new_file_bytes(),
+            "ParallelForEach" => parallel_for_each_image::get_parallel_for_each_bytes(),
+            "Publish" => publish_image::get_publish_bytes(),
+            "QueueListener" => queue_listener_image::get_queue_listener_bytes(),
+            "RemoveVariable" => remove_variable_image::get_remove_variable_bytes(),
+            "ScatterGather" => scatter_gather_image::get_scatter_gather_bytes(),
+            "Scheduler" => scheduler_image::get_scheduler_bytes(),
+            "SetPayload" => set_payload_image::get_set_payload_bytes(),
+            "SetVariable" => set_variable_image::get_set_variable_bytes(),
+            "Transformer" => transformer_image::get_transformer_bytes(),
+            "UntilSuccessful" => until_successful_image::get_until_successful_bytes(),
             _ => return None, // Return None if the name is not recognized
         };
 
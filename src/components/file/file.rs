@@ -0,0 +1,25 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::components::until_successful::until_successful::RETRY_POLICY;
+use crate::support::files::{OPERATIONS, WRITE_MODES};
+
+// Operation on the filesystem; relative paths resolve against the project folder. Read replaces
+// the payload with the file, List with the matching entries; Write and Move keep the message.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("operation", "Operation", PropertyKind::Options(OPERATIONS), "Read"),
+    PropertyField::new("path", "Path (the directory for List, the source for Move)", PropertyKind::Text, "data/input.json"),
+    PropertyField::new("content", "Content (Write)", PropertyKind::Expression, "payload"),
+    PropertyField::new("write_mode", "Write mode (Write)", PropertyKind::Options(WRITE_MODES), "Overwrite"),
+    PropertyField::new("pattern", "File name pattern (List)", PropertyKind::Text, "*"),
+    PropertyField::new("target_path", "Target path (Move)", PropertyKind::Text, ""),
+    PropertyField::new("overwrite", "Overwrite target (Move)", PropertyKind::Options(&["false", "true"]), "false"),
+    RETRY_POLICY,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("File".to_string())
+        .name("File".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,541 @@
+use std::collections::HashSet;
+use std::ops::Range;
+use std::sync::{Arc, Mutex};
+use egui::text::{LayoutJob, TextFormat};
+use egui::{Color32, FontId, RichText, ScrollArea, TextEdit, Ui};
+use regex::{NoExpand, Regex, RegexBuilder};
+use crate::base_configuration::BaseConfiguration;
+use crate::components::{find_component, property_fields};
+use crate::project::Project;
+
+// Characters of a long value shown around its first match
+const SNIPPET_LENGTH: usize = 80;
+
+// Where in a component a match is
+#[derive(Clone, PartialEq)]
+pub(crate) enum Field {
+    Name,
+    Label,
+    Uuid,
+    Property(String),
+    ScriptTarget(usize),
+    Script(usize),
+}
+
+impl Field {
+    fn key(&self) -> String {
+        match self {
+            Field::Name => "name".to_string(),
+            Field::Label => "label".to_string(),
+            Field::Uuid => "uuid".to_string(),
+            Field::Property(key) => format!("property:{}", key),
+            Field::ScriptTarget(index) => format!("target:{}", index),
+            Field::Script(index) => format!("script:{}", index),
+        }
+    }
+
+    fn label(&self, config: &BaseConfiguration) -> String {
+        match self {
+            Field::Name => "Name".to_string(),
+            Field::Label => "Label".to_string(),
+            Field::Uuid => "uuid".to_string(),
+            Field::Property(key) if config.widget_type == "When" && key == "expression" => "Condition".to_string(),
+            Field::Property(key) => property_fields(&config.widget_type).iter()
+                .find(|field| field.key == key)
+                .map(|field| field.label.to_string())
+                .unwrap_or_else(|| key.clone()),
+            Field::ScriptTarget(index) => format!("Script #{} target", index + 1),
+            Field::Script(index) => format!("Script #{}", index + 1),
+        }
+    }
+
+    fn read(&self, config: &BaseConfiguration) -> Option<String> {
+        match self {
+            Field::Name => Some(config.name.clone()),
+            Field::Label => Some(config.label.clone()),
+            Field::Uuid => Some(config.uuid.clone()),
+            Field::Property(key) => config.properties.get(key).cloned(),
+            Field::ScriptTarget(index) => config.transform_script_configs.get(*index)
+                .and_then(|script| script.lock().ok().map(|script| script.target.clone())),
+            Field::Script(index) => config.transform_script_configs.get(*index)
+                .and_then(|script| script.lock().ok().map(|script| script.script.clone())),
+        }
+    }
+
+    fn write(&self, config: &mut BaseConfiguration, value: String) {
+        match self {
+            Field::Name => config.name = value,
+            Field::Label => config.label = value,
+            // Components are identified by their uuid, it is never replaced
+            Field::Uuid => {}
+            Field::Property(key) => {
+                config.properties.insert(key.clone(), value);
+            }
+            Field::ScriptTarget(index) => {
+                if let Some(Ok(mut script)) = config.transform_script_configs.get(*index).map(|script| script.lock()) {
+                    script.target = value;
+                }
+            }
+            Field::Script(index) => {
+                if let Some(Ok(mut script)) = config.transform_script_configs.get(*index).map(|script| script.lock()) {
+                    script.script = value;
+                }
+            }
+        }
+    }
+}
+
+// Every field the search looks at: the declared properties in the inspector's order, then any others
+fn fields(config: &BaseConfiguration) -> Vec<Field> {
+    let mut fields = vec![Field::Name, Field::Label, Field::Uuid];
+    let declared = property_fields(&config.widget_type);
+    fields.extend(declared.iter().filter(|field| config.properties.contains_key(field.key)).map(|field| Field::Property(field.key.to_string())));
+    let mut others: Vec<&String> = config.properties.keys().filter(|key| !declared.iter().any(|field| field.key == key.as_str())).collect();
+    others.sort();
+    fields.extend(others.into_iter().map(|key| Field::Property(key.clone())));
+    for index in 0..config.transform_script_configs.len() {
+        fields.push(Field::ScriptTarget(index));
+        fields.push(Field::Script(index));
+    }
+    fields
+}
+
+// Choice branches and Scatter-Gather routes are edited through the component holding them
+fn is_route(widget_type: &str) -> bool {
+    matches!(widget_type, "When" | "Otherwise" | "Route")
+}
+
+// A match of the search
+pub(crate) struct Hit {
+    pub(crate) target: String, // uuid of the configuration holding the value
+    pub(crate) focus: String, // Component selected when the hit is clicked
+    pub(crate) title: String, // Component the hit is shown under
+    pub(crate) field: Field,
+    pub(crate) label: String,
+    pub(crate) value: String,
+    pub(crate) ranges: Vec<Range<usize>>,
+}
+
+impl Hit {
+    fn key(&self) -> String {
+        format!("{}/{}", self.target, self.field.key())
+    }
+}
+
+// The matches in one flow
+pub(crate) struct FlowHits {
+    pub(crate) flow: String, // Flow uuid
+    pub(crate) name: String,
+    pub(crate) hits: Vec<Hit>,
+}
+
+pub(crate) fn build_pattern(query: &str, regex: bool, match_case: bool) -> Result<Regex, String> {
+    let source = if regex { query.to_string() } else { regex::escape(query) };
+    RegexBuilder::new(&source).case_insensitive(!match_case).build().map_err(|error| error.to_string())
+}
+
+fn search_component(config: &Arc<Mutex<BaseConfiguration>>, owner: Option<(&str, &str)>, pattern: &Regex, hits: &mut Vec<Hit>) {
+    let config = match config.lock() {
+        Ok(config) if !config.is_deleted => config,
+        _ => return,
+    };
+    let title = format!("{}: {}", config.widget_type, config.name);
+    // Routes show their matches under the component holding them
+    let (focus, owner_title) = match owner {
+        Some((focus, owner_title)) if is_route(&config.widget_type) => (focus.to_string(), format!("{} \u{203A} {}", owner_title, config.name)),
+        _ => (config.uuid.clone(), title.clone()),
+    };
+    for field in fields(&config) {
+        let value = field.read(&config).unwrap_or_default();
+        let ranges: Vec<Range<usize>> = pattern.find_iter(&value).map(|found| found.range()).filter(|range| !range.is_empty()).collect();
+        if !ranges.is_empty() {
+            hits.push(Hit {
+                target: config.uuid.clone(),
+                focus: focus.clone(),
+                title: owner_title.clone(),
+                label: field.label(&config),
+                field,
+                value,
+                ranges,
+            });
+        }
+    }
+    for child in &config.children {
+        search_component(child, Some((&config.uuid, &title)), pattern, hits);
+    }
+}
+
+// Matches in every flow of the project, flow by flow in the project explorer's order
+pub(crate) fn search(project: &Project, pattern: &Regex) -> Vec<FlowHits> {
+    let mut found = Vec::new();
+    for flow in project.files.iter().flat_map(|file| file.flows.iter()) {
+        let (uuid, name) = match flow.config.lock() {
+            Ok(config) => (config.uuid.clone(), config.name.clone()),
+            Err(_) => continue,
+        };
+        let mut hits = Vec::new();
+        search_component(&flow.config, None, pattern, &mut hits);
+        if !hits.is_empty() {
+            found.push(FlowHits { flow: uuid, name, hits });
+        }
+    }
+    found
+}
+
+// A hit's value after the replace, or why it cannot be replaced
+fn replaced_value(hit: &Hit, flow: &FlowHits, pattern: &Regex, replacement: &str, regex: bool, flow_names: &[String]) -> Result<String, String> {
+    if hit.field == Field::Uuid {
+        return Err("uuids are not replaced".to_string());
+    }
+    let value = if regex {
+        pattern.replace_all(&hit.value, replacement).into_owned()
+    } else {
+        pattern.replace_all(&hit.value, NoExpand(replacement)).into_owned()
+    };
+    if hit.field == Field::Name && hit.target == flow.flow {
+        if value.trim().is_empty() {
+            return Err("flows need a name".to_string());
+        }
+        if value != hit.value && flow_names.contains(&value) {
+            return Err(format!("a flow is already named {}", value));
+        }
+    }
+    Ok(value)
+}
+
+// New values a replace gives the components of one flow
+pub(crate) struct FlowReplace {
+    pub(crate) flow: String, // Flow uuid
+    values: Vec<(String, Field, String)>, // Configuration uuid, field and new value
+}
+
+impl FlowReplace {
+    pub(crate) fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    // Writes the new values into the flow
+    pub(crate) fn apply(&self, flow: &Arc<Mutex<BaseConfiguration>>) {
+        for (target, field, value) in &self.values {
+            if let Some(Ok(mut config)) = find_component(std::slice::from_ref(flow), target).as_ref().map(|config| config.lock()) {
+                field.write(&mut config, value.clone());
+            }
+        }
+    }
+}
+
+// Matches of the last search, kept until the query, its options or the project change
+struct SearchResult {
+    query: String,
+    regex: bool,
+    match_case: bool,
+    revision: u64,
+    found: Result<(Regex, Vec<FlowHits>), String>, // Or why the query is not a valid pattern
+    flow_names: Vec<String>,
+}
+
+// Search panel state
+#[derive(Default)]
+pub(crate) struct FindState {
+    pub(crate) query: String,
+    regex: bool,
+    match_case: bool,
+    replacing: bool,
+    replacement: String,
+    pub(crate) focus_query: bool, // Moves the keyboard focus to the query on the next frame
+    excluded: HashSet<String>, // Changes left out of the replace, by hit
+    result: Option<SearchResult>,
+}
+
+impl FindState {
+    // Searches again on the next frame, e.g. once another project is open
+    pub(crate) fn invalidate(&mut self) {
+        self.result = None;
+    }
+
+    fn refresh(&mut self, project: &Project, revision: u64) {
+        let current = self.result.as_ref().is_some_and(|result| {
+            result.query == self.query && result.regex == self.regex && result.match_case == self.match_case && result.revision == revision
+        });
+        if current {
+            return;
+        }
+        let found = build_pattern(&self.query, self.regex, self.match_case).map(|pattern| {
+            let found = search(project, &pattern);
+            (pattern, found)
+        });
+        let flow_names = project.files.iter().flat_map(|file| file.flows.iter())
+            .filter_map(|flow| flow.config.lock().ok().map(|config| config.name.clone()))
+            .collect();
+        self.result = Some(SearchResult { query: self.query.clone(), regex: self.regex, match_case: self.match_case, revision, found, flow_names });
+    }
+}
+
+// What the search panel asks of the editor
+pub(crate) enum FindAction {
+    Focus { flow: String, component: String },
+    // Apply each flow's new values as one undoable edit
+    Replace(Vec<FlowReplace>),
+}
+
+// A value with its matches highlighted, cut around the first match when long
+fn highlighted(ui: &Ui, value: &str, ranges: &[Range<usize>], color: Color32) -> LayoutJob {
+    let first = ranges.first().map(|range| range.start).unwrap_or(0);
+    let mut start = first.saturating_sub(SNIPPET_LENGTH / 3);
+    while !value.is_char_boundary(start) {
+        start -= 1;
+    }
+    let mut end = (start + SNIPPET_LENGTH).min(value.len());
+    while !value.is_char_boundary(end) {
+        end += 1;
+    }
+    let font = FontId::monospace(11.0);
+    let plain = TextFormat::simple(font.clone(), ui.visuals().text_color());
+    let matched = TextFormat { background: color, ..plain.clone() };
+    let mut job = LayoutJob::default();
+    let mut append = |text: &str, format: &TextFormat| job.append(&text.replace('\n', "\u{23CE}"), 0.0, format.clone());
+    if start > 0 {
+        append("\u{2026}", &plain);
+    }
+    let mut position = start;
+    for range in ranges.iter().filter(|range| range.end > start && range.start < end) {
+        let (from, to) = (range.start.max(start), range.end.min(end));
+        append(&value[position..from], &plain);
+        append(&value[from..to], &matched);
+        position = to;
+    }
+    append(&value[position..end], &plain);
+    if end < value.len() {
+        append("\u{2026}", &plain);
+    }
+    job
+}
+
+// The included changes, flow by flow; flow names stay unique as they are replaced
+fn plan_replace(found: &[FlowHits], state: &FindState, pattern: &Regex, flow_names: &[String]) -> Vec<FlowReplace> {
+    let mut flow_names = flow_names.to_vec();
+    let mut replaces = Vec::new();
+    for flow_hits in found {
+        let mut values = Vec::new();
+        for hit in flow_hits.hits.iter().filter(|hit| !state.excluded.contains(&hit.key())) {
+            let value = match replaced_value(hit, flow_hits, pattern, &state.replacement, state.regex, &flow_names) {
+                Ok(value) if value != hit.value => value,
+                _ => continue,
+            };
+            if hit.field == Field::Name && hit.target == flow_hits.flow {
+                flow_names.push(value.clone());
+            }
+            values.push((hit.target.clone(), hit.field.clone(), value));
+        }
+        if !values.is_empty() {
+            replaces.push(FlowReplace { flow: flow_hits.flow.clone(), values });
+        }
+    }
+    replaces
+}
+
+// Search over the names, labels, uuids, properties, expressions and scripts of every component,
+// grouped by flow. Clicking a match focuses its component; in replace mode each match previews
+// its new value and can be left out before the changes are applied together. `revision` moves on
+// whenever the project may have changed; the search only runs again then or when the query does.
+pub(crate) fn show_find_replace(ui: &mut Ui, project: &Project, state: &mut FindState, revision: u64) -> Option<FindAction> {
+    let mut action = None;
+    let query = ui.add(TextEdit::singleline(&mut state.query).hint_text("Find").desired_width(f32::INFINITY));
+    if state.focus_query {
+        query.request_focus();
+        state.focus_query = false;
+    }
+    ui.horizontal_wrapped(|ui| {
+        ui.checkbox(&mut state.match_case, "Aa").on_hover_text("Match case");
+        ui.checkbox(&mut state.regex, ".*").on_hover_text("Regular expression");
+        ui.checkbox(&mut state.replacing, "Replace");
+    });
+    if state.replacing {
+        let hint = if state.regex { "Replace with ($1 for groups)" } else { "Replace with" };
+        ui.add(TextEdit::singleline(&mut state.replacement).hint_text(hint).desired_width(f32::INFINITY));
+    }
+    if state.query.is_empty() {
+        return action;
+    }
+    state.refresh(project, revision);
+    let result = match state.result.take() {
+        Some(result) => result,
+        None => return action,
+    };
+    let (pattern, found) = match &result.found {
+        Ok(found) => found,
+        Err(error) => {
+            ui.label(RichText::new(error).small().monospace().color(Color32::RED));
+            state.result = Some(result);
+            return action;
+        }
+    };
+    let flow_names = &result.flow_names;
+    let total: usize = found.iter().map(|flow| flow.hits.len()).sum();
+    ui.label(RichText::new(format!("{} matches in {} flows", total, found.len())).small().weak());
+
+    let match_color = ui.visuals().selection.bg_fill.gamma_multiply(0.6);
+    let replacement_color = Color32::from_rgb(40, 180, 90).gamma_multiply(0.5);
+    let mut apply = false;
+    if state.replacing && total > 0 {
+        let included = found.iter().flat_map(|flow| flow.hits.iter()).filter(|hit| !state.excluded.contains(&hit.key())).count();
+        apply = ui.add_enabled(included > 0, egui::Button::new(format!("Replace {} selected", included))).clicked();
+    }
+    ui.separator();
+    ScrollArea::vertical().id_salt("find_results").show(ui, |ui| {
+        for flow_hits in found {
+            egui::CollapsingHeader::new(format!("{} ({})", flow_hits.name, flow_hits.hits.len()))
+                .id_salt(("find_flow", flow_hits.flow.as_str()))
+                .default_open(true)
+                .show(ui, |ui| {
+                    for hit in &flow_hits.hits {
+                        ui.horizontal(|ui| {
+                            if state.replacing {
+                                let mut included = !state.excluded.contains(&hit.key());
+                                if ui.checkbox(&mut included, "").changed() {
+                                    if included {
+                                        state.excluded.remove(&hit.key());
+                                    } else {
+                                        state.excluded.insert(hit.key());
+                                    }
+                                }
+                            }
+                            let title = ui.add(egui::Label::new(RichText::new(format!("{} \u{00B7} {}", hit.title, hit.label)).small()).sense(egui::Sense::click()));
+                            if title.on_hover_text("Show on the canvas").clicked() {
+                                action = Some(FindAction::Focus { flow: flow_hits.flow.clone(), component: hit.focus.clone() });
+                            }
+                        });
+                        let value = ui.add(egui::Label::new(highlighted(ui, &hit.value, &hit.ranges, match_color)).sense(egui::Sense::click()));
+                        if value.clicked() {
+                            action = Some(FindAction::Focus { flow: flow_hits.flow.clone(), component: hit.focus.clone() });
+                        }
+                        if state.replacing && !state.excluded.contains(&hit.key()) {
+                            match replaced_value(hit, flow_hits, pattern, &state.replacement, state.regex, flow_names) {
+                                Ok(after) => {
+                                    // The new value, with the replaced parts highlighted where they can be found
+                                    let ranges: Vec<Range<usize>> = if state.replacement.is_empty() {
+                                        Vec::new()
+                                    } else {
+                                        after.match_indices(state.replacement.as_str()).map(|(start, text)| start..start + text.len()).collect()
+                                    };
+                                    ui.label(highlighted(ui, &after, &ranges, replacement_color));
+                                }
+                                Err(reason) => {
+                                    ui.label(RichText::new(reason).small().color(Color32::RED));
+                                }
+                            }
+                        }
+                        ui.add_space(2.0);
+                    }
+                });
+        }
+    });
+    if apply {
+        action = Some(FindAction::Replace(plan_replace(found, state, pattern, flow_names)));
+        state.excluded.clear();
+    }
+    state.result = Some(result);
+    action
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::main_flow::main_flow::MainFlow;
+    use crate::components::{create_component, insert_after};
+    use crate::project::ConfigFile;
+
+    fn project(flow_names: &[&str]) -> Project {
+        let mut file = ConfigFile::new("main".to_string());
+        file.flows = flow_names.iter().map(|name| MainFlow::new(name.to_string())).collect();
+        Project { files: vec![file], active_file: 0 }
+    }
+
+    fn add(project: &Project, flow: usize, component: BaseConfiguration) -> String {
+        let config = project.files[0].flows[flow].config.clone();
+        let root = config.lock().unwrap().uuid.clone();
+        insert_after(&[config], &root, component).unwrap()
+    }
+
+    fn state(query: &str, replacement: &str, regex: bool) -> FindState {
+        FindState { query: query.to_string(), replacement: replacement.to_string(), regex, ..Default::default() }
+    }
+
+    fn planned(project: &Project, state: &FindState) -> Vec<(String, String)> {
+        let pattern = build_pattern(&state.query, state.regex, state.match_case).unwrap();
+        let flow_names: Vec<String> = project.files[0].flows.iter().map(|flow| flow.config.lock().unwrap().name.clone()).collect();
+        plan_replace(&search(project, &pattern), state, &pattern, &flow_names).into_iter()
+            .flat_map(|replace| replace.values.into_iter().map(|(_, field, value)| (field.key(), value)))
+            .collect()
+    }
+
+    #[test]
+    fn finds_names_properties_and_route_conditions() {
+        let project = project(&["Orders"]);
+        let logger = add(&project, 0, create_component("Logger").unwrap().property("message", "order received"));
+        let choice = add(&project, 0, create_component("Choice").unwrap());
+        let found = search(&project, &build_pattern("ORDER", false, false).unwrap());
+        let hits: Vec<(&str, &str)> = found[0].hits.iter().map(|hit| (hit.label.as_str(), hit.value.as_str())).collect();
+        assert_eq!(hits, vec![("Name", "Orders"), ("Message", "order received")]);
+        assert_eq!(found[0].hits[1].target, logger);
+        assert!(search(&project, &build_pattern("ORDER", false, true).unwrap()).is_empty());
+        // A branch's condition is shown and focused through its Choice
+        let conditions = search(&project, &build_pattern("true", false, true).unwrap());
+        let condition = conditions[0].hits.iter().find(|hit| hit.label == "Condition").unwrap();
+        assert_eq!(condition.focus, choice);
+        assert_eq!(condition.title, "Choice: Choice \u{203A} When");
+        assert!(build_pattern("(", true, false).is_err());
+    }
+
+    #[test]
+    fn regex_replacements_expand_groups_and_plain_ones_do_not() {
+        let project = project(&["Flow"]);
+        add(&project, 0, create_component("Logger").unwrap().property("message", "id 42"));
+        assert_eq!(planned(&project, &state(r"id (\d+)", "n=$1", true)), vec![("property:message".to_string(), "n=42".to_string())]);
+        assert_eq!(planned(&project, &state("id", "$1", false)), vec![("property:message".to_string(), "$1 42".to_string())]);
+    }
+
+    #[test]
+    fn uuids_excluded_hits_and_duplicate_flow_names_are_left_alone() {
+        let project = project(&["A", "B"]);
+        let uuid = project.files[0].flows[0].config.lock().unwrap().uuid.clone();
+        assert!(planned(&project, &state(&uuid, "x", false)).is_empty());
+        // Renaming both flows to C would give two flows the same name
+        assert_eq!(planned(&project, &state("^[AB]$", "C", true)), vec![("name".to_string(), "C".to_string())]);
+        let mut excluding = state("^[AB]$", "D", true);
+        let pattern = build_pattern(&excluding.query, true, false).unwrap();
+        excluding.excluded.insert(search(&project, &pattern)[0].hits[0].key());
+        assert_eq!(planned(&project, &excluding), vec![("name".to_string(), "D".to_string())]);
+    }
+
+    #[test]
+    fn applying_a_replace_writes_its_values_into_the_flow() {
+        let project = project(&["Flow"]);
+        let logger = add(&project, 0, create_component("Logger").unwrap().property("message", "old text"));
+        let state = state("old", "new", false);
+        let pattern = build_pattern(&state.query, false, false).unwrap();
+        let replaces = plan_replace(&search(&project, &pattern), &state, &pattern, &[]);
+        assert_eq!((replaces.len(), replaces[0].len()), (1, 1));
+        let flow = &project.files[0].flows[0].config;
+        replaces[0].apply(flow);
+        let config = find_component(std::slice::from_ref(flow), &logger).unwrap();
+        assert_eq!(config.lock().unwrap().get_property("message"), "new text");
+    }
+
+    #[test]
+    fn the_search_runs_again_only_when_the_query_or_revision_changes() {
+        let project = project(&["Orders"]);
+        let mut state = state("Orders", "", false);
+        state.refresh(&project, 1);
+        project.files[0].flows[0].config.lock().unwrap().name = "Sales".to_string();
+        state.refresh(&project, 1);
+        let hits = |state: &FindState| state.result.as_ref().unwrap().found.as_ref().unwrap().1.len();
+        assert_eq!(hits(&state), 1);
+        state.refresh(&project, 2);
+        assert_eq!(hits(&state), 0);
+        state.query = "(".to_string();
+        state.regex = true;
+        state.refresh(&project, 2);
+        assert!(state.result.as_ref().unwrap().found.is_err());
+    }
+}
@@ -0,0 +1,20 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Scope that runs its components once per element of a collection, one after the other.
+// Each run sees the element (or a batch array) as payload and its 1-based index in the counter
+// variable; variables set inside are kept, and the original payload is restored afterwards.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("collection", "Collection", PropertyKind::Expression, "payload"),
+    PropertyField::new("batch_size", "Batch size", PropertyKind::Number, "1"),
+    PropertyField::new("counter_variable", "Counter variable", PropertyKind::Text, "counter"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("ForEach".to_string())
+        .name("For Each".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod for_each;
+pub(crate) mod image;
\ No newline at end of file
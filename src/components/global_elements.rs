@@ -0,0 +1,58 @@
+use std::sync::{Arc, Mutex};
+use egui::{RichText, Ui};
+use crate::base_configuration::BaseConfiguration;
+use crate::components::component_view::CanvasState;
+use crate::components::{create_global, GLOBAL_TYPES};
+
+fn label_of(widget_type: &str) -> &str {
+    GLOBAL_TYPES.iter().find(|(global_type, _)| *global_type == widget_type).map(|(_, label)| *label).unwrap_or(widget_type)
+}
+
+// First free name of the form "<label> <n>"
+fn unique_name(globals: &[Arc<Mutex<BaseConfiguration>>], label: &str) -> String {
+    let names: Vec<String> = globals.iter().filter_map(|global| global.lock().ok().map(|global| global.name.clone())).collect();
+    (1..).map(|number| format!("{} {}", label, number)).find(|name| !names.contains(name)).unwrap_or_default()
+}
+
+// Lists the project's global elements; clicking one opens it in the inspector
+pub(crate) fn show_global_elements(ui: &mut Ui, globals: &mut Vec<Arc<Mutex<BaseConfiguration>>>, canvas: &mut CanvasState) {
+    if globals.is_empty() {
+        ui.label(RichText::new("Shared connector settings that components reference by name.").small().weak());
+    }
+    let mut removed = None;
+    for (index, global) in globals.iter().enumerate() {
+        let (uuid, name, widget_type) = match global.lock() {
+            Ok(global) => (global.uuid.clone(), global.name.clone(), global.widget_type.clone()),
+            Err(_) => continue,
+        };
+        ui.horizontal(|ui| {
+            let selected = canvas.selected.as_deref() == Some(uuid.as_str());
+            let label = format!("{} ({})", name, label_of(&widget_type));
+            if ui.selectable_label(selected, label).clicked() {
+                canvas.selected = Some(uuid.clone());
+            }
+            if ui.small_button("🗑").clicked() {
+                removed = Some(index);
+            }
+        });
+    }
+    if let Some(index) = removed {
+        globals.remove(index);
+    }
+    let mut added = None;
+    egui::ComboBox::from_id_salt("add_global_element")
+        .selected_text("Add global element")
+        .show_ui(ui, |ui| {
+            for (widget_type, label) in GLOBAL_TYPES {
+                if ui.selectable_label(false, *label).clicked() {
+                    added = Some((*widget_type, *label));
+                }
+            }
+        });
+    if let Some((widget_type, label)) = added {
+        if let Some(config) = create_global(widget_type, unique_name(globals, label)) {
+            canvas.selected = Some(config.uuid.clone());
+            globals.push(Arc::new(Mutex::new(config)));
+        }
+    }
+}
@@ -0,0 +1,36 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Source that starts its flow for every matching HTTP request. The response is built from the
+// payload at the end of the flow, with status and headers taken from expressions.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Listener config", PropertyKind::GlobalRef("HttpListenerConfig"), ""),
+    PropertyField::new("host", "Host", PropertyKind::Text, "0.0.0.0"),
+    PropertyField::new("port", "Port", PropertyKind::Number, "8081"),
+    PropertyField::new("path", "Path (a trailing /* matches sub paths)", PropertyKind::Text, "/"),
+    PropertyField::new("allowed_methods", "Allowed methods (comma separated, empty for all)", PropertyKind::Text, ""),
+    PropertyField::new("response_status", "Response status", PropertyKind::Expression, "200"),
+    PropertyField::new("response_headers", "Response headers", PropertyKind::Expression, "{}"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("HttpListener".to_string())
+        .name("HTTP Listener".to_string())
+        .component_level(ComponentLevel::SourceOnly);
+    with_defaults(config, PROPERTIES)
+}
+
+// Global element shared by listeners; its host and port replace the listener's own
+pub(crate) const CONFIG_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("host", "Host", PropertyKind::Text, "0.0.0.0"),
+    PropertyField::new("port", "Port", PropertyKind::Number, "8081"),
+];
+
+pub(crate) fn new_global_config(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("HttpListenerConfig".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, CONFIG_PROPERTIES)
+}
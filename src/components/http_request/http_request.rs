@@ -0,0 +1,36 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::components::until_successful::until_successful::RETRY_POLICY;
+
+// Operation that calls an HTTP endpoint; the response replaces payload and attributes
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Request config", PropertyKind::GlobalRef("HttpRequestConfig"), ""),
+    PropertyField::new("method", "Method", PropertyKind::Options(&["GET", "POST", "PUT", "PATCH", "DELETE", "HEAD", "OPTIONS"]), "GET"),
+    PropertyField::new("url", "URL (relative to the config's base URL, if any)", PropertyKind::Text, "http://localhost:8081/"),
+    PropertyField::new("headers", "Headers", PropertyKind::Expression, "{}"),
+    PropertyField::new("body", "Body", PropertyKind::Expression, "payload"),
+    PropertyField::new("timeout", "Timeout (ms)", PropertyKind::Number, "30000"),
+    RETRY_POLICY,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("HttpRequest".to_string())
+        .name("HTTP Request".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
+
+// Global element shared by requests; relative URLs are resolved against its base URL
+pub(crate) const CONFIG_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("base_url", "Base URL", PropertyKind::Text, "http://localhost:8081"),
+    PropertyField::new("timeout", "Timeout (ms)", PropertyKind::Number, ""),
+];
+
+pub(crate) fn new_global_config(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("HttpRequestConfig".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, CONFIG_PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod http_request;
+pub(crate) mod image;
\ No newline at end of file
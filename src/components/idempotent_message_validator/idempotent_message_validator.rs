@@ -0,0 +1,18 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::cache::cache::OBJECT_STORE;
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Lets a message through the first time its id is seen. The id is kept in the object store, so a
+// repeated id fails with `RUNTIME:DUPLICATE_MESSAGE` until its entry expires or is evicted.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("id_expression", "Message id", PropertyKind::Expression, "payload.id"),
+    OBJECT_STORE,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("IdempotentMessageValidator".to_string())
+        .name("Idempotent Message Validator".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod idempotent_message_validator;
\ No newline at end of file
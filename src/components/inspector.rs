@@ -0,0 +1,360 @@
+use std::sync::{Arc, Mutex};
+use chrono::Utc;
+use egui::{Color32, RichText, TextEdit, Ui};
+use serde_json::Value;
+use crate::base_configuration::{BaseConfiguration, TransformScriptConfig};
+use crate::components::{choice, property_fields, scatter_gather};
+use crate::expression;
+use crate::expression::eval::eval_source;
+use crate::properties::{Properties, SecureMode};
+use crate::runtime::scheduler::schedule_of;
+use crate::support::database;
+use crate::support::message::Message;
+
+// How the inspector edits a property
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum PropertyKind {
+    // Plain text; may embed an expression as `#[...]`
+    Text,
+    // Always an expression; parse errors are shown below the field
+    Expression,
+    Number,
+    Options(&'static [&'static str]),
+    // Name of a global element of the given type
+    GlobalRef(&'static str),
+}
+
+// One editable property of a component, declared by the component module
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct PropertyField {
+    pub(crate) key: &'static str,
+    pub(crate) label: &'static str,
+    pub(crate) kind: PropertyKind,
+    pub(crate) default: &'static str,
+}
+
+impl PropertyField {
+    pub(crate) const fn new(key: &'static str, label: &'static str, kind: PropertyKind, default: &'static str) -> Self {
+        Self { key, label, kind, default }
+    }
+}
+
+// Applies the default value of every declared property
+pub(crate) fn with_defaults(mut config: BaseConfiguration, fields: &[PropertyField]) -> BaseConfiguration {
+    for field in fields {
+        config = config.property(field.key, field.default);
+    }
+    config
+}
+
+// Project data the inspector shows next to a component's own settings
+pub(crate) struct InspectorContext<'a> {
+    pub(crate) globals: &'a [Arc<Mutex<BaseConfiguration>>],
+    pub(crate) properties: &'a Properties,
+}
+
+impl InspectorContext<'_> {
+    fn global_names(&self, widget_type: &str) -> Vec<String> {
+        self.globals.iter()
+            .filter_map(|global| global.lock().ok().filter(|global| global.widget_type == widget_type).map(|global| global.name.clone()))
+            .collect()
+    }
+
+    fn global(&self, name: &str) -> Option<Arc<Mutex<BaseConfiguration>>> {
+        self.globals.iter().find(|global| global.lock().map(|global| global.name == name).unwrap_or(false)).cloned()
+    }
+}
+
+// Shows the value a property has in the selected environment when it uses placeholders
+fn show_resolved_value(ui: &mut Ui, value: &str, properties: &Properties) {
+    if !value.contains("${") {
+        return;
+    }
+    match properties.resolve(value, SecureMode::Mask) {
+        Ok(resolved) => ui.label(RichText::new(format!("{}: {}", properties.environment, resolved)).small().weak()),
+        Err(error) => ui.label(RichText::new(error).small().color(Color32::RED)),
+    };
+}
+
+// Settings a component takes from the global element it references
+fn show_referenced_config(ui: &mut Ui, name: &str, context: &InspectorContext) {
+    let global = match context.global(name) {
+        Some(global) => global,
+        None => {
+            ui.label(RichText::new(format!("Global element '{}' does not exist", name)).small().color(Color32::RED));
+            return;
+        }
+    };
+    let global = match global.lock() {
+        Ok(global) => global,
+        Err(_) => return,
+    };
+    for field in property_fields(&global.widget_type) {
+        let value = global.get_property(field.key);
+        if value.trim().is_empty() {
+            continue;
+        }
+        let resolved = context.properties.resolve(value, SecureMode::Mask).unwrap_or_else(|_| value.to_string());
+        ui.label(RichText::new(format!("{}: {}", field.label, resolved)).small().weak());
+    }
+}
+
+// Shows a parse error for an expression, if any
+fn show_expression_error(ui: &mut Ui, source: &str) {
+    if let Err(error) = expression::parse(source) {
+        ui.label(RichText::new(error.to_string()).small().color(Color32::RED));
+    }
+}
+
+fn show_property(ui: &mut Ui, config: &mut BaseConfiguration, field: &PropertyField, context: &InspectorContext) {
+    let mut value = config.get_property(field.key).to_string();
+    ui.label(field.label);
+    let changed = match field.kind {
+        PropertyKind::Text | PropertyKind::Number => ui.text_edit_singleline(&mut value).changed(),
+        PropertyKind::Expression => ui.add(TextEdit::multiline(&mut value).code_editor().desired_rows(1)).changed(),
+        PropertyKind::Options(options) => {
+            let mut changed = false;
+            egui::ComboBox::from_id_salt(field.key)
+                .selected_text(value.clone())
+                .show_ui(ui, |ui| {
+                    for option in options {
+                        changed |= ui.selectable_value(&mut value, option.to_string(), *option).changed();
+                    }
+                });
+            changed
+        }
+        PropertyKind::GlobalRef(widget_type) => {
+            let mut changed = false;
+            let selected = if value.is_empty() { "None".to_string() } else { value.clone() };
+            egui::ComboBox::from_id_salt(field.key)
+                .selected_text(selected)
+                .show_ui(ui, |ui| {
+                    changed |= ui.selectable_value(&mut value, String::new(), "None").changed();
+                    for name in context.global_names(widget_type) {
+                        changed |= ui.selectable_value(&mut value, name.clone(), name).changed();
+                    }
+                });
+            changed
+        }
+    };
+    match field.kind {
+        PropertyKind::Expression => show_expression_error(ui, &value),
+        PropertyKind::Text => {
+            if let Some(source) = expression::embedded_expression(&value) {
+                show_expression_error(ui, source);
+            }
+        }
+        PropertyKind::Number if !value.trim().is_empty() && value.trim().parse::<f64>().is_err() => {
+            ui.label(RichText::new("Not a number").small().color(Color32::RED));
+        }
+        PropertyKind::GlobalRef(_) if !value.is_empty() => show_referenced_config(ui, &value, context),
+        _ => {}
+    }
+    if !matches!(field.kind, PropertyKind::GlobalRef(_)) {
+        show_resolved_value(ui, &value, context.properties);
+    }
+    if changed {
+        config.properties.insert(field.key.to_string(), value);
+    }
+}
+
+// Script list of a Transformer: target (empty for the payload) and expression
+fn show_transform_scripts(ui: &mut Ui, config: &mut BaseConfiguration) {
+    let mut removed = None;
+    for (index, script) in config.transform_script_configs.iter().enumerate() {
+        if let Ok(mut script) = script.lock() {
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label("Target");
+                ui.add(TextEdit::singleline(&mut script.target).hint_text("payload").desired_width(100.0));
+                if ui.small_button("🗑").clicked() {
+                    removed = Some(index);
+                }
+            });
+            ui.add(TextEdit::multiline(&mut script.script).code_editor().desired_rows(2));
+            show_expression_error(ui, &script.script);
+        }
+    }
+    if let Some(index) = removed {
+        config.transform_script_configs.remove(index);
+    }
+    if ui.button("Add script").clicked() {
+        config.transform_script_configs.push(Arc::new(Mutex::new(TransformScriptConfig::new("variable", "payload"))));
+    }
+}
+
+// Branches of a Choice: each `When` holds a condition; new branches go before `Otherwise`
+fn show_choice_routes(ui: &mut Ui, config: &mut BaseConfiguration) {
+    let mut removed = None;
+    for (index, route) in config.children.iter().enumerate() {
+        if let Ok(mut route) = route.lock() {
+            if route.widget_type != "When" {
+                continue;
+            }
+            ui.separator();
+            ui.horizontal(|ui| {
+                ui.label(format!("When #{}", index + 1));
+                if ui.small_button("🗑").clicked() {
+                    removed = Some(index);
+                }
+            });
+            let mut expression = route.get_property("expression").to_string();
+            if ui.add(TextEdit::multiline(&mut expression).code_editor().desired_rows(1)).changed() {
+                route.properties.insert("expression".to_string(), expression.clone());
+            }
+            show_expression_error(ui, &expression);
+        }
+    }
+    if let Some(index) = removed {
+        config.children.remove(index);
+    }
+    if ui.button("Add When").clicked() {
+        let position = config.children.iter()
+            .position(|route| route.lock().map(|route| route.widget_type == "Otherwise").unwrap_or(false))
+            .unwrap_or(config.children.len());
+        config.children.insert(position, Arc::new(Mutex::new(choice::choice::new_when("true"))));
+    }
+}
+
+// Flow settings: whether the flow starts with the runtime, how many runs may overlap and what
+// happens to runs beyond that
+fn show_flow_settings(ui: &mut Ui, config: &mut BaseConfiguration) {
+    ui.label("Initial state");
+    let mut state = if config.initial_state.is_empty() { "started".to_string() } else { config.initial_state.clone() };
+    egui::ComboBox::from_id_salt("initial_state")
+        .selected_text(state.clone())
+        .show_ui(ui, |ui| {
+            for option in ["started", "stopped"] {
+                ui.selectable_value(&mut state, option.to_string(), option);
+            }
+        });
+    config.initial_state = state;
+    ui.label("Max concurrency");
+    ui.add(egui::DragValue::new(&mut config.max_concurrency).range(1..=64));
+    ui.label("When at max concurrency");
+    let mut backpressure = match config.get_property("backpressure") {
+        "" => "Queue".to_string(),
+        backpressure => backpressure.to_string(),
+    };
+    egui::ComboBox::from_id_salt("backpressure")
+        .selected_text(backpressure.clone())
+        .show_ui(ui, |ui| {
+            for option in ["Queue", "Reject"] {
+                ui.selectable_value(&mut backpressure, option.to_string(), option);
+            }
+        });
+    config.properties.insert("backpressure".to_string(), backpressure);
+    ui.checkbox(&mut config.is_tracking_enabled, "Track messages")
+        .on_hover_text("Record every component of each run in the run history");
+}
+
+// Next fire times of a Scheduler, or why its schedule is invalid
+fn show_schedule_preview(ui: &mut Ui, config: &BaseConfiguration) {
+    ui.separator();
+    match schedule_of(&config.properties) {
+        Ok(schedule) => {
+            ui.label("Next runs");
+            let now = Utc::now();
+            for time in schedule.fire_times(now, now, 5) {
+                ui.label(RichText::new(schedule.format_time(time)).small().monospace());
+            }
+        }
+        Err(error) => {
+            ui.label(RichText::new(error).small().color(Color32::RED));
+        }
+    }
+}
+
+// Runs a Database operation against its configured database with input parameters evaluated on an
+// empty message. Insert, Update, Delete and Bulk really change the database.
+fn run_test_query(config: &BaseConfiguration, context: &InspectorContext) -> Result<Value, String> {
+    let config_name = config.get_property("config");
+    let global = context.global(config_name).ok_or_else(|| format!("Global element '{}' does not exist", config_name))?;
+    let database_file = global.lock().map(|global| global.get_property("database_file").to_string()).unwrap_or_default();
+    let database_file = context.properties.resolve(&database_file, SecureMode::Decrypt)?;
+    let sql = context.properties.resolve(config.get_property("sql"), SecureMode::Decrypt)?;
+    let parameters = context.properties.resolve_expression(config.get_property("input_parameters"), SecureMode::Decrypt)?;
+    let parameters = eval_source(&parameters, &Message::default()).map_err(|error| error.to_string())?;
+    database::query(database_file.trim(), config.get_property("operation"), &sql, &parameters).map_err(|error| error.to_string())
+}
+
+fn show_test_query(ui: &mut Ui, config: &BaseConfiguration, context: &InspectorContext) {
+    let id = egui::Id::new(("test_query", config.uuid.clone()));
+    ui.separator();
+    let enabled = !config.get_property("config").is_empty();
+    if ui.add_enabled(enabled, egui::Button::new("Test query")).on_disabled_hover_text("Select a database config first").clicked() {
+        let result = run_test_query(config, context);
+        ui.data_mut(|data| data.insert_temp(id, result));
+    }
+    match ui.data(|data| data.get_temp::<Result<Value, String>>(id)) {
+        Some(Ok(result)) => {
+            ui.label(RichText::new(serde_json::to_string_pretty(&result).unwrap_or_default()).small().monospace());
+        }
+        Some(Err(error)) => {
+            ui.label(RichText::new(error).small().color(Color32::RED));
+        }
+        None => {}
+    }
+}
+
+// Routes of a Scatter-Gather; routes are renumbered when one is removed
+fn show_scatter_gather_routes(ui: &mut Ui, config: &mut BaseConfiguration) {
+    let mut removed = None;
+    ui.separator();
+    for (index, route) in config.children.iter().enumerate() {
+        if let Ok(mut route) = route.lock() {
+            ui.horizontal(|ui| {
+                ui.label(format!("Route {}", index + 1));
+                if ui.small_button("🗑").clicked() {
+                    removed = Some(index);
+                }
+            });
+            let mut timeout = route.get_property("timeout").to_string();
+            ui.horizontal(|ui| {
+                ui.label("Timeout in ms");
+                if ui.add(TextEdit::singleline(&mut timeout).hint_text("none").desired_width(80.0)).changed() {
+                    route.properties.insert("timeout".to_string(), timeout.clone());
+                }
+            });
+        }
+    }
+    if let Some(index) = removed {
+        config.children.remove(index);
+        for (index, route) in config.children.iter().enumerate() {
+            if let Ok(mut route) = route.lock() {
+                route.name = format!("Route {}", index + 1);
+            }
+        }
+    }
+    if ui.button("Add Route").clicked() {
+        let number = config.children.len() + 1;
+        config.children.push(Arc::new(Mutex::new(scatter_gather::scatter_gather::new_route(number))));
+    }
+}
+
+// Right panel form for the selected component
+pub(crate) fn show_inspector(ui: &mut Ui, component: &Arc<Mutex<BaseConfiguration>>, context: &InspectorContext) {
+    let mut config = match component.lock() {
+        Ok(config) => config,
+        Err(_) => return,
+    };
+
+    ui.label(RichText::new(config.widget_type.clone()).strong());
+    ui.label(RichText::new(config.uuid.clone()).small().weak());
+    ui.label("Name");
+    ui.text_edit_singleline(&mut config.name);
+
+    for field in property_fields(&config.widget_type) {
+        show_property(ui, &mut config, field, context);
+    }
+
+    match config.widget_type.as_str() {
+        "Transformer" => show_transform_scripts(ui, &mut config),
+        "Choice" => show_choice_routes(ui, &mut config),
+        "ScatterGather" => show_scatter_gather_routes(ui, &mut config),
+        "MainFlow" => show_flow_settings(ui, &mut config),
+        "Scheduler" => show_schedule_preview(ui, &config),
+        "Database" => show_test_query(ui, &config, context),
+        _ => {}
+    }
+}
@@ -0,0 +1,19 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::support::logging::LEVELS;
+
+// Writes a log entry and passes the message on unchanged. An empty message logs the payload.
+// Entries appear in the editor's log panel during runs and as JSON lines in generated services.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("level", "Level", PropertyKind::Options(LEVELS), "INFO"),
+    PropertyField::new("message", "Message", PropertyKind::Text, ""),
+    PropertyField::new("category", "Category", PropertyKind::Text, "flow"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Logger".to_string())
+        .name("Logger".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -3,8 +3,9 @@ use egui::{Context, Frame, Ui, Color32, vec2, Id, Align2, Order, CentralPanel, W
 use std::sync::{Arc, Mutex};
 use std::thread;
 use std::time::Duration;
-use eframe::emath::Align;
 use crate::base_configuration::{BaseConfiguration, ComponentLevel, ConfigParams};
+use crate::components::component_view::{show_chevron, show_children, show_summary, CanvasState};
+use crate::runtime::Runtime;
 
 // Placeholder for MainFlow struct
 pub struct MainFlow {
@@ -15,94 +16,105 @@ pub struct MainFlow {
 
 impl MainFlow {
 
-    pub fn show_in_panel(&mut self, ui: &mut Ui, desired_size: egui::Vec2, label: &str) {
+    pub fn show_in_panel(&mut self, ui: &mut Ui, desired_size: egui::Vec2, label: &str, canvas: &mut CanvasState, runtime: Option<&Arc<Runtime>>) {
         // Create a frame or container for MainFlow
-        ui.group(|ui| {
+        let (uuid, expanded) = self.config.lock().map(|config| (config.uuid.clone(), config.is_expanded)).unwrap_or_default();
+        // The configuration holds the expansion, so it is saved with the flow and follows undo
+        self.is_expanded = expanded;
+        let response = ui.group(|ui| {
             ui.set_min_size(desired_size);
 
             // Draw the MainFlow title and size as part of a label
             ui.horizontal(|ui| {
-                ui.label(label);
+                if show_chevron(ui, self.is_expanded) {
+                    self.toggle_expansion();
+                }
+                // Clicking the title shows the flow in the inspector
+                let selected = canvas.selected.as_deref() == Some(uuid.as_str());
+                if ui.selectable_label(selected, label).clicked() {
+                    canvas.selected = Some(uuid.clone());
+                }
 
                 let size = ui.min_size();
                 let size_text = format!("Size: {:.1} x {:.1}", size.x, size.y);
                 ui.label(size_text);
-            });
-
-            // Draw the content of the MainFlow
-            self.show_contents(ui);
-        });
-    }
-
-    pub fn show_contents(&mut self, ui: &mut Ui) {
-        // Drag-and-drop area and children display
-        let frame = egui::Frame {
-            fill: egui::Color32::from_rgba_premultiplied(0, 0, 0, 0),
-            ..Default::default()
-        };
-
-        // Drag-and-drop area and children display
-        let (response, dropped_payload) =
-            ui.dnd_drop_zone::<BaseConfiguration, ()>(frame,|ui| {
-                ui.layer_id().order = Order::Background;
-                ui.allocate_space(ui.available_size());
 
-                if let Ok(config) = self.config.lock() {
-                    for child in config.children.iter() {
-                        let unwrapped_child = child.lock().unwrap();
-
-                        ui.label(format!("Child: {}", unwrapped_child.uuid));
-                    }
-                } else {
-                    println!("Failed to acquire lock on BaseConfiguration.");
+                if let Some(runtime) = runtime {
+                    show_flow_state(ui, label, runtime);
                 }
             });
 
-        if let Some(new_child) = dropped_payload {
-            if let Ok(mut config) = self.config.lock() {
-                let unwrapped_child = Arc::try_unwrap(new_child).unwrap();
-                config.children.push(Arc::new(Mutex::from(unwrapped_child)));
+            // Draw the content of the MainFlow, or a summary of it while collapsed
+            if expanded {
+                self.show_contents(ui, canvas);
             } else {
-                println!("Failed to acquire lock on BaseConfiguration.");
+                show_summary(ui, &self.config, canvas);
             }
+        });
+        canvas.blocks.push((uuid.clone(), response.response.rect));
+        if canvas.reveal.as_deref() == Some(uuid.as_str()) {
+            response.response.scroll_to_me(None);
+            canvas.reveal = None;
         }
     }
 
+    pub fn show_contents(&mut self, ui: &mut Ui, canvas: &mut CanvasState) {
+        // Children are drawn in a drop zone that accepts components from the palette
+        show_children(ui, &self.config, canvas);
+    }
+
 
     pub fn new(title: String) -> Self {
+        // The flow name doubles as the title so snapshots and generated code can refer to it
+        let config = Arc::new(Mutex::new(
+            BaseConfiguration::default().widget_type("MainFlow".to_string()).name(title.clone()).is_expanded(true),
+        ));
         Self {
             is_expanded: true,
             title: title,
-            config: Arc::new(Mutex::new(
-                BaseConfiguration::default().widget_type("MainFlow".to_string()),
-            )),
+            config,
         }
     }
 
+    // A flow without a source, holding components that flows share
+    pub fn new_sub_flow(title: String) -> Self {
+        let flow = Self::new(title);
+        if let Ok(mut config) = flow.config.lock() {
+            config.widget_type = "SubFlow".to_string();
+        }
+        flow
+    }
+
     pub fn from_base_config(config: Arc<Mutex<BaseConfiguration>>) -> Self {
-        let title = {
+        let (title, is_expanded) = {
             let config_lock = config.lock().unwrap();
-            config_lock.name.clone()
+            (config_lock.name.clone(), config_lock.is_expanded)
         };
         Self {
-            is_expanded: true,
+            is_expanded,
             title,
             config,
         }
     }
 
     pub fn set_expansion(&mut self, expanded: bool) {
+        if let Ok(mut config) = self.config.lock() {
+            config.is_expanded = expanded;
+        }
         self.is_expanded = expanded;
     }
 
     // Method to set the title
     pub fn set_title(&mut self, new_title: String) {
+        if let Ok(mut config) = self.config.lock() {
+            config.name = new_title.clone();
+        }
         self.title = new_title;
     }
 
     // Function to toggle expansion/collapse
     pub fn toggle_expansion(&mut self) {
-        self.is_expanded = !self.is_expanded;
+        self.set_expansion(!self.is_expanded);
     }
 
     // Add child widget to the MainFlow if it meets the requirements (ComponentLevel::ChildOnly)
@@ -165,6 +177,25 @@ impl MainFlow {
 
 }
 
-
-
-
+// Live state badges of a running flow, with a button to start or stop it
+fn show_flow_state(ui: &mut Ui, name: &str, runtime: &Arc<Runtime>) {
+    let state = match runtime.flow_state(name) {
+        Some(state) => state,
+        None => return,
+    };
+    if state.running {
+        ui.label(egui::RichText::new("\u{25CF} running").small().color(Color32::from_rgb(40, 180, 90)));
+        if ui.small_button("\u{23F9}").on_hover_text("Stop this flow").clicked() {
+            runtime.stop_flow(name);
+        }
+    } else {
+        ui.label(egui::RichText::new("\u{25A0} stopped").small().color(Color32::GRAY));
+        if ui.small_button("\u{25B6}").on_hover_text("Start this flow").clicked() {
+            runtime.start_flow(name);
+        }
+    }
+    ui.label(egui::RichText::new(format!("in flight {}", state.in_flight)).small());
+    if state.queued > 0 {
+        ui.label(egui::RichText::new(format!("queued {}", state.queued)).small().color(Color32::from_rgb(230, 140, 0)));
+    }
+}
@@ -0,0 +1,73 @@
+use egui::scroll_area::ScrollAreaOutput;
+use egui::{Color32, CursorIcon, Rect, Sense, Stroke, Ui, Vec2, vec2};
+use crate::components::component_view::{CanvasState, PROBLEM_COLOR, REPLAY_COLOR};
+
+const MAX_SIZE: Vec2 = vec2(160.0, 200.0);
+const MARGIN: f32 = 16.0; // Keeps the minimap clear of the scroll bar
+
+// Overview of a pane's canvas in its bottom right corner: a block per flow and component drawn
+// this frame, components with problems in red, the replayed step in green, and the visible part
+// as a rectangle that pans the canvas when dragged. Clicking elsewhere centers the view there.
+pub(crate) fn show_minimap<R>(ui: &mut Ui, scroll: &ScrollAreaOutput<R>, canvas: &CanvasState) {
+    let content = scroll.content_size;
+    let viewport = scroll.inner_rect;
+    if canvas.blocks.is_empty() || content.x <= 0.0 || content.y <= 0.0 {
+        return;
+    }
+    let scale = (MAX_SIZE.x / content.x).min(MAX_SIZE.y / content.y);
+    let size = content * scale;
+    let map = Rect::from_min_size(viewport.right_bottom() - size - Vec2::splat(MARGIN), size);
+    if !viewport.contains_rect(map) {
+        return;
+    }
+    let offset = scroll.state.offset;
+    // Blocks were recorded in screen positions for this frame's scroll offset
+    let to_map = |rect: Rect| Rect::from_min_max(
+        map.min + (rect.min - viewport.min + offset) * scale,
+        map.min + (rect.max - viewport.min + offset) * scale,
+    ).intersect(map);
+    let visible = Rect::from_min_size(map.min + offset * scale, viewport.size() * scale).intersect(map);
+
+    let response = ui.interact(map, ui.id().with("minimap"), Sense::click_and_drag());
+    let painter = ui.painter_at(map);
+    let visuals = ui.visuals();
+    painter.rect(map, 2.0, visuals.extreme_bg_color.gamma_multiply(0.9), visuals.widgets.noninteractive.bg_stroke);
+    let block_color = visuals.widgets.inactive.bg_fill;
+    let selected_color = visuals.selection.bg_fill;
+    for (uuid, rect) in &canvas.blocks {
+        let fill = if canvas.selected.as_deref() == Some(uuid.as_str()) { selected_color } else { block_color };
+        painter.rect(to_map(*rect), 1.0, fill.gamma_multiply(0.6), Stroke::new(0.5, visuals.weak_text_color()));
+    }
+    // Markers go on top so nested components do not hide them
+    for (uuid, rect) in &canvas.blocks {
+        if canvas.problems.contains_key(uuid) {
+            painter.rect_stroke(to_map(*rect), 1.0, Stroke::new(1.5, PROBLEM_COLOR));
+        }
+        if canvas.replay_component.as_deref() == Some(uuid.as_str()) {
+            painter.rect_filled(to_map(*rect), 1.0, REPLAY_COLOR);
+        }
+    }
+    painter.rect(visible, 1.0, Color32::TRANSPARENT, Stroke::new(1.5, visuals.strong_text_color()));
+
+    let mut pan = offset;
+    let pointer = response.interact_pointer_pos();
+    if response.drag_started() || response.clicked() {
+        if let Some(pointer) = pointer.filter(|pointer| !visible.contains(*pointer)) {
+            pan = (pointer - map.min) / scale - viewport.size() / 2.0;
+        }
+    }
+    if response.dragged() {
+        pan += response.drag_delta() / scale;
+    }
+    if response.hovered() {
+        ui.ctx().set_cursor_icon(if response.dragged() { CursorIcon::Grabbing } else { CursorIcon::Grab });
+    }
+    let max = (content - viewport.size()).max(Vec2::ZERO);
+    let pan = pan.clamp(Vec2::ZERO, max);
+    if pan != offset {
+        let mut state = scroll.state;
+        state.offset = pan;
+        state.store(ui.ctx(), scroll.id);
+        ui.ctx().request_repaint();
+    }
+}
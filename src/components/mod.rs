@@ -1,3 +1,282 @@
+pub mod async_scope;
+pub mod cache;
 pub mod choice;
+pub mod consume;
+pub mod database;
+pub mod file;
+pub mod for_each;
+pub mod http_listener;
+pub mod http_request;
+pub mod idempotent_message_validator;
+pub mod logger;
 pub mod main_flow;
+pub mod on_new_file;
+pub mod parallel_for_each;
+pub mod publish;
+pub mod queue_listener;
+pub mod remove_variable;
+pub mod scatter_gather;
+pub mod scheduler;
+pub mod set_payload;
+pub mod set_variable;
+pub mod transformer;
+pub mod until_successful;
+pub(crate) mod command_palette;
+pub(crate) mod component_view;
 pub(crate) mod draggable_item;
+pub(crate) mod find_replace;
+pub(crate) mod global_elements;
+pub(crate) mod inspector;
+pub(crate) mod minimap;
+pub(crate) mod object_stores;
+pub(crate) mod problems;
+pub(crate) mod project_explorer;
+pub(crate) mod recovery_prompt;
+pub(crate) mod run_history;
+pub(crate) mod tabs;
+pub(crate) mod variables;
+
+use std::sync::{Arc, Mutex};
+use crate::base_configuration::BaseConfiguration;
+use crate::components::component_view::place_child;
+use crate::components::inspector::PropertyField;
+
+// Creates the configuration of a component dropped from the palette
+pub(crate) fn create_component(name: &str) -> Option<BaseConfiguration> {
+    match name {
+        "Async" => Some(async_scope::async_scope::new_config()),
+        "Cache" => Some(cache::cache::new_config()),
+        "Choice" => Some(choice::choice::new_config()),
+        "Consume" => Some(consume::consume::new_config()),
+        "Database" => Some(database::database::new_config()),
+        "File" => Some(file::file::new_config()),
+        "ForEach" => Some(for_each::for_each::new_config()),
+        "HttpListener" => Some(http_listener::http_listener::new_config()),
+        "HttpRequest" => Some(http_request::http_request::new_config()),
+        "IdempotentMessageValidator" => Some(idempotent_message_validator::idempotent_message_validator::new_config()),
+        "Logger" => Some(logger::logger::new_config()),
+        "OnNewFile" => Some(on_new_file::on_new_file::new_config()),
+        "ParallelForEach" => Some(parallel_for_each::parallel_for_each::new_config()),
+        "Publish" => Some(publish::publish::new_config()),
+        "QueueListener" => Some(queue_listener::queue_listener::new_config()),
+        "RemoveVariable" => Some(remove_variable::remove_variable::new_config()),
+        "ScatterGather" => Some(scatter_gather::scatter_gather::new_config()),
+        "Scheduler" => Some(scheduler::scheduler::new_config()),
+        "SetPayload" => Some(set_payload::set_payload::new_config()),
+        "SetVariable" => Some(set_variable::set_variable::new_config()),
+        "Transformer" => Some(transformer::transformer::new_config()),
+        "UntilSuccessful" => Some(until_successful::until_successful::new_config()),
+        _ => None,
+    }
+}
+
+// Global element types with the label the editor shows for them
+pub(crate) const GLOBAL_TYPES: &[(&str, &str)] = &[
+    ("BrokerConfig", "Message broker config"),
+    ("DatabaseConfig", "Database config"),
+    ("HttpListenerConfig", "HTTP Listener config"),
+    ("HttpRequestConfig", "HTTP Request config"),
+    ("ObjectStore", "Object store"),
+    ("RetryPolicy", "Retry policy"),
+];
+
+// Creates a global element; `name` is how components reference it
+pub(crate) fn create_global(widget_type: &str, name: String) -> Option<BaseConfiguration> {
+    match widget_type {
+        "BrokerConfig" => Some(publish::publish::new_global_config(name)),
+        "DatabaseConfig" => Some(database::database::new_global_config(name)),
+        "HttpListenerConfig" => Some(http_listener::http_listener::new_global_config(name)),
+        "HttpRequestConfig" => Some(http_request::http_request::new_global_config(name)),
+        "ObjectStore" => Some(cache::cache::new_object_store(name)),
+        "RetryPolicy" => Some(until_successful::until_successful::new_retry_policy(name)),
+        _ => None,
+    }
+}
+
+// Properties the inspector offers for a component type
+pub(crate) fn property_fields(widget_type: &str) -> &'static [PropertyField] {
+    match widget_type {
+        "Async" => async_scope::async_scope::PROPERTIES,
+        "BrokerConfig" => publish::publish::CONFIG_PROPERTIES,
+        "Cache" => cache::cache::PROPERTIES,
+        "Consume" => consume::consume::PROPERTIES,
+        "Database" => database::database::PROPERTIES,
+        "DatabaseConfig" => database::database::CONFIG_PROPERTIES,
+        "File" => file::file::PROPERTIES,
+        "ForEach" => for_each::for_each::PROPERTIES,
+        "HttpListener" => http_listener::http_listener::PROPERTIES,
+        "HttpListenerConfig" => http_listener::http_listener::CONFIG_PROPERTIES,
+        "HttpRequest" => http_request::http_request::PROPERTIES,
+        "HttpRequestConfig" => http_request::http_request::CONFIG_PROPERTIES,
+        "IdempotentMessageValidator" => idempotent_message_validator::idempotent_message_validator::PROPERTIES,
+        "Logger" => logger::logger::PROPERTIES,
+        "ObjectStore" => cache::cache::STORE_PROPERTIES,
+        "OnNewFile" => on_new_file::on_new_file::PROPERTIES,
+        "ParallelForEach" => parallel_for_each::parallel_for_each::PROPERTIES,
+        "Publish" => publish::publish::PROPERTIES,
+        "QueueListener" => queue_listener::queue_listener::PROPERTIES,
+        "RemoveVariable" => remove_variable::remove_variable::PROPERTIES,
+        "RetryPolicy" => until_successful::until_successful::POLICY_PROPERTIES,
+        "Route" => scatter_gather::scatter_gather::ROUTE_PROPERTIES,
+        "ScatterGather" => scatter_gather::scatter_gather::PROPERTIES,
+        "Scheduler" => scheduler::scheduler::PROPERTIES,
+        "SetPayload" => set_payload::set_payload::PROPERTIES,
+        "SetVariable" => set_variable::set_variable::PROPERTIES,
+        "UntilSuccessful" => until_successful::until_successful::PROPERTIES,
+        _ => &[],
+    }
+}
+
+// Finds a component by uuid anywhere below the given roots
+pub(crate) fn find_component(roots: &[Arc<Mutex<BaseConfiguration>>], uuid: &str) -> Option<Arc<Mutex<BaseConfiguration>>> {
+    for root in roots {
+        let children = match root.lock() {
+            Ok(config) if config.uuid == uuid => return Some(root.clone()),
+            Ok(config) => config.children.clone(),
+            Err(_) => continue,
+        };
+        if let Some(found) = find_component(&children, uuid) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+// Finds the container holding a component
+fn find_parent(roots: &[Arc<Mutex<BaseConfiguration>>], uuid: &str) -> Option<Arc<Mutex<BaseConfiguration>>> {
+    for root in roots {
+        let children = match root.lock() {
+            Ok(config) => config.children.clone(),
+            Err(_) => continue,
+        };
+        if children.iter().any(|child| child.lock().is_ok_and(|child| child.uuid == uuid)) {
+            return Some(root.clone());
+        }
+        if let Some(found) = find_parent(&children, uuid) {
+            return Some(found);
+        }
+    }
+    None
+}
+
+// Expands every container around a component so that it is drawn; returns whether it was found
+pub(crate) fn expand_to(roots: &[Arc<Mutex<BaseConfiguration>>], uuid: &str) -> bool {
+    for root in roots {
+        let children = match root.lock() {
+            Ok(config) if config.uuid == uuid => return true,
+            Ok(config) => config.children.clone(),
+            Err(_) => continue,
+        };
+        if expand_to(&children, uuid) {
+            if let Ok(mut config) = root.lock() {
+                config.is_expanded = true;
+            }
+            return true;
+        }
+    }
+    false
+}
+
+// Moves a component to the end of another container, possibly in another flow; sources go first
+// and only into a flow without another source
+pub(crate) fn move_component(roots: &[Arc<Mutex<BaseConfiguration>>], uuid: &str, target_uuid: &str) -> Result<(), String> {
+    let component = find_component(roots, uuid).ok_or_else(|| "The component no longer exists".to_string())?;
+    if find_component(std::slice::from_ref(&component), target_uuid).is_some() {
+        return Err("A component cannot move into itself".to_string());
+    }
+    let target = find_component(roots, target_uuid).ok_or_else(|| "The container no longer exists".to_string())?;
+    let parent = find_parent(roots, uuid).ok_or_else(|| "The component has no container".to_string())?;
+    let name = component.lock().map(|config| config.widget_type.clone()).map_err(|_| "The component is unavailable".to_string())?;
+    // Taken out first, so that a source can move within its own flow
+    let index = {
+        let mut parent = parent.lock().map_err(|_| "The container is unavailable".to_string())?;
+        let index = parent.children.iter().position(|child| Arc::ptr_eq(child, &component));
+        parent.children.retain(|child| !Arc::ptr_eq(child, &component));
+        index
+    };
+    let (placed, target_type) = match target.lock() {
+        Ok(mut target) => (place_child(&mut target, component.clone(), None), target.widget_type.clone()),
+        Err(_) => (false, String::new()),
+    };
+    if !placed {
+        if let (Ok(mut parent), Some(index)) = (parent.lock(), index) {
+            parent.children.insert(index, component);
+        }
+        return Err(format!("{} cannot be dropped into {}", name, target_type));
+    }
+    Ok(())
+}
+
+// Where the keyboard moves the selection
+#[derive(Clone, Copy)]
+pub(crate) enum Step {
+    Previous, // Up the flow, in reading order
+    Next,
+    Container,
+    FirstChild,
+}
+
+// Components of a tree in reading order, each with its container's uuid
+fn reading_order(root: &Arc<Mutex<BaseConfiguration>>, parent: Option<String>, order: &mut Vec<(String, Option<String>)>) {
+    let (uuid, children) = match root.lock() {
+        Ok(config) if !config.is_deleted => (config.uuid.clone(), config.children.clone()),
+        _ => return,
+    };
+    order.push((uuid.clone(), parent));
+    for child in &children {
+        reading_order(child, Some(uuid.clone()), order);
+    }
+}
+
+// The component a keyboard step leads to from the selected one, within its flow
+pub(crate) fn step_from(flows: &[Arc<Mutex<BaseConfiguration>>], uuid: &str, step: Step) -> Option<String> {
+    for flow in flows {
+        let mut order = Vec::new();
+        reading_order(flow, None, &mut order);
+        let position = match order.iter().position(|(component, _)| component == uuid) {
+            Some(position) => position,
+            None => continue,
+        };
+        return match step {
+            Step::Previous => position.checked_sub(1).map(|previous| order[previous].0.clone()),
+            Step::Next => order.get(position + 1).map(|(next, _)| next.clone()),
+            Step::Container => order[position].1.clone(),
+            Step::FirstChild => order.iter().find(|(_, parent)| parent.as_deref() == Some(uuid)).map(|(child, _)| child.clone()),
+        };
+    }
+    None
+}
+
+// Inserts a component after the selected one. With a flow or a Choice or Scatter-Gather route
+// selected it goes at the end of it; sources always go first in their flow.
+pub(crate) fn insert_after(flows: &[Arc<Mutex<BaseConfiguration>>], selected: &str, component: BaseConfiguration) -> Result<String, String> {
+    let selected_component = find_component(flows, selected).ok_or_else(|| "Select a component or flow first".to_string())?;
+    let parent = find_parent(flows, selected);
+    let is_route = parent.as_ref()
+        .and_then(|parent| parent.lock().ok().map(|parent| parent.widget_type == "Choice" || parent.widget_type == "ScatterGather"))
+        .unwrap_or(false);
+    let (container, position) = match parent {
+        Some(parent) if !is_route => {
+            let position = parent.lock().ok()
+                .and_then(|parent| parent.children.iter().position(|child| Arc::ptr_eq(child, &selected_component)))
+                .map(|index| index + 1);
+            (parent, position)
+        }
+        _ => (selected_component, None),
+    };
+    let uuid = component.uuid.clone();
+    let widget_type = component.widget_type.clone();
+    let mut container = container.lock().map_err(|_| "The container is unavailable".to_string())?;
+    if !place_child(&mut container, Arc::new(Mutex::new(component)), position) {
+        return Err(format!("{} cannot be inserted into {}", widget_type, container.widget_type));
+    }
+    Ok(uuid)
+}
+
+// Removes a component from its container; flows are removed from the project explorer
+pub(crate) fn remove_component(flows: &[Arc<Mutex<BaseConfiguration>>], uuid: &str) -> Result<(), String> {
+    let parent = find_parent(flows, uuid).ok_or_else(|| "Flows are deleted from the project explorer".to_string())?;
+    let mut parent = parent.lock().map_err(|_| "The container is unavailable".to_string())?;
+    parent.children.retain(|child| child.lock().map(|child| child.uuid != uuid).unwrap_or(true));
+    Ok(())
+}
@@ -0,0 +1,49 @@
+use egui::{Color32, RichText, ScrollArea, Ui};
+use crate::runtime::Runtime;
+
+// Entries of the object stores the running flows use, one collapsible section per store.
+// Returns the name of the store whose Clear button was clicked.
+pub(crate) fn show_object_stores(ui: &mut Ui, runtime: Option<&Runtime>) -> Option<String> {
+    let stores = runtime.map(Runtime::object_stores).unwrap_or_default();
+    let mut clear = None;
+    ScrollArea::vertical().id_salt("object_stores").auto_shrink([false, false]).show(ui, |ui| {
+        if stores.is_empty() {
+            ui.label(RichText::new("Entries of the object stores used by running flows appear here.").small().weak());
+        }
+        for (name, store) in &stores {
+            let entries = match store.as_ref().map_err(Clone::clone).and_then(|store| store.entries()) {
+                Ok(entries) => entries,
+                Err(error) => {
+                    ui.label(RichText::new(format!("{}: {}", name, error)).color(Color32::RED));
+                    continue;
+                }
+            };
+            egui::CollapsingHeader::new(format!("{} ({} entries)", name, entries.len()))
+                .id_salt(name)
+                .default_open(true)
+                .show(ui, |ui| {
+                    if ui.small_button("Clear store").clicked() {
+                        clear = Some(name.clone());
+                    }
+                    egui::Grid::new(("object_store_entries", name)).striped(true).show(ui, |ui| {
+                        ui.label(RichText::new("Key").small().weak());
+                        ui.label(RichText::new("Value").small().weak());
+                        ui.label(RichText::new("Stored").small().weak());
+                        ui.label(RichText::new("Expires").small().weak());
+                        ui.end_row();
+                        for entry in &entries {
+                            let time = |at: chrono::DateTime<chrono::Utc>| at.with_timezone(&chrono::Local).format("%H:%M:%S").to_string();
+                            ui.label(RichText::new(&entry.key).monospace());
+                            let value = entry.value.to_string();
+                            let shown: String = value.chars().take(120).collect();
+                            ui.label(RichText::new(shown).monospace()).on_hover_text(value);
+                            ui.label(time(entry.stored_at));
+                            ui.label(entry.expires_at.map(time).unwrap_or_else(|| "never".to_string()));
+                            ui.end_row();
+                        }
+                    });
+                });
+        }
+    });
+    clear
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod on_new_file;
\ No newline at end of file
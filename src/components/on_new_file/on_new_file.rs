@@ -0,0 +1,19 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Source that polls a directory and runs its flow once per new file, oldest first, with the file
+// as the message. Each file is remembered by name, size and modification time so it is not picked
+// up twice.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("directory", "Directory", PropertyKind::Text, "data/inbox"),
+    PropertyField::new("pattern", "File name pattern", PropertyKind::Text, "*"),
+    PropertyField::new("polling_frequency", "Polling frequency (ms)", PropertyKind::Number, "1000"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("OnNewFile".to_string())
+        .name("On New File".to_string())
+        .component_level(ComponentLevel::SourceOnly);
+    with_defaults(config, PROPERTIES)
+}
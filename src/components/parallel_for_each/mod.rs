@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod parallel_for_each;
\ No newline at end of file
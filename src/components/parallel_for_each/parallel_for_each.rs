@@ -0,0 +1,21 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Scope that runs its components for every element of a collection concurrently, each on a copy
+// of the message. The payload afterwards is the array of route results in collection order;
+// variables set inside do not leak out. Any failed or timed out route fails the whole scope; the
+// timeout applies to each element from when its route starts, and a hung route is not waited for.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("collection", "Collection", PropertyKind::Expression, "payload"),
+    PropertyField::new("max_concurrency", "Max concurrency", PropertyKind::Number, "4"),
+    PropertyField::new("timeout", "Timeout per element in ms (empty for none)", PropertyKind::Number, ""),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("ParallelForEach".to_string())
+        .name("Parallel For Each".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,68 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use crate::base_configuration::BaseConfiguration;
+use crate::components::inspector::PropertyKind;
+use crate::components::property_fields;
+use crate::expression::{embedded_expression, parse};
+
+fn expression_problem(what: &str, source: &str) -> Option<String> {
+    parse(source).err().map(|error| format!("{}: {}", what, error))
+}
+
+// Problems the editor spots without running a component: expressions that do not parse, numbers
+// that are not numbers and global elements that do not exist
+pub(crate) fn component_problems(config: &BaseConfiguration, globals: &[Arc<Mutex<BaseConfiguration>>]) -> Vec<String> {
+    let mut problems = Vec::new();
+    for field in property_fields(&config.widget_type) {
+        let value = config.get_property(field.key);
+        match field.kind {
+            PropertyKind::Expression => problems.extend(expression_problem(field.label, value)),
+            PropertyKind::Text => problems.extend(embedded_expression(value).and_then(|source| expression_problem(field.label, source))),
+            // Placeholders are resolved when the flows run
+            PropertyKind::Number if !value.trim().is_empty() && !value.contains("${") && value.trim().parse::<f64>().is_err() => {
+                problems.push(format!("{}: not a number", field.label));
+            }
+            PropertyKind::GlobalRef(_) if !value.is_empty() && !globals.iter().any(|global| global.lock().is_ok_and(|global| global.name == value)) => {
+                problems.push(format!("{}: global element '{}' does not exist", field.label, value));
+            }
+            _ => {}
+        }
+    }
+    for script in &config.transform_script_configs {
+        if let Ok(script) = script.lock() {
+            problems.extend(expression_problem(&format!("Script for {}", script.target), &script.script));
+        }
+    }
+    // Choice conditions belong to the Choice, which is where the inspector edits them
+    for (index, route) in config.children.iter().enumerate() {
+        if let Ok(route) = route.lock() {
+            if route.widget_type == "When" {
+                problems.extend(expression_problem(&format!("When #{}", index + 1), route.get_property("expression")));
+            }
+        }
+    }
+    problems
+}
+
+// Problems of every component below the given roots, by uuid
+pub(crate) fn find_problems(roots: &[Arc<Mutex<BaseConfiguration>>], globals: &[Arc<Mutex<BaseConfiguration>>]) -> HashMap<String, Vec<String>> {
+    let mut found = HashMap::new();
+    collect_problems(roots, globals, &mut found);
+    found
+}
+
+fn collect_problems(roots: &[Arc<Mutex<BaseConfiguration>>], globals: &[Arc<Mutex<BaseConfiguration>>], found: &mut HashMap<String, Vec<String>>) {
+    for root in roots {
+        let children = match root.lock() {
+            Ok(config) if !config.is_deleted => {
+                let problems = component_problems(&config, globals);
+                if !problems.is_empty() {
+                    found.insert(config.uuid.clone(), problems);
+                }
+                config.children.clone()
+            }
+            _ => continue,
+        };
+        collect_problems(&children, globals, found);
+    }
+}
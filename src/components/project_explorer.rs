@@ -0,0 +1,386 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use egui::{CollapsingHeader, RichText, Ui};
+use crate::base_configuration::BaseConfiguration;
+use crate::components::component_view::CanvasState;
+use crate::components::main_flow::main_flow::MainFlow;
+use crate::components::tabs::{Document, Workspace};
+use crate::project::{contains_ignoring_case, create_test_suite, delete_test_suite, file_name_problem, rename_test_suite, test_suites, ConfigFile, Project};
+
+// An entry of the explorer tree that can be renamed
+#[derive(Clone, PartialEq)]
+enum ExplorerItem {
+    File(usize),
+    Flow(String),   // uuid
+    Global(String), // uuid
+    TestSuite(String),
+}
+
+// Explorer state kept between frames
+#[derive(Default)]
+pub(crate) struct ExplorerState {
+    renaming: Option<(ExplorerItem, String)>, // Entry being renamed and its new name
+    removing: Option<usize>,                  // File whose deletion waits for confirmation
+    status: String,                           // Outcome of the last action that could fail
+}
+
+// Changes picked in the tree, applied once it is drawn
+enum Action {
+    NewFile,
+    RemoveFile(usize),
+    NewFlow(usize, bool), // File, sub-flow
+    Open(String),
+    OpenFile(usize),
+    MoveFlow(String, usize),
+    RemoveFlow(String),
+    RemoveGlobal(usize),
+    NewTestSuite,
+    RemoveTestSuite(String),
+    Rename(ExplorerItem, String),
+}
+
+// Inline editor of an entry's new name; Enter applies it and Escape cancels
+fn rename_field(ui: &mut Ui, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    let response = match &mut state.renaming {
+        Some((_, name)) => ui.text_edit_singleline(name),
+        None => return,
+    };
+    response.request_focus();
+    if ui.input(|input| input.key_pressed(egui::Key::Escape)) {
+        state.renaming = None;
+    } else if response.lost_focus() {
+        if let Some((item, name)) = state.renaming.take() {
+            actions.push(Action::Rename(item, name.trim().to_string()));
+        }
+    }
+}
+
+fn is_renaming(state: &ExplorerState, item: &ExplorerItem) -> bool {
+    state.renaming.as_ref().is_some_and(|(renaming, _)| renaming == item)
+}
+
+fn show_flow(ui: &mut Ui, project: &Project, file_index: usize, flow: &MainFlow, canvas: &CanvasState, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    let (uuid, name, widget_type) = match flow.config.lock() {
+        Ok(config) => (config.uuid.clone(), config.name.clone(), config.widget_type.clone()),
+        Err(_) => return,
+    };
+    let item = ExplorerItem::Flow(uuid.clone());
+    if is_renaming(state, &item) {
+        rename_field(ui, state, actions);
+        return;
+    }
+    let icon = if widget_type == "SubFlow" { "\u{21AA}" } else { "\u{25B6}" };
+    let selected = canvas.selected.as_deref() == Some(uuid.as_str());
+    let response = ui.selectable_label(selected, format!("{} {}", icon, name))
+        .on_hover_text(if widget_type == "SubFlow" { "Sub-flow" } else { "Flow" });
+    if response.clicked() {
+        actions.push(Action::Open(uuid.clone()));
+    }
+    response.context_menu(|ui| {
+        if ui.button("Open").clicked() {
+            actions.push(Action::Open(uuid.clone()));
+            ui.close_menu();
+        }
+        if ui.button("Rename").clicked() {
+            state.renaming = Some((item.clone(), name.clone()));
+            ui.close_menu();
+        }
+        if project.files.len() > 1 {
+            ui.menu_button("Move to", |ui| {
+                for (target, file) in project.files.iter().enumerate().filter(|(target, _)| *target != file_index) {
+                    if ui.button(&file.name).clicked() {
+                        actions.push(Action::MoveFlow(uuid.clone(), target));
+                        ui.close_menu();
+                    }
+                }
+            });
+        }
+        if ui.button("Delete").clicked() {
+            actions.push(Action::RemoveFlow(uuid.clone()));
+            ui.close_menu();
+        }
+    });
+}
+
+fn show_files(ui: &mut Ui, project: &Project, canvas: &CanvasState, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    for (file_index, file) in project.files.iter().enumerate() {
+        let item = ExplorerItem::File(file_index);
+        if is_renaming(state, &item) {
+            rename_field(ui, state, actions);
+            continue;
+        }
+        let response = CollapsingHeader::new(format!("\u{1F5CE} {}", file.name))
+            .id_salt(("config_file", file_index))
+            .default_open(true)
+            .show(ui, |ui| {
+                if file.flows.is_empty() {
+                    ui.label(RichText::new("No flows").small().weak());
+                }
+                for flow in &file.flows {
+                    show_flow(ui, project, file_index, flow, canvas, state, actions);
+                }
+            });
+        response.header_response.context_menu(|ui| {
+            if ui.button("Open").clicked() {
+                actions.push(Action::OpenFile(file_index));
+                ui.close_menu();
+            }
+            if ui.button("New flow").clicked() {
+                actions.push(Action::NewFlow(file_index, false));
+                ui.close_menu();
+            }
+            if ui.button("New sub-flow").clicked() {
+                actions.push(Action::NewFlow(file_index, true));
+                ui.close_menu();
+            }
+            if ui.button("Rename").clicked() {
+                state.renaming = Some((item.clone(), file.name.clone()));
+                ui.close_menu();
+            }
+            if project.files.len() > 1 {
+                let label = match file.flows.len() {
+                    0 => "Delete".to_string(),
+                    count => format!("Delete with {} flows", count),
+                };
+                if ui.button(label).clicked() {
+                    state.removing = Some(file_index);
+                    ui.close_menu();
+                }
+            }
+        });
+        if state.removing == Some(file_index) {
+            confirm_remove_file(ui, file, file_index, state, actions);
+        }
+    }
+}
+
+// Deleting a file also deletes its saved copy and cannot be undone, so it is confirmed first
+fn confirm_remove_file(ui: &mut Ui, file: &ConfigFile, file_index: usize, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    let what = match file.flows.len() {
+        0 => format!("Delete '{}' from disk?", file.name),
+        1 => format!("Delete '{}' and its flow from disk?", file.name),
+        count => format!("Delete '{}' and its {} flows from disk?", file.name, count),
+    };
+    ui.label(RichText::new(what).small().color(egui::Color32::from_rgb(230, 160, 0)));
+    ui.horizontal(|ui| {
+        if ui.small_button("Delete").clicked() {
+            actions.push(Action::RemoveFile(file_index));
+            state.removing = None;
+        }
+        if ui.small_button("Cancel").clicked() {
+            state.removing = None;
+        }
+    });
+}
+
+fn show_globals(ui: &mut Ui, globals: &[Arc<Mutex<BaseConfiguration>>], canvas: &CanvasState, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    if globals.is_empty() {
+        ui.label(RichText::new("Add global elements in the right panel.").small().weak());
+    }
+    for (index, global) in globals.iter().enumerate() {
+        let (uuid, name, widget_type) = match global.lock() {
+            Ok(global) => (global.uuid.clone(), global.name.clone(), global.widget_type.clone()),
+            Err(_) => continue,
+        };
+        let item = ExplorerItem::Global(uuid.clone());
+        if is_renaming(state, &item) {
+            rename_field(ui, state, actions);
+            continue;
+        }
+        let selected = canvas.selected.as_deref() == Some(uuid.as_str());
+        let response = ui.selectable_label(selected, format!("\u{2699} {}", name)).on_hover_text(widget_type);
+        if response.clicked() {
+            actions.push(Action::Open(uuid.clone()));
+        }
+        response.context_menu(|ui| {
+            if ui.button("Rename").clicked() {
+                state.renaming = Some((item.clone(), name.clone()));
+                ui.close_menu();
+            }
+            if ui.button("Delete").clicked() {
+                actions.push(Action::RemoveGlobal(index));
+                ui.close_menu();
+            }
+        });
+    }
+}
+
+fn show_test_suites(ui: &mut Ui, project_folder: &Path, state: &mut ExplorerState, actions: &mut Vec<Action>) {
+    let suites = test_suites(project_folder);
+    if suites.is_empty() {
+        ui.label(RichText::new("No test suites in tests/").small().weak());
+    }
+    for suite in suites {
+        let item = ExplorerItem::TestSuite(suite.clone());
+        if is_renaming(state, &item) {
+            rename_field(ui, state, actions);
+            continue;
+        }
+        let response = ui.add(egui::Label::new(format!("\u{2714} {}", suite)).sense(egui::Sense::click()));
+        response.context_menu(|ui| {
+            if ui.button("Rename").clicked() {
+                state.renaming = Some((item.clone(), suite.clone()));
+                ui.close_menu();
+            }
+            if ui.button("Delete").clicked() {
+                actions.push(Action::RemoveTestSuite(suite.clone()));
+                ui.close_menu();
+            }
+        });
+    }
+}
+
+// Applies a rename, refusing empty names, names already taken and, for what is stored as a file,
+// names that are not a plain file name
+fn rename(project: &mut Project, globals: &[Arc<Mutex<BaseConfiguration>>], project_folder: &Path, item: ExplorerItem, name: String) -> Result<(), String> {
+    if name.is_empty() {
+        return Err("Names cannot be empty".to_string());
+    }
+    match item {
+        ExplorerItem::File(index) => {
+            if project.files.get(index).is_some_and(|file| file.name == name) {
+                return Ok(());
+            }
+            if let Some(problem) = file_name_problem(&name) {
+                return Err(problem);
+            }
+            // Files differing only by case would be one file on Windows and macOS
+            let others: Vec<String> = project.files.iter().enumerate()
+                .filter(|(other, _)| *other != index)
+                .map(|(_, file)| file.name.clone())
+                .collect();
+            if contains_ignoring_case(&others, &name) {
+                return Err(format!("A configuration file named '{}' exists", name));
+            }
+            if let Some(file) = project.files.get_mut(index) {
+                file.name = name;
+            }
+        }
+        ExplorerItem::Flow(uuid) => {
+            let (file, position) = project.locate(&uuid).ok_or_else(|| "The flow no longer exists".to_string())?;
+            let config = project.files[file].flows[position].config.clone();
+            let current = config.lock().map(|config| config.name.clone()).unwrap_or_default();
+            if current != name && project.flow_names().contains(&name) {
+                return Err(format!("A flow named '{}' exists", name));
+            }
+            let renamed = config.lock().map(|mut config| config.name = name);
+            renamed.map_err(|_| "The flow is unavailable".to_string())?;
+        }
+        ExplorerItem::Global(uuid) => {
+            let taken = globals.iter().any(|global| global.lock().is_ok_and(|global| global.name == name && global.uuid != uuid));
+            if taken {
+                return Err(format!("A global element named '{}' exists", name));
+            }
+            if let Some(global) = globals.iter().find(|global| global.lock().is_ok_and(|global| global.uuid == uuid)) {
+                project.rename_global(global, &name);
+            }
+        }
+        ExplorerItem::TestSuite(suite) => rename_test_suite(project_folder, &suite, &name).map_err(|error| error.to_string())?,
+    }
+    Ok(())
+}
+
+// Configuration files with their flows and sub-flows, global elements and test suites. Clicking
+// a flow opens it in a tab; right-click an entry to open a whole file, create, rename, move or
+// delete.
+pub(crate) fn show_project_explorer(
+    ui: &mut Ui,
+    project: &mut Project,
+    globals: &mut Vec<Arc<Mutex<BaseConfiguration>>>,
+    canvas: &mut CanvasState,
+    workspace: &mut Workspace,
+    project_folder: &Path,
+    state: &mut ExplorerState,
+) {
+    let mut actions = Vec::new();
+    CollapsingHeader::new("Configuration files").default_open(true).show(ui, |ui| {
+        show_files(ui, project, canvas, state, &mut actions);
+        if ui.small_button("+ Configuration file").clicked() {
+            actions.push(Action::NewFile);
+        }
+    });
+    CollapsingHeader::new("Global elements").default_open(true).show(ui, |ui| {
+        show_globals(ui, globals, canvas, state, &mut actions);
+    });
+    CollapsingHeader::new("Test suites").default_open(true).show(ui, |ui| {
+        show_test_suites(ui, project_folder, state, &mut actions);
+        if ui.small_button("+ Test suite").clicked() {
+            actions.push(Action::NewTestSuite);
+        }
+    });
+    if !state.status.is_empty() {
+        ui.label(RichText::new(&state.status).small().weak());
+    }
+
+    for action in actions {
+        let result = match action {
+            Action::NewFile => {
+                let name = project.unique_file_name();
+                let index = project.add_file(name.clone());
+                state.renaming = Some((ExplorerItem::File(index), name));
+                Ok(())
+            }
+            Action::RemoveFile(index) => project.remove_file(index, project_folder).map_err(|error| error.to_string()),
+            Action::NewFlow(file, sub_flow) => {
+                let flow = if sub_flow {
+                    MainFlow::new_sub_flow(project.unique_flow_name("SubFlow"))
+                } else {
+                    MainFlow::new(project.unique_flow_name("MainFlow"))
+                };
+                let uuid = match flow.config.lock() {
+                    Ok(mut config) => {
+                        config.project_folder = project_folder.display().to_string();
+                        config.uuid.clone()
+                    }
+                    Err(_) => continue,
+                };
+                project.add_flow(file, flow);
+                project.active_file = file;
+                workspace.open(project, Document::Flow(uuid.clone()));
+                canvas.selected = Some(uuid);
+                Ok(())
+            }
+            Action::Open(uuid) => {
+                if let Some((file, _)) = project.locate(&uuid) {
+                    project.active_file = file;
+                    workspace.open(project, Document::Flow(uuid.clone()));
+                }
+                canvas.selected = Some(uuid);
+                Ok(())
+            }
+            Action::OpenFile(index) => {
+                if let Some(file) = project.files.get(index) {
+                    let document = Document::File(file.id.clone());
+                    project.active_file = index;
+                    workspace.open(project, document);
+                }
+                Ok(())
+            }
+            Action::MoveFlow(uuid, target) => {
+                project.move_flow(&uuid, target);
+                Ok(())
+            }
+            Action::RemoveFlow(uuid) => {
+                project.remove_flow(&uuid);
+                Ok(())
+            }
+            Action::RemoveGlobal(index) => {
+                if index < globals.len() {
+                    globals.remove(index);
+                }
+                Ok(())
+            }
+            Action::NewTestSuite => {
+                let suites = test_suites(project_folder);
+                let name = (1..).map(|number| format!("suite-{}", number)).find(|name| !contains_ignoring_case(&suites, name)).unwrap_or_default();
+                create_test_suite(project_folder, &name).map_err(|error| error.to_string())
+            }
+            Action::RemoveTestSuite(suite) => delete_test_suite(project_folder, &suite).map_err(|error| error.to_string()),
+            Action::Rename(item, name) => rename(project, globals, project_folder, item, name),
+        };
+        state.status = match result {
+            Ok(()) => String::new(),
+            Err(error) => error,
+        };
+    }
+}
@@ -0,0 +1,37 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::components::until_successful::until_successful::RETRY_POLICY;
+use crate::support::broker::{BROKERS, DESTINATION_TYPES};
+
+// Operation that sends a message to a queue or topic of its broker. The message keeps flowing
+// unchanged; the sent body carries the message's MIME type.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Broker config", PropertyKind::GlobalRef("BrokerConfig"), ""),
+    PropertyField::new("destination_type", "Destination type", PropertyKind::Options(DESTINATION_TYPES), "Queue"),
+    PropertyField::new("destination", "Destination", PropertyKind::Text, "orders"),
+    PropertyField::new("body", "Body", PropertyKind::Expression, "payload"),
+    PropertyField::new("properties", "Message properties", PropertyKind::Expression, "{}"),
+    RETRY_POLICY,
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Publish".to_string())
+        .name("Publish".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
+
+// Global element choosing the broker behind Publish, Consume and Queue Listener; components with
+// the same config share its queues and topics
+pub(crate) const CONFIG_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("broker", "Broker", PropertyKind::Options(BROKERS), "InMemory"),
+];
+
+pub(crate) fn new_global_config(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("BrokerConfig".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, CONFIG_PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod queue_listener;
\ No newline at end of file
@@ -0,0 +1,23 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::support::broker::{ACK_MODES, DESTINATION_TYPES};
+
+// Source that runs its flow once per message of a queue or topic. With Auto acknowledgement a
+// failed run puts the message back for redelivery; past the maximum redelivery count it moves to
+// the dead letter queue, if any, instead of running again.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("config", "Broker config", PropertyKind::GlobalRef("BrokerConfig"), ""),
+    PropertyField::new("destination_type", "Destination type", PropertyKind::Options(DESTINATION_TYPES), "Queue"),
+    PropertyField::new("destination", "Destination", PropertyKind::Text, "orders"),
+    PropertyField::new("ack_mode", "Acknowledgement mode", PropertyKind::Options(ACK_MODES), "Auto"),
+    PropertyField::new("max_redelivery", "Max redelivery count (-1 for no limit)", PropertyKind::Number, "3"),
+    PropertyField::new("dead_letter_queue", "Dead letter queue", PropertyKind::Text, ""),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("QueueListener".to_string())
+        .name("Queue Listener".to_string())
+        .component_level(ComponentLevel::SourceOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,116 @@
+use std::path::Path;
+use egui::{Color32, Context, RichText, ScrollArea};
+use crate::components::tabs::{Document, Workspace};
+use crate::project::Project;
+use crate::recovery::Recovery;
+
+// Recovery files found at startup that wait for an answer
+#[derive(Default)]
+pub(crate) struct RecoveryPrompt {
+    pub(crate) recoveries: Vec<Recovery>,
+    diff: Option<(String, Vec<(char, String)>)>, // Recovery whose differences are shown, with its diff computed when opened
+}
+
+impl RecoveryPrompt {
+    pub(crate) fn new(recoveries: Vec<Recovery>) -> Self {
+        Self { recoveries, diff: None }
+    }
+
+    pub(crate) fn is_pending(&self) -> bool {
+        !self.recoveries.is_empty()
+    }
+}
+
+enum Answer {
+    Restore(usize),
+    Discard(usize),
+}
+
+// Offers to restore or discard each recovery file, or to compare it with the saved file.
+// Returns the outcome of the answer given.
+pub(crate) fn show_recovery_prompt(
+    ctx: &Context,
+    prompt: &mut RecoveryPrompt,
+    project: &mut Project,
+    workspace: &mut Workspace,
+    project_folder: &Path,
+) -> Option<String> {
+    if !prompt.is_pending() {
+        return None;
+    }
+    let mut answer = None;
+    let mut toggled = None;
+    egui::Window::new("Recover unsaved changes")
+        .collapsible(false)
+        .default_width(480.0)
+        .show(ctx, |ui| {
+            ui.label("The editor closed before these configuration files were saved.");
+            for (index, recovery) in prompt.recoveries.iter().enumerate() {
+                ui.separator();
+                ui.horizontal(|ui| {
+                    ui.label(RichText::new(format!("\u{1F5CE} {}", recovery.name)).strong());
+                    ui.label(RichText::new(format!("autosaved {}", recovery.written.format("%Y-%m-%d %H:%M:%S"))).small().weak());
+                    if recovery.saved.is_none() {
+                        ui.label(RichText::new("never saved").small().weak());
+                    }
+                });
+                ui.horizontal(|ui| {
+                    if ui.button("Restore").on_hover_text("Open the autosaved flows, unsaved").clicked() {
+                        answer = Some(Answer::Restore(index));
+                    }
+                    if ui.button("Discard").on_hover_text("Delete the autosaved copy").clicked() {
+                        answer = Some(Answer::Discard(index));
+                    }
+                    let showing = prompt.diff.as_ref().is_some_and(|(name, _)| *name == recovery.name);
+                    if ui.selectable_label(showing, "Diff").on_hover_text("Compare with the saved file").clicked() {
+                        toggled = Some((index, !showing));
+                    }
+                });
+                if let Some((_, lines)) = prompt.diff.as_ref().filter(|(name, _)| *name == recovery.name) {
+                    show_diff(ui, &recovery.name, lines);
+                }
+            }
+        });
+    match toggled {
+        Some((index, true)) => {
+            let recovery = &prompt.recoveries[index];
+            prompt.diff = Some((recovery.name.clone(), recovery.diff()));
+        }
+        Some((_, false)) => prompt.diff = None,
+        None => {}
+    }
+
+    let result = match answer? {
+        Answer::Restore(index) => {
+            let recovery = prompt.recoveries.remove(index);
+            let file = recovery.restore(project, project_folder);
+            workspace.open(project, Document::File(project.files[file].id.clone()));
+            format!("Restored the unsaved changes to {}", recovery.name)
+        }
+        Answer::Discard(index) => {
+            let recovery = prompt.recoveries.remove(index);
+            match recovery.discard() {
+                Ok(()) => format!("Discarded the autosaved copy of {}", recovery.name),
+                Err(error) => format!("Could not delete {}: {}", recovery.path.display(), error),
+            }
+        }
+    };
+    Some(result)
+}
+
+fn show_diff(ui: &mut egui::Ui, name: &str, lines: &[(char, String)]) {
+    if lines.iter().all(|(change, _)| *change == ' ') {
+        ui.label(RichText::new("No differences with the saved file").small().weak());
+        return;
+    }
+    ScrollArea::vertical().id_salt(("recovery_diff", name)).max_height(300.0).show(ui, |ui| {
+        for (change, line) in lines {
+            let text = RichText::new(format!("{} {}", change, line)).monospace();
+            ui.label(match change {
+                '+' => text.color(Color32::from_rgb(40, 180, 90)),
+                '-' => text.color(Color32::from_rgb(220, 60, 60)),
+                _ => text.weak(),
+            });
+        }
+    });
+}
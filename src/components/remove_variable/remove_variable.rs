@@ -0,0 +1,15 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Removes a flow variable; removing a variable that is not set does nothing
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("variable_name", "Variable name", PropertyKind::Text, "variable"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("RemoveVariable".to_string())
+        .name("Remove Variable".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,143 @@
+use egui::{Color32, RichText, ScrollArea, Ui};
+use serde_json::Value;
+use crate::runtime::tracking::{RunHistory, RunRecord, TrackingEvent};
+
+// Run and step selected in the run history panel; the step's component is highlighted on the canvas
+#[derive(Default)]
+pub(crate) struct ReplayState {
+    pub(crate) run_id: Option<String>,
+    pub(crate) step: usize,
+}
+
+impl ReplayState {
+    // Component of the selected step, if that run is still in the history
+    pub(crate) fn current_component(&self, history: &RunHistory) -> Option<String> {
+        let history = history.lock().ok()?;
+        let run = history.iter().find(|run| Some(&run.id) == self.run_id.as_ref())?;
+        run.events.get(self.step).map(|event| event.component_uuid.clone())
+    }
+}
+
+fn pretty(value: &Value) -> String {
+    serde_json::to_string_pretty(value).unwrap_or_default()
+}
+
+fn show_run_entry(ui: &mut Ui, run: &RunRecord, replay: &mut ReplayState) {
+    let status = if run.error.is_some() { "\u{2716}" } else { "\u{2714}" };
+    let flow = match &run.scope {
+        Some(scope) => format!("\u{21B3} {} \u{203A} {}", run.flow, scope),
+        None => run.flow.clone(),
+    };
+    let label = format!(
+        "{} {} {} ({} ms)",
+        status,
+        run.started.with_timezone(&chrono::Local).format("%H:%M:%S"),
+        flow,
+        run.duration.as_millis()
+    );
+    if ui.selectable_label(replay.run_id.as_ref() == Some(&run.id), label).clicked() {
+        replay.run_id = Some(run.id.clone());
+        replay.step = 0;
+    }
+}
+
+// Runs newest first, each followed by the async runs it started
+fn show_run_list(ui: &mut Ui, runs: &[RunRecord], replay: &mut ReplayState) {
+    if runs.is_empty() {
+        ui.label(RichText::new("Runs of flows with message tracking appear here.").small().weak());
+    }
+    let is_listed = |id: &String| runs.iter().any(|run| &run.id == id);
+    // Async runs whose parent left the history are listed on their own
+    for run in runs.iter().rev().filter(|run| !run.parent_run.as_ref().is_some_and(is_listed)) {
+        show_run_entry(ui, run, replay);
+        ui.indent(&run.id, |ui| {
+            for child in runs.iter().filter(|child| child.parent_run.as_ref() == Some(&run.id)) {
+                show_run_entry(ui, child, replay);
+            }
+        });
+    }
+}
+
+fn show_step(ui: &mut Ui, run: &RunRecord, step: usize, event: &TrackingEvent) {
+    let offset = (event.started - run.started).num_milliseconds();
+    ui.label(RichText::new(format!("{}: {}", event.widget_type, event.component_name)).strong());
+    ui.label(RichText::new(format!("Started at +{} ms, took {} ms", offset, event.duration.as_millis())).small());
+    if let Some(error) = &event.error {
+        ui.label(RichText::new(error.to_string()).color(Color32::RED));
+    }
+    ui.label(RichText::new("Payload").small().weak());
+    ui.label(RichText::new(pretty(&event.payload)).monospace());
+    if !event.variable_changes.is_empty() {
+        ui.label(RichText::new("Variable changes").small().weak());
+        for change in &event.variable_changes {
+            let before = change.before.as_ref().map(Value::to_string).unwrap_or_else(|| "unset".to_string());
+            let after = change.after.as_ref().map(Value::to_string).unwrap_or_else(|| "unset".to_string());
+            ui.label(RichText::new(format!("vars.{}: {} \u{2192} {}", change.name, before, after)).monospace());
+        }
+    }
+    ui.label(RichText::new("Variables").small().weak());
+    ui.label(RichText::new(pretty(&Value::Object(run.variables_at(step)))).monospace());
+}
+
+// Past runs on the left; the selected run's steps can be replayed one at a time on the right
+pub(crate) fn show_run_history(ui: &mut Ui, history: &RunHistory, replay: &mut ReplayState) {
+    let runs: Vec<RunRecord> = history.lock().map(|history| history.iter().cloned().collect()).unwrap_or_default();
+    ui.horizontal_top(|ui| {
+        ui.vertical(|ui| {
+            ui.set_width(240.0);
+            ScrollArea::vertical().id_salt("run_list").show(ui, |ui| show_run_list(ui, &runs, replay));
+        });
+        ui.separator();
+        let run = match runs.iter().find(|run| Some(&run.id) == replay.run_id.as_ref()) {
+            Some(run) => run,
+            None => return,
+        };
+        ui.vertical(|ui| {
+            let last = run.events.len().saturating_sub(1);
+            ui.horizontal(|ui| {
+                if ui.button("\u{23EE}").clicked() {
+                    replay.step = 0;
+                }
+                if ui.button("\u{25C0}").clicked() {
+                    replay.step = replay.step.saturating_sub(1);
+                }
+                ui.label(format!("Step {} of {}", (replay.step + 1).min(run.events.len()), run.events.len()));
+                if ui.button("\u{25B6}").clicked() {
+                    replay.step = (replay.step + 1).min(last);
+                }
+                if ui.button("\u{23ED}").clicked() {
+                    replay.step = last;
+                }
+                if let Some(error) = &run.error {
+                    ui.label(RichText::new(format!("Run failed: {}", error)).color(Color32::RED));
+                }
+                if let Some(parent) = run.parent_run.as_ref().filter(|parent| runs.iter().any(|run| &run.id == *parent)) {
+                    if ui.small_button("Parent run").on_hover_text("Show the run whose Async scope started this one").clicked() {
+                        replay.run_id = Some(parent.clone());
+                        replay.step = 0;
+                    }
+                }
+            });
+            ui.separator();
+            ScrollArea::vertical().id_salt("run_steps").show(ui, |ui| {
+                ui.horizontal_top(|ui| {
+                    ui.vertical(|ui| {
+                        for (index, event) in run.events.iter().enumerate() {
+                            let color = if event.error.is_some() { Color32::RED } else { ui.visuals().text_color() };
+                            let label = RichText::new(format!("{}. {}", index + 1, event.component_name)).color(color);
+                            if ui.selectable_label(replay.step == index, label).clicked() {
+                                replay.step = index;
+                            }
+                        }
+                    });
+                    ui.separator();
+                    ui.vertical(|ui| {
+                        if let Some(event) = run.events.get(replay.step) {
+                            show_step(ui, run, replay.step, event);
+                        }
+                    });
+                });
+            });
+        });
+    });
+}
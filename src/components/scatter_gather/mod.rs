@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod scatter_gather;
\ No newline at end of file
@@ -0,0 +1,33 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Router that sends a copy of the message down every `Route` lane concurrently and waits for all
+// of them. The result payload maps each route index to that route's payload and attributes.
+// A failed or timed out route fails the router with an error listing every failed route.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("max_concurrency", "Max concurrency", PropertyKind::Number, "4"),
+];
+
+// Each route has its own timeout, counted from when the route starts
+pub(crate) const ROUTE_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("timeout", "Timeout in ms (empty for none)", PropertyKind::Number, ""),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("ScatterGather".to_string())
+        .name("Scatter-Gather".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true)
+        .child(new_route(1))
+        .child(new_route(2));
+    with_defaults(config, PROPERTIES)
+}
+
+pub(crate) fn new_route(number: usize) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Route".to_string())
+        .name(format!("Route {}", number))
+        .component_level(ComponentLevel::Other);
+    with_defaults(config, ROUTE_PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod scheduler;
\ No newline at end of file
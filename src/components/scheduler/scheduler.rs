@@ -0,0 +1,22 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Source that starts its flow on a timer. Runs that would exceed the flow's `max_concurrency`
+// are skipped or queued, and flows whose `initial_state` is "stopped" never fire.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("mode", "Mode", PropertyKind::Options(&["Fixed Frequency", "Cron"]), "Fixed Frequency"),
+    PropertyField::new("frequency", "Frequency", PropertyKind::Number, "1000"),
+    PropertyField::new("start_delay", "Start delay", PropertyKind::Number, "0"),
+    PropertyField::new("time_unit", "Time unit", PropertyKind::Options(&["MILLISECONDS", "SECONDS", "MINUTES", "HOURS", "DAYS"]), "MILLISECONDS"),
+    PropertyField::new("expression", "Cron expression (sec min hour day month weekday)", PropertyKind::Text, "0 0 * * * *"),
+    PropertyField::new("timezone", "Timezone (e.g. Europe/Vienna, empty for local)", PropertyKind::Text, ""),
+    PropertyField::new("overlap", "Overlapping runs", PropertyKind::Options(&["Skip", "Queue"]), "Skip"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("Scheduler".to_string())
+        .name("Scheduler".to_string())
+        .component_level(ComponentLevel::SourceOnly);
+    with_defaults(config, PROPERTIES)
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod set_payload;
\ No newline at end of file
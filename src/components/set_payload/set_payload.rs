@@ -0,0 +1,16 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Replaces the payload with the result of an expression; an empty MIME type keeps the current one
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("value", "Value", PropertyKind::Expression, "payload"),
+    PropertyField::new("mime_type", "MIME type", PropertyKind::Text, "application/json"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("SetPayload".to_string())
+        .name("Set Payload".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
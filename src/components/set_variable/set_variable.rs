@@ -0,0 +1,16 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+
+// Stores the result of an expression in a flow variable, readable later as `vars.<name>`
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("variable_name", "Variable name", PropertyKind::Text, "variable"),
+    PropertyField::new("value", "Value", PropertyKind::Expression, "payload"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("SetVariable".to_string())
+        .name("Set Variable".to_string())
+        .component_level(ComponentLevel::ChildOnly);
+    with_defaults(config, PROPERTIES)
+}
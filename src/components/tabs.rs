@@ -0,0 +1,436 @@
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use egui::{Align2, RichText, ScrollArea, Ui, vec2};
+use serde_json::Value;
+use crate::base_configuration::BaseConfiguration;
+use crate::components::component_view::CanvasState;
+use crate::components::main_flow::main_flow::MainFlow;
+use crate::project::{flow_to_json, restore_flow, save_globals, Project};
+use crate::runtime::Runtime;
+
+// Edits closer together than this undo as one step, so typing a name is a single step
+const COALESCE: Duration = Duration::from_millis(1000);
+const UNDO_LIMIT: usize = 100;
+
+// What a tab shows: a single flow or every flow of a configuration file
+#[derive(Clone, PartialEq)]
+pub(crate) enum Document {
+    Flow(String), // Flow uuid
+    File(String), // Configuration file id
+}
+
+pub(crate) struct Tab {
+    pub(crate) document: Document,
+    undo: Vec<Value>, // Earlier states of the document, newest last
+    redo: Vec<Value>,
+    last: Value, // State when last recorded
+    last_change: Option<Instant>,
+    pub(crate) dirty: bool, // Whether the document differed from its saved copy when last recorded
+}
+
+// Open tabs of the central area. The split view shows a second tab next to the active one.
+#[derive(Default)]
+pub(crate) struct Workspace {
+    pub(crate) tabs: Vec<Tab>,
+    pub(crate) active: usize,
+    pub(crate) split: Option<usize>, // Tab shown in the right pane
+    closing: Option<usize>, // Tab waiting for an answer to the unsaved changes prompt
+    // Documents are only compared with their recorded and saved states after an edit: `touch`
+    // moves the revision on and `record` catches up with it
+    revision: u64,
+    recorded: u64,
+    unsaved: bool, // Whether any configuration file had unsaved changes when last recorded
+}
+
+fn file_of(project: &Project, document: &Document) -> Option<usize> {
+    match document {
+        Document::Flow(uuid) => project.locate(uuid).map(|(file, _)| file),
+        Document::File(id) => project.file_index(id),
+    }
+}
+
+// Current state of a document, None once it no longer exists
+fn document_state(project: &Project, document: &Document) -> Option<Value> {
+    match document {
+        Document::Flow(uuid) => project.find_flow(uuid).map(flow_to_json),
+        Document::File(id) => project.file_index(id).map(|file| project.files[file].to_json()),
+    }
+}
+
+fn document_title(project: &Project, document: &Document) -> String {
+    match document {
+        Document::Flow(uuid) => project.find_flow(uuid)
+            .and_then(|flow| flow.config.lock().ok().map(|config| {
+                let icon = if config.widget_type == "SubFlow" { "\u{21AA}" } else { "\u{25B6}" };
+                format!("{} {}", icon, config.name)
+            }))
+            .unwrap_or_default(),
+        Document::File(id) => project.file_index(id)
+            .map(|file| format!("\u{1F5CE} {}", project.files[file].name))
+            .unwrap_or_default(),
+    }
+}
+
+// Whether a document differs from its saved copy
+pub(crate) fn is_dirty(project: &Project, document: &Document) -> bool {
+    let file = match file_of(project, document) {
+        Some(file) => &project.files[file],
+        None => return false,
+    };
+    match document {
+        Document::Flow(uuid) => match (file.saved_flow(uuid), project.find_flow(uuid)) {
+            (Some(saved), Some(flow)) => *saved != flow_to_json(flow),
+            _ => true,
+        },
+        Document::File(_) => file.is_dirty(),
+    }
+}
+
+// Puts a document back into an earlier state
+fn apply_state(project: &mut Project, document: &Document, state: &Value, project_folder: &Path) {
+    match document {
+        Document::Flow(uuid) => {
+            if let Some(flow) = project.find_flow(uuid) {
+                restore_flow(flow, state);
+            }
+        }
+        Document::File(id) => {
+            let index = match project.file_index(id) {
+                Some(index) => index,
+                None => return,
+            };
+            // Flows moved to other files since come back here only
+            let uuids: Vec<&str> = state.get("flows").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(|flow| flow.get("uuid").and_then(Value::as_str))
+                .collect();
+            for (_, file) in project.files.iter_mut().enumerate().filter(|(other, _)| *other != index) {
+                file.flows.retain(|flow| flow.config.lock().map(|config| !uuids.contains(&config.uuid.as_str())).unwrap_or(true));
+            }
+            let file = &mut project.files[index];
+            if let Some(name) = state.get("name").and_then(Value::as_str) {
+                file.name = name.to_string();
+            }
+            file.restore(state, project_folder);
+        }
+    }
+}
+
+// Saves the configuration file holding a document, with the global elements
+pub(crate) fn save_document(project: &mut Project, globals: &[Arc<Mutex<BaseConfiguration>>], project_folder: &Path, document: &Document) -> io::Result<String> {
+    let index = file_of(project, document).ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "The document no longer exists"))?;
+    project.files[index].save(project_folder)?;
+    save_globals(project_folder, globals)?;
+    Ok(project.files[index].name.clone())
+}
+
+pub(crate) fn save_all(project: &mut Project, globals: &[Arc<Mutex<BaseConfiguration>>], project_folder: &Path) -> io::Result<()> {
+    for file in project.files.iter_mut() {
+        file.save(project_folder)?;
+    }
+    save_globals(project_folder, globals)
+}
+
+// Drops unsaved changes: saved documents go back to their saved state, unsaved ones are removed
+fn discard(project: &mut Project, document: &Document, project_folder: &Path) -> io::Result<()> {
+    let index = match file_of(project, document) {
+        Some(index) => index,
+        None => return Ok(()),
+    };
+    match document {
+        Document::Flow(uuid) => match project.files[index].saved_flow(uuid).cloned() {
+            Some(saved) => apply_state(project, document, &saved, project_folder),
+            None => {
+                project.remove_flow(uuid);
+            }
+        },
+        Document::File(_) => {
+            let saved = project.files[index].saved.clone();
+            if saved.is_null() {
+                if project.files.len() > 1 {
+                    return project.remove_file(index, project_folder);
+                }
+                project.files[index].flows.clear();
+            } else {
+                apply_state(project, document, &saved, project_folder);
+            }
+        }
+    }
+    Ok(())
+}
+
+impl Tab {
+    fn new(document: Document, state: Value, dirty: bool) -> Self {
+        Self { document, undo: Vec::new(), redo: Vec::new(), last: state, last_change: None, dirty }
+    }
+}
+
+impl Workspace {
+    // Shows a document, opening a tab for it unless one is open
+    pub(crate) fn open(&mut self, project: &Project, document: Document) {
+        if let Some(index) = self.tabs.iter().position(|tab| tab.document == document) {
+            self.active = index;
+            return;
+        }
+        if let Some(state) = document_state(project, &document) {
+            let dirty = is_dirty(project, &document);
+            self.tabs.push(Tab::new(document, state, dirty));
+            self.active = self.tabs.len() - 1;
+        }
+    }
+
+    pub(crate) fn active_document(&self) -> Option<&Document> {
+        self.tabs.get(self.active).map(|tab| &tab.document)
+    }
+
+    // Configuration file new flows go to: the one of the active tab if any
+    pub(crate) fn active_file(&self, project: &Project) -> usize {
+        self.active_document().and_then(|document| file_of(project, document)).unwrap_or(project.active_file)
+    }
+
+    fn remove(&mut self, index: usize) {
+        if index >= self.tabs.len() {
+            return;
+        }
+        self.tabs.remove(index);
+        if self.active > index || self.active >= self.tabs.len() {
+            self.active = self.active.saturating_sub(1);
+        }
+        self.split = match self.split {
+            Some(split) if split == index => None,
+            Some(split) if split > index => Some(split - 1),
+            split => split,
+        };
+    }
+
+    // Closes a tab, asking first when its document has unsaved changes
+    pub(crate) fn close(&mut self, project: &Project, index: usize) {
+        match self.tabs.get(index) {
+            Some(tab) if is_dirty(project, &tab.document) => self.closing = Some(index),
+            Some(_) => self.remove(index),
+            None => {}
+        }
+    }
+
+    // Notes that documents may have changed, e.g. after a click or key press, so the next `record`
+    // looks at them
+    pub(crate) fn touch(&mut self) {
+        self.revision += 1;
+    }
+
+    // Moves on each time `record` looks at touched documents, so views derived from the whole
+    // project only need computing again when it changes
+    pub(crate) fn recorded_revision(&self) -> u64 {
+        self.recorded
+    }
+
+    pub(crate) fn has_unsaved(&self) -> bool {
+        self.unsaved
+    }
+
+    // Records the changes of every open document in its tab's undo history and refreshes their
+    // unsaved state, if anything was touched since the last time; tabs of removed documents close
+    pub(crate) fn record(&mut self, project: &Project) {
+        if self.recorded == self.revision {
+            return;
+        }
+        self.recorded = self.revision;
+        self.unsaved = project.files.iter().any(|file| file.is_dirty());
+        let mut index = 0;
+        while index < self.tabs.len() {
+            let state = match document_state(project, &self.tabs[index].document) {
+                Some(state) => state,
+                None => {
+                    self.remove(index);
+                    self.closing = None;
+                    continue;
+                }
+            };
+            let dirty = is_dirty(project, &self.tabs[index].document);
+            let tab = &mut self.tabs[index];
+            index += 1;
+            tab.dirty = dirty;
+            if state == tab.last {
+                continue;
+            }
+            let now = Instant::now();
+            let coalesce = !tab.undo.is_empty() && tab.last_change.is_some_and(|last| now.duration_since(last) < COALESCE);
+            let previous = std::mem::replace(&mut tab.last, state);
+            if !coalesce {
+                tab.undo.push(previous);
+                if tab.undo.len() > UNDO_LIMIT {
+                    tab.undo.remove(0);
+                }
+            }
+            tab.redo.clear();
+            tab.last_change = Some(now);
+        }
+    }
+
+    // Applies an edit to a document as an undo step of its own, not merged with the changes around
+    // it. The document's tab opens first when no tab shows it.
+    pub(crate) fn edit(&mut self, project: &Project, document: Document, edit: impl FnOnce()) {
+        let file = file_of(project, &document);
+        let shown = self.tabs.iter().any(|tab| tab.document == document || (matches!(tab.document, Document::File(_)) && file_of(project, &tab.document) == file));
+        if !shown {
+            self.open(project, document);
+        }
+        // Changes made so far stay in their own step
+        self.touch();
+        self.record(project);
+        self.tabs.iter_mut().for_each(|tab| tab.last_change = None);
+        edit();
+        self.touch();
+        self.record(project);
+        self.tabs.iter_mut().for_each(|tab| tab.last_change = None);
+    }
+
+    pub(crate) fn can_undo(&self, index: usize) -> bool {
+        self.tabs.get(index).is_some_and(|tab| !tab.undo.is_empty())
+    }
+
+    pub(crate) fn can_redo(&self, index: usize) -> bool {
+        self.tabs.get(index).is_some_and(|tab| !tab.redo.is_empty())
+    }
+
+    pub(crate) fn undo(&mut self, index: usize, project: &mut Project, project_folder: &Path) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            if let Some(state) = tab.undo.pop() {
+                tab.redo.push(std::mem::replace(&mut tab.last, state.clone()));
+                tab.last_change = None;
+                apply_state(project, &tab.document, &state, project_folder);
+            }
+        }
+        self.touch();
+    }
+
+    pub(crate) fn redo(&mut self, index: usize, project: &mut Project, project_folder: &Path) {
+        if let Some(tab) = self.tabs.get_mut(index) {
+            if let Some(state) = tab.redo.pop() {
+                tab.undo.push(std::mem::replace(&mut tab.last, state.clone()));
+                tab.last_change = None;
+                apply_state(project, &tab.document, &state, project_folder);
+            }
+        }
+        self.touch();
+    }
+}
+
+// Tab strip above the canvas: a dot marks unsaved changes and closing such a tab asks whether to
+// save. Returns the outcome of a save or discard.
+pub(crate) fn show_tab_strip(
+    ui: &mut Ui,
+    workspace: &mut Workspace,
+    project: &mut Project,
+    globals: &[Arc<Mutex<BaseConfiguration>>],
+    project_folder: &Path,
+) -> Option<String> {
+    let mut close = None;
+    ScrollArea::horizontal().id_salt("tab_strip").show(ui, |ui| {
+        ui.horizontal(|ui| {
+            if workspace.tabs.is_empty() {
+                ui.label(RichText::new("Open a flow or configuration file from the project explorer.").small().weak());
+            }
+            for (index, tab) in workspace.tabs.iter().enumerate() {
+                let mut title = document_title(project, &tab.document);
+                if tab.dirty {
+                    title.push_str(" \u{25CF}");
+                }
+                if workspace.split == Some(index) {
+                    title.push_str(" \u{25EB}");
+                }
+                let response = ui.selectable_label(workspace.active == index, title);
+                if response.clicked() {
+                    workspace.active = index;
+                }
+                if response.middle_clicked() || ui.small_button("\u{00D7}").on_hover_text("Close").clicked() {
+                    close = Some(index);
+                }
+                ui.separator();
+            }
+        });
+    });
+    if let Some(index) = close {
+        workspace.close(project, index);
+    }
+    show_close_prompt(ui, workspace, project, globals, project_folder)
+}
+
+// Asks whether to save a tab's unsaved changes before closing it
+fn show_close_prompt(
+    ui: &mut Ui,
+    workspace: &mut Workspace,
+    project: &mut Project,
+    globals: &[Arc<Mutex<BaseConfiguration>>],
+    project_folder: &Path,
+) -> Option<String> {
+    let index = workspace.closing?;
+    let document = workspace.tabs.get(index)?.document.clone();
+    let title = document_title(project, &document);
+    let mut answer = None;
+    egui::Window::new("Unsaved changes")
+        .collapsible(false)
+        .resizable(false)
+        .anchor(Align2::CENTER_CENTER, vec2(0.0, 0.0))
+        .show(ui.ctx(), |ui| {
+            ui.label(format!("Save the changes to {} before closing?", title));
+            if matches!(document, Document::Flow(_)) {
+                ui.label(RichText::new("Saving a flow saves its configuration file.").small().weak());
+            }
+            ui.horizontal(|ui| {
+                if ui.button("Save").clicked() {
+                    answer = Some(true);
+                }
+                if ui.button("Don't save").clicked() {
+                    answer = Some(false);
+                }
+                if ui.button("Cancel").clicked() {
+                    workspace.closing = None;
+                }
+            });
+        });
+    let save = answer?;
+    workspace.closing = None;
+    let result = if save {
+        save_document(project, globals, project_folder, &document).map(|name| format!("Saved {}", name))
+    } else {
+        discard(project, &document, project_folder).map(|()| format!("Discarded the changes to {}", title))
+    };
+    match result {
+        Ok(message) => {
+            workspace.remove(index);
+            Some(message)
+        }
+        Err(error) => Some(format!("Could not save {}: {}", title, error)),
+    }
+}
+
+fn show_flow(ui: &mut Ui, flow: &mut MainFlow, canvas: &mut CanvasState, runtime: Option<&Arc<Runtime>>) {
+    // The inspector edits the configuration name, keep the title in step
+    let title = flow.config.lock().map(|config| config.name.clone()).unwrap_or_default();
+    flow.title = title.clone();
+    flow.show_in_panel(ui, vec2(150.0, 100.0), &title, canvas, runtime);
+}
+
+// Draws the flows of a document
+pub(crate) fn show_document(ui: &mut Ui, project: &mut Project, document: &Document, canvas: &mut CanvasState, runtime: Option<&Arc<Runtime>>) {
+    match document {
+        Document::Flow(uuid) => {
+            if let Some((file, position)) = project.locate(uuid) {
+                show_flow(ui, &mut project.files[file].flows[position], canvas, runtime);
+            }
+        }
+        Document::File(id) => {
+            if let Some(file) = project.file_index(id) {
+                let file = &mut project.files[file];
+                if file.flows.is_empty() {
+                    ui.label(RichText::new("Drop a MainFlow from the palette to add a flow to this file.").small().weak());
+                }
+                for flow in file.flows.iter_mut() {
+                    show_flow(ui, flow, canvas, runtime);
+                }
+            }
+        }
+    }
+}
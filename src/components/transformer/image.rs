@@ -0,0 +1,4 @@
+
+pub fn get_bytes() -> &'static [u8] {
+    include_bytes!("image_48.png")
+}
\ No newline at end of file
@@ -0,0 +1,11 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel, TransformScriptConfig};
+
+// A Transformer evaluates its scripts against the incoming message and writes each result
+// to the payload or to a flow variable
+pub(crate) fn new_config() -> BaseConfiguration {
+    BaseConfiguration::default()
+        .widget_type("Transformer".to_string())
+        .name("Transformer".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .transform_script(TransformScriptConfig::new("", "payload"))
+}
@@ -0,0 +1,2 @@
+pub(crate) mod image;
+pub(crate) mod until_successful;
\ No newline at end of file
@@ -0,0 +1,48 @@
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::inspector::{with_defaults, PropertyField, PropertyKind};
+use crate::support::retry::BACKOFF_STRATEGIES;
+
+// Scope that runs its components again, each time with the message it received, until they
+// succeed. Any error is retried; once the retries run out the run fails with `RETRY:RETRY_EXHAUSTED`.
+pub(crate) const PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("max_retries", "Max retries", PropertyKind::Number, "5"),
+    PropertyField::new("milliseconds_between_retries", "Delay between retries (ms)", PropertyKind::Number, "1000"),
+];
+
+pub(crate) fn new_config() -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("UntilSuccessful".to_string())
+        .name("Until Successful".to_string())
+        .component_level(ComponentLevel::ChildOnly)
+        .is_expanded(true);
+    with_defaults(config, PROPERTIES)
+}
+
+// Lets a connector operation reference a retry policy
+pub(crate) const RETRY_POLICY: PropertyField =
+    PropertyField::new("retry_policy", "Retry policy", PropertyKind::GlobalRef("RetryPolicy"), "");
+
+// Global element retrying the operations that reference it when they fail with a retryable
+// error. Error types are comma separated; `HTTP:*` covers a namespace and an empty list every error.
+pub(crate) const POLICY_PROPERTIES: &[PropertyField] = &[
+    PropertyField::new("retry_backoff", "Backoff", PropertyKind::Options(BACKOFF_STRATEGIES), "Exponential"),
+    PropertyField::new("retry_max_retries", "Max retries", PropertyKind::Number, "3"),
+    PropertyField::new("retry_delay", "Initial delay (ms)", PropertyKind::Number, "500"),
+    PropertyField::new("retry_max_delay", "Max delay (ms)", PropertyKind::Number, "10000"),
+    PropertyField::new("retry_multiplier", "Multiplier", PropertyKind::Number, "2"),
+    PropertyField::new("retry_jitter", "Jitter (0 to 1)", PropertyKind::Number, "0.2"),
+    PropertyField::new(
+        "retry_errors",
+        "Retryable error types",
+        PropertyKind::Text,
+        "HTTP:CONNECTIVITY, HTTP:TIMEOUT, HTTP:SERVICE_UNAVAILABLE, DB:CONNECTIVITY, MQ:CONNECTIVITY",
+    ),
+];
+
+pub(crate) fn new_retry_policy(name: String) -> BaseConfiguration {
+    let config = BaseConfiguration::default()
+        .widget_type("RetryPolicy".to_string())
+        .name(name)
+        .component_level(ComponentLevel::Global);
+    with_defaults(config, POLICY_PROPERTIES)
+}
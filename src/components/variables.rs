@@ -0,0 +1,316 @@
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{Arc, Mutex};
+use egui::{RichText, TextEdit, Ui};
+use crate::base_configuration::{BaseConfiguration, ComponentLevel};
+use crate::components::component_view::CanvasState;
+use crate::components::inspector::PropertyKind;
+use crate::components::property_fields;
+use crate::expression::variables::{rename_in_expression, rename_in_text, variable_reads};
+use crate::expression::parser::KEYWORDS;
+use crate::expression::{embedded_expression, parse};
+
+// A component that reads or writes a variable
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ComponentRef {
+    pub(crate) uuid: String,
+    pub(crate) flow: String,
+    pub(crate) name: String,
+}
+
+// A variable of one flow: flow uuid and variable name. Flows do not share variables, so the same
+// name in two flows is two variables.
+pub(crate) type VariableKey = (String, String);
+
+// Every component that writes and reads one flow variable
+#[derive(Debug, Clone, Default)]
+pub(crate) struct VariableUsage {
+    pub(crate) writers: Vec<ComponentRef>,
+    pub(crate) readers: Vec<ComponentRef>,
+}
+
+// Variables a component sets or removes
+fn written_variables(config: &BaseConfiguration) -> Vec<String> {
+    match config.widget_type.as_str() {
+        "SetVariable" | "RemoveVariable" => vec![config.get_property("variable_name").trim().to_string()],
+        "ForEach" => match config.get_property("counter_variable").trim() {
+            "" => vec!["counter".to_string()],
+            counter => vec![counter.to_string()],
+        },
+        "Transformer" => config.transform_script_configs.iter()
+            .filter_map(|script| script.lock().ok().map(|script| script.target.trim().to_string()))
+            .filter(|target| !target.is_empty())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+// Expression sources of a component, including its Transformer scripts and Choice conditions
+fn expression_sources(config: &BaseConfiguration) -> Vec<String> {
+    let mut sources = Vec::new();
+    for field in property_fields(&config.widget_type) {
+        let value = config.get_property(field.key);
+        match field.kind {
+            PropertyKind::Expression => sources.push(value.to_string()),
+            PropertyKind::Text => sources.extend(embedded_expression(value).map(str::to_string)),
+            _ => {}
+        }
+    }
+    for script in &config.transform_script_configs {
+        if let Ok(script) = script.lock() {
+            sources.push(script.script.clone());
+        }
+    }
+    for route in &config.children {
+        if let Ok(route) = route.lock() {
+            if route.widget_type == "When" {
+                sources.push(route.get_property("expression").to_string());
+            }
+        }
+    }
+    sources
+}
+
+fn read_variables(config: &BaseConfiguration) -> BTreeSet<String> {
+    let mut reads = BTreeSet::new();
+    for source in expression_sources(config) {
+        if let Ok(expr) = parse(&source) {
+            variable_reads(&expr, &mut reads);
+        }
+    }
+    reads
+}
+
+// A rename the Variables view asks for; the editor applies it with `rename_variable`
+pub(crate) struct VariableRename {
+    pub(crate) flow: String, // Flow uuid
+    pub(crate) old: String,
+    pub(crate) new: String,
+}
+
+fn analyze_component(component: &Arc<Mutex<BaseConfiguration>>, flow: &(String, String), usages: &mut BTreeMap<VariableKey, VariableUsage>) {
+    let children = match component.lock() {
+        Ok(mut config) if !config.is_deleted => {
+            // Branch lanes belong to their router, which reports their conditions
+            if config.component_level != ComponentLevel::Other {
+                let (flow_uuid, flow_name) = flow;
+                let reference = ComponentRef { uuid: config.uuid.clone(), flow: flow_name.clone(), name: config.name.clone() };
+                let writes = written_variables(&config);
+                for variable in &writes {
+                    usages.entry((flow_uuid.clone(), variable.clone())).or_default().writers.push(reference.clone());
+                }
+                for variable in read_variables(&config) {
+                    usages.entry((flow_uuid.clone(), variable)).or_default().readers.push(reference.clone());
+                }
+                config.variable_changed = writes.join(", ");
+            }
+            config.children.clone()
+        }
+        _ => return,
+    };
+    for child in &children {
+        analyze_component(child, flow, usages);
+    }
+}
+
+// Finds the writers and readers of every variable of every flow. Also refreshes each component's
+// `variable_changed` with the variables it writes.
+pub(crate) fn analyze(flows: &[Arc<Mutex<BaseConfiguration>>]) -> BTreeMap<VariableKey, VariableUsage> {
+    let mut usages = BTreeMap::new();
+    for flow in flows {
+        let (key, children) = match flow.lock() {
+            Ok(config) => ((config.uuid.clone(), config.name.clone()), config.children.clone()),
+            Err(_) => continue,
+        };
+        for child in &children {
+            analyze_component(child, &key, &mut usages);
+        }
+    }
+    usages
+}
+
+// Components related to the selected one: writers of what it reads and readers of what it writes,
+// within its own flow
+pub(crate) fn related_components(usages: &BTreeMap<VariableKey, VariableUsage>, selected: &str) -> (HashSet<String>, HashSet<String>) {
+    let mut writers = HashSet::new();
+    let mut readers = HashSet::new();
+    for usage in usages.values() {
+        if usage.readers.iter().any(|reader| reader.uuid == selected) {
+            writers.extend(usage.writers.iter().map(|writer| writer.uuid.clone()));
+        }
+        if usage.writers.iter().any(|writer| writer.uuid == selected) {
+            readers.extend(usage.readers.iter().map(|reader| reader.uuid.clone()));
+        }
+    }
+    writers.remove(selected);
+    readers.remove(selected);
+    (writers, readers)
+}
+
+fn rename_in_component(component: &Arc<Mutex<BaseConfiguration>>, old: &str, new: &str) {
+    let children = match component.lock() {
+        Ok(mut config) => {
+            if written_variables(&config).iter().any(|variable| variable == old) {
+                match config.widget_type.as_str() {
+                    "SetVariable" | "RemoveVariable" => { config.properties.insert("variable_name".to_string(), new.to_string()); }
+                    "ForEach" => { config.properties.insert("counter_variable".to_string(), new.to_string()); }
+                    _ => {}
+                }
+            }
+            let mut renamed = Vec::new();
+            for field in property_fields(&config.widget_type) {
+                let value = config.get_property(field.key);
+                let result = match field.kind {
+                    PropertyKind::Expression => rename_in_expression(value, old, new),
+                    PropertyKind::Text => rename_in_text(value, old, new),
+                    _ => None,
+                };
+                renamed.extend(result.map(|value| (field.key.to_string(), value)));
+            }
+            if config.widget_type == "When" {
+                renamed.extend(rename_in_expression(config.get_property("expression"), old, new).map(|value| ("expression".to_string(), value)));
+            }
+            config.properties.extend(renamed);
+            for script in &config.transform_script_configs {
+                if let Ok(mut script) = script.lock() {
+                    if script.target.trim() == old {
+                        script.target = new.to_string();
+                    }
+                    if let Some(renamed) = rename_in_expression(&script.script, old, new) {
+                        script.script = renamed;
+                    }
+                }
+            }
+            config.children.clone()
+        }
+        Err(_) => return,
+    };
+    for child in &children {
+        rename_in_component(child, old, new);
+    }
+}
+
+// Renames a variable of one flow in every component that writes it and every expression that
+// reads it
+pub(crate) fn rename_variable(flow: &Arc<Mutex<BaseConfiguration>>, old: &str, new: &str) {
+    rename_in_component(flow, old, new);
+}
+
+// Why a variable cannot be renamed to `name`, given the other variables of its flow
+fn name_problem(name: &str, taken: &[&str]) -> Option<&'static str> {
+    if name.is_empty() {
+        Some("Enter a name")
+    } else if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        Some("Use letters, digits and _ only")
+    } else if name.starts_with(|c: char| c.is_ascii_digit()) {
+        Some("A name cannot start with a digit")
+    } else if KEYWORDS.contains(&name) {
+        Some("This is an expression keyword")
+    } else if taken.contains(&name) {
+        Some("The flow already has a variable with this name")
+    } else {
+        None
+    }
+}
+
+fn show_references(ui: &mut Ui, label: &str, references: &[ComponentRef], canvas: &mut CanvasState) {
+    ui.label(RichText::new(label).small().weak());
+    for reference in references {
+        let selected = canvas.selected.as_deref() == Some(reference.uuid.as_str());
+        if ui.selectable_label(selected, format!("{} / {}", reference.flow, reference.name)).clicked() {
+            canvas.selected = Some(reference.uuid.clone());
+        }
+    }
+}
+
+// Lists the variables of every flow with their writers and readers; clicking one selects it on
+// the canvas. Returns the rename to apply when one is confirmed.
+pub(crate) fn show_variables(ui: &mut Ui, usages: &BTreeMap<VariableKey, VariableUsage>, canvas: &mut CanvasState) -> Option<VariableRename> {
+    if usages.is_empty() {
+        ui.label(RichText::new("No flow variables yet.").small().weak());
+    }
+    let mut rename = None;
+    for (key, usage) in usages {
+        let (flow, variable) = key;
+        let flow_name = usage.writers.iter().chain(&usage.readers).next().map(|reference| reference.flow.as_str()).unwrap_or_default();
+        egui::CollapsingHeader::new(format!("{} / vars.{}", flow_name, variable)).id_salt(("variable", key)).show(ui, |ui| {
+            if usage.writers.is_empty() {
+                ui.label(RichText::new("Never written").small().color(egui::Color32::from_rgb(230, 160, 0)));
+            } else {
+                show_references(ui, "Written by", &usage.writers, canvas);
+            }
+            if !usage.readers.is_empty() {
+                show_references(ui, "Read by", &usage.readers, canvas);
+            }
+            let renaming = canvas.renaming.as_ref().filter(|(renamed, _)| renamed == key).map(|(_, new_name)| new_name.clone());
+            match renaming {
+                Some(mut new_name) => {
+                    let taken: Vec<&str> = usages.keys()
+                        .filter(|(other_flow, other)| other_flow == flow && other != variable)
+                        .map(|(_, other)| other.as_str())
+                        .collect();
+                    let problem = name_problem(&new_name, &taken);
+                    ui.horizontal(|ui| {
+                        ui.add(TextEdit::singleline(&mut new_name).desired_width(100.0));
+                        if ui.add_enabled(problem.is_none(), egui::Button::new("Apply")).clicked() {
+                            rename = Some(VariableRename { flow: flow.clone(), old: variable.clone(), new: new_name.clone() });
+                            canvas.renaming = None;
+                        } else if ui.button("Cancel").clicked() {
+                            canvas.renaming = None;
+                        } else {
+                            canvas.renaming = Some((key.clone(), new_name.clone()));
+                        }
+                    });
+                    if let Some(problem) = problem.filter(|_| new_name != *variable) {
+                        ui.label(RichText::new(problem).small().color(egui::Color32::RED));
+                    }
+                }
+                None => {
+                    if ui.small_button("Rename").clicked() {
+                        canvas.renaming = Some((key.clone(), variable.clone()));
+                    }
+                }
+            }
+        });
+    }
+    rename
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::components::{create_component, find_component, insert_after};
+    use crate::components::main_flow::main_flow::MainFlow;
+
+    fn property(flow: &Arc<Mutex<BaseConfiguration>>, uuid: &str, key: &str) -> String {
+        find_component(std::slice::from_ref(flow), uuid).unwrap().lock().unwrap().get_property(key).to_string()
+    }
+
+    #[test]
+    fn renaming_updates_writers_and_readers_of_the_flow() {
+        let flow = MainFlow::new("Orders".to_string()).config;
+        let flows = vec![flow.clone()];
+        let root = flow.lock().unwrap().uuid.clone();
+        let setter = create_component("SetVariable").unwrap().property("variable_name", "total").property("value", "payload.total");
+        let setter = insert_after(&flows, &root, setter).unwrap();
+        let logger = create_component("Logger").unwrap().property("message", "#[vars.total ++ \" of \" ++ vars.count]");
+        let logger = insert_after(&flows, &setter, logger).unwrap();
+
+        rename_variable(&flow, "total", "sum");
+        assert_eq!(property(&flow, &setter, "variable_name"), "sum");
+        assert_eq!(property(&flow, &logger, "message"), "#[vars.sum ++ \" of \" ++ vars.count]");
+        let usages = analyze(&flows);
+        let names: Vec<&str> = usages.keys().map(|(_, name)| name.as_str()).collect();
+        assert_eq!(names, vec!["count", "sum"]);
+        assert_eq!(usages[&(root, "sum".to_string())].readers[0].uuid, logger);
+    }
+
+    #[test]
+    fn new_names_must_be_valid_and_unused() {
+        assert_eq!(name_problem("", &[]), Some("Enter a name"));
+        assert_eq!(name_problem("a-b", &[]), Some("Use letters, digits and _ only"));
+        assert_eq!(name_problem("1st", &[]), Some("A name cannot start with a digit"));
+        assert_eq!(name_problem("payload", &[]), Some("This is an expression keyword"));
+        assert_eq!(name_problem("sum", &["sum"]), Some("The flow already has a variable with this name"));
+        assert_eq!(name_problem("sum_2", &["sum"]), None);
+    }
+}
@@ -0,0 +1,98 @@
+use serde_json::Value;
+use crate::expression::Expr;
+use crate::support::message::{FlowError, Message};
+use crate::support::ops;
+
+// Interprets an expression against the current message
+pub(crate) fn eval(expr: &Expr, message: &Message) -> Result<Value, FlowError> {
+    match expr {
+        Expr::Literal(value) => Ok(value.clone()),
+        Expr::Root(root) => Ok(match root.as_str() {
+            "payload" => message.payload.clone(),
+            "vars" => Value::Object(message.variables.clone()),
+            _ => Value::Object(message.attributes.clone()),
+        }),
+        Expr::Select(target, key) => Ok(ops::select(&eval(target, message)?, key)),
+        Expr::Index(target, index) => Ok(ops::index(&eval(target, message)?, &eval(index, message)?)),
+        Expr::Unary(operator, operand) => ops::unary(operator, &eval(operand, message)?),
+        Expr::Binary(operator, left, right) => ops::binary(operator, &eval(left, message)?, &eval(right, message)?),
+        Expr::And(left, right) => Ok(Value::Bool(
+            ops::truthy(&eval(left, message)?) && ops::truthy(&eval(right, message)?),
+        )),
+        Expr::Or(left, right) => Ok(Value::Bool(
+            ops::truthy(&eval(left, message)?) || ops::truthy(&eval(right, message)?),
+        )),
+        Expr::If(condition, then, otherwise) => {
+            if ops::truthy(&eval(condition, message)?) {
+                eval(then, message)
+            } else {
+                eval(otherwise, message)
+            }
+        }
+        Expr::Call(name, args) => ops::call(name, args.iter().map(|arg| eval(arg, message)).collect::<Result<_, _>>()?),
+        Expr::Array(items) => Ok(Value::Array(items.iter().map(|item| eval(item, message)).collect::<Result<_, _>>()?)),
+        Expr::Object(entries) => {
+            let mut values = Vec::new();
+            for (key, value) in entries {
+                values.push((key.as_str(), eval(value, message)?));
+            }
+            Ok(ops::object(values))
+        }
+    }
+}
+
+// Parses and evaluates in one step, as used by components that keep expressions as text
+pub(crate) fn eval_source(source: &str, message: &Message) -> Result<Value, FlowError> {
+    eval(&super::parse(source)?, message)
+}
+
+// Resolves a text property that may embed an expression as `#[...]`
+pub(crate) fn eval_text(value: &str, message: &Message) -> Result<Value, FlowError> {
+    eval(&super::parse_text(value)?, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn message() -> Message {
+        let mut message = Message::new(json!({ "order": { "total": 120, "items": ["a", "b"] }, "name": "ada" }));
+        message.variables.insert("country".to_string(), json!("AT"));
+        message.attributes.insert("method".to_string(), json!("POST"));
+        message
+    }
+
+    #[test]
+    fn reads_the_message_roots() {
+        let message = message();
+        assert_eq!(eval_source("payload.order.items[1]", &message).unwrap(), json!("b"));
+        assert_eq!(eval_source("vars.country", &message).unwrap(), json!("AT"));
+        assert_eq!(eval_source("attributes.method", &message).unwrap(), json!("POST"));
+        assert_eq!(eval_source("payload.missing.field", &message).unwrap(), json!(null));
+    }
+
+    #[test]
+    fn evaluates_operators_calls_and_conditionals() {
+        let message = message();
+        assert_eq!(eval_source("payload.order.total > 100 and vars.country == \"AT\"", &message).unwrap(), json!(true));
+        assert_eq!(eval_source("upper(payload.name) ++ \"!\"", &message).unwrap(), json!("ADA!"));
+        assert_eq!(eval_source("(1 + 2) * 3 - 10 / 4", &message).unwrap(), json!(6.5));
+        assert_eq!(eval_source("if (sizeOf(payload.order.items) > 2) \"many\" else \"few\"", &message).unwrap(), json!("few"));
+        assert_eq!(eval_source("{ id: 7, tags: [vars.country] }", &message).unwrap(), json!({ "id": 7, "tags": ["AT"] }));
+    }
+
+    #[test]
+    fn failures_are_expression_errors() {
+        let error = eval_source("1 / 0", &message()).unwrap_err();
+        assert_eq!(error.error_type, "EXPRESSION");
+        assert_eq!(eval_source("1 +", &message()).unwrap_err().error_type, "EXPRESSION");
+    }
+
+    #[test]
+    fn text_properties_are_literal_unless_embedded() {
+        let message = message();
+        assert_eq!(eval_text("payload.name", &message).unwrap(), json!("payload.name"));
+        assert_eq!(eval_text("#[payload.name]", &message).unwrap(), json!("ada"));
+    }
+}
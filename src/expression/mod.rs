@@ -0,0 +1,68 @@
+// Expression language used by component properties (conditions, transformations, messages).
+//
+//   payload.order.total > 100 and vars.country == "AT"
+//   { id: payload.id, name: upper(payload.name) ++ "!" }
+//   if (sizeOf(payload) > 0) payload[0] else null
+//
+// Expressions are parsed once into an `Expr` tree which is then either interpreted (`eval`)
+// or translated to Rust source (`rust`); both paths share `support::ops`.
+pub(crate) mod eval;
+pub(crate) mod parser;
+pub(crate) mod rust;
+pub(crate) mod variables;
+
+use std::fmt;
+use serde_json::Value;
+use crate::support::message::FlowError;
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Expr {
+    Literal(Value),
+    // One of the message roots: `payload`, `vars` or `attributes`
+    Root(String),
+    Select(Box<Expr>, String),
+    Index(Box<Expr>, Box<Expr>),
+    Unary(String, Box<Expr>),
+    Binary(String, Box<Expr>, Box<Expr>),
+    And(Box<Expr>, Box<Expr>),
+    Or(Box<Expr>, Box<Expr>),
+    If(Box<Expr>, Box<Expr>, Box<Expr>),
+    Call(String, Vec<Expr>),
+    Array(Vec<Expr>),
+    Object(Vec<(String, Expr)>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ParseError {
+    pub(crate) message: String,
+    pub(crate) position: usize,
+}
+
+impl fmt::Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} (at position {})", self.message, self.position)
+    }
+}
+
+impl From<ParseError> for FlowError {
+    fn from(error: ParseError) -> Self {
+        FlowError::new("EXPRESSION", error.to_string())
+    }
+}
+
+pub(crate) fn parse(source: &str) -> Result<Expr, ParseError> {
+    parser::Parser::new(source)?.parse()
+}
+
+// Plain text properties may embed an expression as `#[...]`; returns the expression source if so
+pub(crate) fn embedded_expression(value: &str) -> Option<&str> {
+    value.trim().strip_prefix("#[").and_then(|inner| inner.strip_suffix(']'))
+}
+
+// Parses a text property into an expression: embedded expressions as such, anything else as a string literal
+pub(crate) fn parse_text(value: &str) -> Result<Expr, ParseError> {
+    match embedded_expression(value) {
+        Some(source) => parse(source),
+        None => Ok(Expr::Literal(serde_json::Value::String(value.to_string()))),
+    }
+}
@@ -0,0 +1,386 @@
+use serde_json::{Number, Value};
+use crate::expression::{Expr, ParseError};
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum Token {
+    Number(f64),
+    Text(String),
+    Identifier(String),
+    Symbol(&'static str),
+}
+
+const SYMBOLS: &[&str] = &[
+    "++", "==", "!=", "<=", ">=", "<", ">", "+", "-", "*", "/", "%", "(", ")", "[", "]", "{", "}", ",", ":", ".",
+];
+
+// Identifiers the expression language gives a meaning of its own
+pub(crate) const KEYWORDS: &[&str] = &["if", "else", "or", "and", "not", "true", "false", "null", "payload", "vars", "attributes"];
+
+// Splits the source into tokens, each paired with its byte offset
+pub(crate) fn tokenize(source: &str) -> Result<Vec<(Token, usize)>, ParseError> {
+    let mut tokens = Vec::new();
+    let mut chars = source.char_indices().peekable();
+    while let Some(&(position, c)) = chars.peek() {
+        if c.is_whitespace() {
+            chars.next();
+        } else if c.is_ascii_digit() {
+            let mut end = position;
+            while let Some(&(index, c)) = chars.peek() {
+                if !(c.is_ascii_digit() || c == '.') {
+                    break;
+                }
+                // A dot not followed by a digit is a selector, not a decimal point
+                if c == '.' && !source[index + 1..].starts_with(|c: char| c.is_ascii_digit()) {
+                    break;
+                }
+                end = index + c.len_utf8();
+                chars.next();
+            }
+            let number = source[position..end].parse::<f64>().map_err(|_| ParseError {
+                message: format!("Invalid number '{}'", &source[position..end]),
+                position,
+            })?;
+            tokens.push((Token::Number(number), position));
+        } else if c == '"' || c == '\'' {
+            chars.next();
+            let mut text = String::new();
+            let mut closed = false;
+            while let Some((_, next)) = chars.next() {
+                match next {
+                    '\\' => match chars.next() {
+                        Some((_, 'n')) => text.push('\n'),
+                        Some((_, 't')) => text.push('\t'),
+                        Some((_, escaped)) => text.push(escaped),
+                        None => break,
+                    },
+                    _ if next == c => {
+                        closed = true;
+                        break;
+                    }
+                    _ => text.push(next),
+                }
+            }
+            if !closed {
+                return Err(ParseError { message: "Unterminated string".to_string(), position });
+            }
+            tokens.push((Token::Text(text), position));
+        } else if c.is_alphabetic() || c == '_' {
+            let mut identifier = String::new();
+            while let Some(&(_, c)) = chars.peek() {
+                if !(c.is_alphanumeric() || c == '_') {
+                    break;
+                }
+                identifier.push(c);
+                chars.next();
+            }
+            tokens.push((Token::Identifier(identifier), position));
+        } else {
+            let symbol = SYMBOLS.iter().find(|symbol| source[position..].starts_with(**symbol)).ok_or_else(|| ParseError {
+                message: format!("Unexpected character '{}'", c),
+                position,
+            })?;
+            for _ in 0..symbol.len() {
+                chars.next();
+            }
+            tokens.push((Token::Symbol(symbol), position));
+        }
+    }
+    Ok(tokens)
+}
+
+// Recursive descent parser, lowest precedence first:
+// if/else, or, and, not, comparison, ++, + -, * / %, unary minus, selectors, primary
+pub(crate) struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    end: usize,
+}
+
+impl Parser {
+    pub(crate) fn new(source: &str) -> Result<Self, ParseError> {
+        Ok(Self {
+            tokens: tokenize(source)?,
+            position: 0,
+            end: source.len(),
+        })
+    }
+
+    pub(crate) fn parse(mut self) -> Result<Expr, ParseError> {
+        if self.tokens.is_empty() {
+            return Err(self.error("Empty expression"));
+        }
+        let expr = self.parse_if()?;
+        if self.position < self.tokens.len() {
+            return Err(self.error("Unexpected token"));
+        }
+        Ok(expr)
+    }
+
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn error(&self, message: &str) -> ParseError {
+        let position = self.tokens.get(self.position).map(|(_, position)| *position).unwrap_or(self.end);
+        ParseError { message: message.to_string(), position }
+    }
+
+    fn eat_symbol(&mut self, symbol: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Symbol(current)) if *current == symbol) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn eat_keyword(&mut self, keyword: &str) -> bool {
+        if matches!(self.peek(), Some(Token::Identifier(identifier)) if identifier == keyword) {
+            self.position += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn expect_symbol(&mut self, symbol: &str) -> Result<(), ParseError> {
+        if self.eat_symbol(symbol) {
+            Ok(())
+        } else {
+            Err(self.error(&format!("Expected '{}'", symbol)))
+        }
+    }
+
+    fn parse_if(&mut self) -> Result<Expr, ParseError> {
+        if !self.eat_keyword("if") {
+            return self.parse_or();
+        }
+        self.expect_symbol("(")?;
+        let condition = self.parse_if()?;
+        self.expect_symbol(")")?;
+        let then = self.parse_if()?;
+        if !self.eat_keyword("else") {
+            return Err(self.error("Expected 'else'"));
+        }
+        let otherwise = self.parse_if()?;
+        Ok(Expr::If(Box::new(condition), Box::new(then), Box::new(otherwise)))
+    }
+
+    fn parse_or(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_and()?;
+        while self.eat_keyword("or") {
+            left = Expr::Or(Box::new(left), Box::new(self.parse_and()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_and(&mut self) -> Result<Expr, ParseError> {
+        let mut left = self.parse_not()?;
+        while self.eat_keyword("and") {
+            left = Expr::And(Box::new(left), Box::new(self.parse_not()?));
+        }
+        Ok(left)
+    }
+
+    fn parse_not(&mut self) -> Result<Expr, ParseError> {
+        if self.eat_keyword("not") {
+            return Ok(Expr::Unary("not".to_string(), Box::new(self.parse_not()?)));
+        }
+        self.parse_comparison()
+    }
+
+    fn parse_binary(&mut self, operators: &[&'static str], next: fn(&mut Self) -> Result<Expr, ParseError>) -> Result<Expr, ParseError> {
+        let mut left = next(self)?;
+        while let Some(operator) = operators.iter().find(|operator| matches!(self.peek(), Some(Token::Symbol(current)) if current == *operator)) {
+            self.position += 1;
+            left = Expr::Binary(operator.to_string(), Box::new(left), Box::new(next(self)?));
+        }
+        Ok(left)
+    }
+
+    fn parse_comparison(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["==", "!=", "<=", ">=", "<", ">"], Self::parse_concat)
+    }
+
+    fn parse_concat(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["++"], Self::parse_additive)
+    }
+
+    fn parse_additive(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["+", "-"], Self::parse_multiplicative)
+    }
+
+    fn parse_multiplicative(&mut self) -> Result<Expr, ParseError> {
+        self.parse_binary(&["*", "/", "%"], Self::parse_unary)
+    }
+
+    fn parse_unary(&mut self) -> Result<Expr, ParseError> {
+        if self.eat_symbol("-") {
+            return Ok(Expr::Unary("-".to_string(), Box::new(self.parse_unary()?)));
+        }
+        self.parse_postfix()
+    }
+
+    fn parse_postfix(&mut self) -> Result<Expr, ParseError> {
+        let mut expr = self.parse_primary()?;
+        loop {
+            if self.eat_symbol(".") {
+                match self.tokens.get(self.position).map(|(token, _)| token.clone()) {
+                    Some(Token::Identifier(key)) | Some(Token::Text(key)) => {
+                        self.position += 1;
+                        expr = Expr::Select(Box::new(expr), key);
+                    }
+                    _ => return Err(self.error("Expected a field name after '.'")),
+                }
+            } else if self.eat_symbol("[") {
+                let index = self.parse_if()?;
+                self.expect_symbol("]")?;
+                expr = Expr::Index(Box::new(expr), Box::new(index));
+            } else {
+                return Ok(expr);
+            }
+        }
+    }
+
+    fn parse_primary(&mut self) -> Result<Expr, ParseError> {
+        let token = self.peek().cloned().ok_or_else(|| self.error("Unexpected end of expression"))?;
+        match token {
+            Token::Number(number) => {
+                self.position += 1;
+                let value = if number.fract() == 0.0 && number.abs() < i64::MAX as f64 {
+                    Value::Number(Number::from(number as i64))
+                } else {
+                    Number::from_f64(number).map(Value::Number).unwrap_or(Value::Null)
+                };
+                Ok(Expr::Literal(value))
+            }
+            Token::Text(text) => {
+                self.position += 1;
+                Ok(Expr::Literal(Value::String(text)))
+            }
+            Token::Identifier(identifier) => {
+                self.position += 1;
+                match identifier.as_str() {
+                    "true" => Ok(Expr::Literal(Value::Bool(true))),
+                    "false" => Ok(Expr::Literal(Value::Bool(false))),
+                    "null" => Ok(Expr::Literal(Value::Null)),
+                    "payload" | "vars" | "attributes" => Ok(Expr::Root(identifier)),
+                    _ if self.eat_symbol("(") => {
+                        let mut args = Vec::new();
+                        if !self.eat_symbol(")") {
+                            loop {
+                                args.push(self.parse_if()?);
+                                if self.eat_symbol(")") {
+                                    break;
+                                }
+                                self.expect_symbol(",")?;
+                            }
+                        }
+                        Ok(Expr::Call(identifier, args))
+                    }
+                    _ => {
+                        self.position -= 1;
+                        Err(self.error(&format!("Unknown identifier '{}'", identifier)))
+                    }
+                }
+            }
+            Token::Symbol("(") => {
+                self.position += 1;
+                let expr = self.parse_if()?;
+                self.expect_symbol(")")?;
+                Ok(expr)
+            }
+            Token::Symbol("[") => {
+                self.position += 1;
+                let mut items = Vec::new();
+                if !self.eat_symbol("]") {
+                    loop {
+                        items.push(self.parse_if()?);
+                        if self.eat_symbol("]") {
+                            break;
+                        }
+                        self.expect_symbol(",")?;
+                    }
+                }
+                Ok(Expr::Array(items))
+            }
+            Token::Symbol("{") => {
+                self.position += 1;
+                let mut entries = Vec::new();
+                if !self.eat_symbol("}") {
+                    loop {
+                        let key = match self.peek().cloned() {
+                            Some(Token::Identifier(key)) | Some(Token::Text(key)) => key,
+                            _ => return Err(self.error("Expected an object key")),
+                        };
+                        self.position += 1;
+                        self.expect_symbol(":")?;
+                        entries.push((key, self.parse_if()?));
+                        if self.eat_symbol("}") {
+                            break;
+                        }
+                        self.expect_symbol(",")?;
+                    }
+                }
+                Ok(Expr::Object(entries))
+            }
+            Token::Symbol(_) => Err(self.error("Unexpected symbol")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use crate::expression::{parse, Expr};
+
+    fn literal(value: serde_json::Value) -> Box<Expr> {
+        Box::new(Expr::Literal(value))
+    }
+
+    #[test]
+    fn multiplication_binds_tighter_than_addition() {
+        let expected = Expr::Binary("+".to_string(), literal(json!(1)), Box::new(Expr::Binary("*".to_string(), literal(json!(2)), literal(json!(3)))));
+        assert_eq!(parse("1 + 2 * 3").unwrap(), expected);
+    }
+
+    #[test]
+    fn selectors_and_indexes_chain_on_a_root() {
+        let expected = Expr::Index(
+            Box::new(Expr::Select(Box::new(Expr::Root("payload".to_string())), "items".to_string())),
+            literal(json!(0)),
+        );
+        assert_eq!(parse("payload.items[0]").unwrap(), expected);
+    }
+
+    #[test]
+    fn and_binds_tighter_than_or() {
+        let parsed = parse("true or false and not true").unwrap();
+        assert!(matches!(parsed, Expr::Or(_, right) if matches!(*right, Expr::And(..))));
+    }
+
+    #[test]
+    fn objects_arrays_calls_and_conditionals_parse() {
+        let parsed = parse("if (sizeOf(payload) > 0) { id: payload.id, \"full name\": [1, 2.5] } else null").unwrap();
+        match parsed {
+            Expr::If(condition, then, otherwise) => {
+                assert!(matches!(*condition, Expr::Binary(ref operator, ..) if operator == ">"));
+                assert!(matches!(*then, Expr::Object(ref entries) if entries[1].0 == "full name"));
+                assert_eq!(*otherwise, Expr::Literal(json!(null)));
+            }
+            other => panic!("Expected a conditional, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn errors_point_at_the_offending_token() {
+        let error = parse("payload.id +").unwrap_err();
+        assert_eq!(error.message, "Unexpected end of expression");
+        assert_eq!(error.position, 12);
+        let error = parse("1 2").unwrap_err();
+        assert_eq!((error.message.as_str(), error.position), ("Unexpected token", 2));
+        assert_eq!(parse("unknown").unwrap_err().message, "Unknown identifier 'unknown'");
+        assert_eq!(parse("if (true) 1").unwrap_err().message, "Expected 'else'");
+        assert_eq!(parse("  ").unwrap_err().message, "Empty expression");
+    }
+}
@@ -0,0 +1,101 @@
+use serde_json::Value;
+use crate::expression::Expr;
+
+// Translates an expression into a Rust expression of type `Value`. The generated code expects
+// `msg: Message` in scope, the support crate's `ops` module imported, and a `FlowError` return type.
+pub(crate) fn to_rust(expr: &Expr) -> String {
+    match expr {
+        Expr::Literal(value) => literal(value),
+        Expr::Root(root) => match root.as_str() {
+            "payload" => "msg.payload.clone()".to_string(),
+            "vars" => "Value::Object(msg.variables.clone())".to_string(),
+            _ => "Value::Object(msg.attributes.clone())".to_string(),
+        },
+        Expr::Select(target, key) => format!("ops::select(&{}, {:?})", to_rust(target), key),
+        Expr::Index(target, index) => format!("ops::index(&{}, &{})", to_rust(target), to_rust(index)),
+        Expr::Unary(operator, operand) => format!("ops::unary({:?}, &{})?", operator, to_rust(operand)),
+        Expr::Binary(operator, left, right) => {
+            format!("ops::binary({:?}, &{}, &{})?", operator, to_rust(left), to_rust(right))
+        }
+        Expr::And(left, right) => format!(
+            "Value::Bool(ops::truthy(&{}) && ops::truthy(&{}))",
+            to_rust(left),
+            to_rust(right)
+        ),
+        Expr::Or(left, right) => format!(
+            "Value::Bool(ops::truthy(&{}) || ops::truthy(&{}))",
+            to_rust(left),
+            to_rust(right)
+        ),
+        Expr::If(condition, then, otherwise) => format!(
+            "(if ops::truthy(&{}) {{ {} }} else {{ {} }})",
+            to_rust(condition),
+            to_rust(then),
+            to_rust(otherwise)
+        ),
+        Expr::Call(name, args) => format!(
+            "ops::call({:?}, vec![{}])?",
+            name,
+            args.iter().map(to_rust).collect::<Vec<_>>().join(", ")
+        ),
+        Expr::Array(items) => format!(
+            "Value::Array(vec![{}])",
+            items.iter().map(to_rust).collect::<Vec<_>>().join(", ")
+        ),
+        Expr::Object(entries) => format!(
+            "ops::object(vec![{}])",
+            entries.iter()
+                .map(|(key, value)| format!("({:?}, {})", key, to_rust(value)))
+                .collect::<Vec<_>>()
+                .join(", ")
+        ),
+    }
+}
+
+// Literals are embedded as JSON text so numbers keep their exact representation
+fn literal(value: &Value) -> String {
+    match value {
+        Value::Null => "Value::Null".to_string(),
+        Value::Bool(value) => format!("Value::Bool({})", value),
+        // Secure placeholders are left by the generator and decrypted when the service runs
+        Value::String(value) if value.contains("${secure::") => format!("Value::String(secure::resolve({:?})?)", value),
+        Value::String(value) => format!("Value::String({:?}.to_string())", value),
+        other => format!("serde_json::from_str::<Value>({:?}).unwrap()", other.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+
+    fn translate(source: &str) -> String {
+        to_rust(&parse(source).unwrap())
+    }
+
+    #[test]
+    fn roots_and_selectors_read_the_message() {
+        assert_eq!(translate("payload.id"), "ops::select(&msg.payload.clone(), \"id\")");
+        assert_eq!(translate("vars[0]"), "ops::index(&Value::Object(msg.variables.clone()), &serde_json::from_str::<Value>(\"0\").unwrap())");
+    }
+
+    #[test]
+    fn fallible_operations_propagate_errors() {
+        assert_eq!(
+            translate("upper(\"a\") ++ null"),
+            "ops::binary(\"++\", &ops::call(\"upper\", vec![Value::String(\"a\".to_string())])?, &Value::Null)?"
+        );
+        assert_eq!(
+            translate("if (true) 1 else 2.5"),
+            "(if ops::truthy(&Value::Bool(true)) { serde_json::from_str::<Value>(\"1\").unwrap() } else { serde_json::from_str::<Value>(\"2.5\").unwrap() })"
+        );
+    }
+
+    #[test]
+    fn secure_placeholders_are_resolved_at_run_time() {
+        assert_eq!(
+            translate("\"${secure::db.password}\""),
+            "Value::String(secure::resolve(\"${secure::db.password}\")?)"
+        );
+    }
+}
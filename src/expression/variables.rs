@@ -0,0 +1,111 @@
+use std::collections::BTreeSet;
+use serde_json::Value;
+use crate::expression::parser::{tokenize, Token};
+use crate::expression::{embedded_expression, Expr};
+
+// Collects the flow variables an expression reads through `vars.<name>` or `vars["<name>"]`
+pub(crate) fn variable_reads(expr: &Expr, reads: &mut BTreeSet<String>) {
+    match expr {
+        Expr::Select(target, name) if **target == Expr::Root("vars".to_string()) => {
+            reads.insert(name.clone());
+        }
+        Expr::Index(target, index) if **target == Expr::Root("vars".to_string()) => {
+            if let Expr::Literal(Value::String(name)) = &**index {
+                reads.insert(name.clone());
+            } else {
+                variable_reads(index, reads);
+            }
+        }
+        Expr::Literal(_) | Expr::Root(_) => {}
+        Expr::Select(target, _) | Expr::Unary(_, target) => variable_reads(target, reads),
+        Expr::Index(left, right) | Expr::Binary(_, left, right) | Expr::And(left, right) | Expr::Or(left, right) => {
+            variable_reads(left, reads);
+            variable_reads(right, reads);
+        }
+        Expr::If(condition, then, otherwise) => {
+            variable_reads(condition, reads);
+            variable_reads(then, reads);
+            variable_reads(otherwise, reads);
+        }
+        Expr::Call(_, items) | Expr::Array(items) => items.iter().for_each(|item| variable_reads(item, reads)),
+        Expr::Object(entries) => entries.iter().for_each(|(_, value)| variable_reads(value, reads)),
+    }
+}
+
+// Rewrites every reference to variable `old` in an expression source to `new`, leaving the rest
+// of the source untouched. Returns None when nothing changes or the source does not tokenize.
+pub(crate) fn rename_in_expression(source: &str, old: &str, new: &str) -> Option<String> {
+    let tokens = tokenize(source).ok()?;
+    let mut replacements = Vec::new();
+    for (index, (token, _)) in tokens.iter().enumerate() {
+        // `vars` is a root unless it is itself selected from something (`payload.vars`)
+        let is_root = matches!(token, Token::Identifier(name) if name == "vars")
+            && !matches!(index.checked_sub(1).map(|previous| &tokens[previous].0), Some(Token::Symbol(".")));
+        if !is_root {
+            continue;
+        }
+        match (tokens.get(index + 1), tokens.get(index + 2)) {
+            (Some((Token::Symbol("."), _)), Some((Token::Identifier(name), start))) if name == old => {
+                replacements.push((*start, start + name.len(), new.to_string()));
+            }
+            (Some((Token::Symbol("["), _)), Some((Token::Text(name), start))) if name == old => {
+                // A string token ends where the next token starts, minus whitespace
+                let end = tokens.get(index + 3).map(|(_, next)| *next).unwrap_or(source.len());
+                let end = start + source[*start..end].trim_end().len();
+                replacements.push((*start, end, serde_json::to_string(new).ok()?));
+            }
+            _ => {}
+        }
+    }
+    if replacements.is_empty() {
+        return None;
+    }
+    let mut renamed = source.to_string();
+    for (start, end, replacement) in replacements.into_iter().rev() {
+        renamed.replace_range(start..end, &replacement);
+    }
+    Some(renamed)
+}
+
+// Same as `rename_in_expression` for a text property, which only holds an expression as `#[...]`
+pub(crate) fn rename_in_text(value: &str, old: &str, new: &str) -> Option<String> {
+    let source = embedded_expression(value)?;
+    rename_in_expression(source, old, new).map(|renamed| format!("#[{}]", renamed))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::expression::parse;
+
+    fn reads(source: &str) -> Vec<String> {
+        let mut reads = BTreeSet::new();
+        variable_reads(&parse(source).unwrap(), &mut reads);
+        reads.into_iter().collect()
+    }
+
+    #[test]
+    fn finds_the_variables_an_expression_reads() {
+        assert_eq!(reads("vars.total > 1 and upper(vars[\"name\"]) == payload.vars.other"), vec!["name", "total"]);
+        assert_eq!(reads("{ a: [vars.items[vars.index]] }"), vec!["index", "items"]);
+        assert!(reads("payload.vars.total").is_empty());
+    }
+
+    #[test]
+    fn renames_selected_and_indexed_references_only() {
+        assert_eq!(
+            rename_in_expression("vars.total + vars[ \"total\" ] * payload.vars.total", "total", "sum").unwrap(),
+            "vars.sum + vars[ \"sum\" ] * payload.vars.total"
+        );
+        // Other variables and strings with the same text stay
+        assert_eq!(rename_in_expression("vars.totals ++ \"vars.total\"", "total", "sum"), None);
+        assert_eq!(rename_in_expression("vars.total +", "total", "sum").unwrap(), "vars.sum +");
+        assert_eq!(rename_in_expression("\"unclosed", "total", "sum"), None);
+    }
+
+    #[test]
+    fn renames_only_embedded_expressions_in_text() {
+        assert_eq!(rename_in_text("#[vars.total]", "total", "sum").unwrap(), "#[vars.sum]");
+        assert_eq!(rename_in_text("vars.total", "total", "sum"), None);
+    }
+}
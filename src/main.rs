@@ -2,26 +2,65 @@
 
 
 pub mod base_configuration;
+pub mod codegen;
 pub mod components;
+pub mod expression;
+pub mod preferences;
+pub mod project;
+pub mod properties;
+pub mod recovery;
+pub mod runtime;
+pub mod support;
 
 use std::cell::RefCell;
 use eframe::{egui, App, NativeOptions};
-use egui::{Align, Align2, Context, Layout, RichText, ScrollArea, TopBottomPanel, CentralPanel, SidePanel, Ui, ImageButton, Image, TextBuffer, Id, LayerId, Order, TextStyle, vec2, TextureHandle, epaint::{Color32}, Widget};
+use egui::{Align, Align2, Context, Layout, RichText, ScrollArea, TopBottomPanel, CentralPanel, SidePanel, Ui, ImageButton, Image, TextBuffer, Id, LayerId, Order, TextStyle, TextureHandle, epaint::{Color32}, Widget};
 
 
 use std::thread;
 use std::time::Duration;
-use std::collections::VecDeque;
+use std::collections::{BTreeMap, HashMap, VecDeque};
 use std::rc::Rc;
+use std::path::PathBuf;
 use std::sync::{Arc, Mutex};
-use crate::base_configuration::ComponentLevel;
+use crate::base_configuration::{BaseConfiguration, ComponentLevel, ComponentSnapshot};
+use crate::components::command_palette::{pressed_command, shortcut, format_shortcut, show_command_palette, show_shortcuts_window, Command, CommandPalette};
+use crate::components::component_view::CanvasState;
 use crate::components::draggable_item::DraggableItem;
+use crate::components::find_replace::{show_find_replace, FindAction, FindState, FlowReplace};
+use crate::components::global_elements::show_global_elements;
+use crate::components::inspector::{show_inspector, InspectorContext};
+use crate::components::minimap::show_minimap;
+use crate::components::object_stores::show_object_stores;
+use crate::components::problems::find_problems;
+use crate::components::project_explorer::{show_project_explorer, ExplorerState};
+use crate::components::recovery_prompt::{show_recovery_prompt, RecoveryPrompt};
+use crate::components::run_history::{show_run_history, ReplayState};
+use crate::components::tabs::{save_all, save_document, show_document, show_tab_strip, Document, Workspace};
+use crate::components::variables::{analyze, related_components, rename_variable, show_variables, VariableKey, VariableRename, VariableUsage};
 use crate::components::main_flow::main_flow::MainFlow;
+use crate::preferences::{add_recent, Preferences};
+use crate::project::{load_project, Project};
+use crate::recovery::{find_recoveries, Autosave, AUTOSAVE_INTERVAL};
+use crate::properties::{environments, properties_folder, resolve_snapshot, secure_file, Properties, SecureMode};
+use crate::runtime::tracking::RunHistory;
+use crate::support::secure::Cipher;
+use crate::runtime::{LogBuffer, Runtime};
 
-enum ComponentType {
-    MainFlow(components::main_flow::main_flow::MainFlow),
-    // Choice(Choice),
-    // Add more widget types here
+// What the bottom panel shows
+#[derive(PartialEq)]
+enum BottomTab {
+    Log,
+    Runs,
+    ObjectStores,
+}
+
+// What the left panel shows
+#[derive(PartialEq)]
+enum LeftTab {
+    Palette,
+    Project,
+    Search,
 }
 
 pub struct Panels {
@@ -29,37 +68,670 @@ pub struct Panels {
     right_panel_open: bool,
     bottom_panel_open: bool,
     arrow_up_texture: Option<TextureHandle>,
-    main_flow_draggable_item: Option<DraggableItem>,
-    choice_draggable_item: Option<DraggableItem>,
+    palette_items: Vec<DraggableItem>, // Components offered in the left panel, in display order
     is_dragging: bool,
     show_dropped_box: bool,
     dragging_item: Option<DraggableItem>, // Track which item is being dragged
     pub accepted_items: VecDeque<DraggableItem>, // Queue to store accepted items
-    widget_list: Vec<ComponentType>,
+    project: Project, // Configuration files with their flows and sub-flows
+    workspace: Workspace, // Tabs open in the central area
+    autosave: Autosave, // Copies of unsaved configuration files, for crash recovery
+    recovery: RecoveryPrompt, // Autosaved copies found at startup
+    left_tab: LeftTab,
+    explorer: ExplorerState,
+    find: FindState, // Project-wide find and replace in the left panel
+    status_message: String, // Outcome of the last toolbar action
+    canvas: CanvasState,
+    runtime: Option<Arc<Runtime>>, // Set while the flows run inside the editor
+    run_log: LogBuffer, // Logger output of editor runs, shown in the bottom panel
+    variable_usages: BTreeMap<VariableKey, VariableUsage>, // Writers and readers per flow variable, refreshed after edits
+    analyzed: Option<u64>, // Workspace revision `variable_usages` and the canvas problems were computed at
+    run_history: RunHistory, // Runs of tracked flows, browsed in the bottom panel
+    bottom_tab: BottomTab,
+    replay: ReplayState,
+    globals: Vec<Arc<Mutex<BaseConfiguration>>>, // Global elements components reference by name
+    environments: Vec<String>, // Environments offered in the toolbar
+    properties: Properties, // Property values of the selected environment
+    project_folder: PathBuf, // Relative file paths of flows resolve against it
+    preferences: Preferences, // Saved when the editor closes; the panel flags above are kept in step
+    open_folder: String, // Folder typed in the Project menu
+    command_palette: CommandPalette,
+    shortcuts_open: bool, // Whether the keyboard shortcut settings are shown
 }
 
 
 impl Panels {
     /// New method to create an instance of `Panels` and load textures
-    pub fn new(ctx: &Context) -> Self {
+    pub(crate) fn new(ctx: &Context, preferences: Preferences) -> Self {
         let arrow_up_bytes = include_bytes!("../assets/icons/arrow-up_8600241.png");
         let choice_bytes = include_bytes!("../assets/images/choiceSimplified_48.png");
 
         let arrow_up_texture = Self::load_texture(ctx, arrow_up_bytes, "arrow_up");
-        let choice_draggable_item = DraggableItem::new(ctx, "Choice",  ComponentLevel::ParentOnly.as_str());
-        let main_flow_draggable_item = DraggableItem::new(ctx, "MainFlow",  ComponentLevel::ParentOnly.as_str()) ;
-        Panels {
-            left_panel_open: true,
-            right_panel_open: true,
-            bottom_panel_open: false,
+        let palette_items = vec![
+            DraggableItem::new(ctx, "MainFlow", ComponentLevel::ParentOnly.as_str()),
+            DraggableItem::new(ctx, "HttpListener", ComponentLevel::SourceOnly.as_str()),
+            DraggableItem::new(ctx, "Scheduler", ComponentLevel::SourceOnly.as_str()),
+            DraggableItem::new(ctx, "OnNewFile", ComponentLevel::SourceOnly.as_str()),
+            DraggableItem::new(ctx, "QueueListener", ComponentLevel::SourceOnly.as_str()),
+            DraggableItem::new(ctx, "HttpRequest", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Database", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "File", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Publish", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Consume", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Choice", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "ScatterGather", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Transformer", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "SetPayload", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "SetVariable", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "RemoveVariable", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Logger", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "ForEach", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "ParallelForEach", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "UntilSuccessful", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Async", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "Cache", ComponentLevel::ChildOnly.as_str()),
+            DraggableItem::new(ctx, "IdempotentMessageValidator", ComponentLevel::ChildOnly.as_str()),
+        ];
+        // Collapsed containers show the palette icons of their first components
+        let canvas = CanvasState {
+            icons: palette_items.iter()
+                .filter_map(|item| item.texture.clone().map(|texture| (item.name.clone(), texture)))
+                .collect(),
+            ..CanvasState::default()
+        };
+        // The last project opened, or else the working directory
+        let project_folder = preferences.last_project.clone()
+            .filter(|folder| folder.is_dir())
+            .unwrap_or_else(|| std::env::current_dir().unwrap_or_default());
+        let mut panels = Panels {
+            left_panel_open: preferences.left_panel_open,
+            right_panel_open: preferences.right_panel_open,
+            bottom_panel_open: preferences.bottom_panel_open,
             arrow_up_texture,
-            main_flow_draggable_item: Option::from(main_flow_draggable_item),
-            choice_draggable_item: Option::from(choice_draggable_item),
+            palette_items,
             is_dragging: false,
             show_dropped_box: false,
             dragging_item: None,
             accepted_items: VecDeque::new(),
-            widget_list: vec![],
+            project: Project::default(),
+            workspace: Workspace::default(),
+            autosave: Autosave::default(),
+            recovery: RecoveryPrompt::default(),
+            left_tab: LeftTab::Palette,
+            explorer: ExplorerState::default(),
+            find: FindState::default(),
+            status_message: String::new(),
+            canvas,
+            runtime: None,
+            run_log: LogBuffer::default(),
+            variable_usages: BTreeMap::new(),
+            analyzed: None,
+            run_history: RunHistory::default(),
+            bottom_tab: BottomTab::Log,
+            replay: ReplayState::default(),
+            globals: Vec::new(),
+            environments: Vec::new(),
+            properties: Properties::empty("dev"),
+            project_folder: PathBuf::new(),
+            preferences,
+            open_folder: String::new(),
+            command_palette: CommandPalette::default(),
+            shortcuts_open: false,
+        };
+        panels.open_project(project_folder);
+        panels
+    }
+
+    // Opens the project in a folder: its configuration files, global elements and property files.
+    // Unsaved changes of the current project are autosaved first, so reopening it offers them back.
+    fn open_project(&mut self, folder: PathBuf) {
+        if !folder.is_dir() {
+            self.status_message = format!("{} is not a folder", folder.display());
+            return;
+        }
+        let folder = std::fs::canonicalize(&folder).unwrap_or(folder);
+        if !self.recovery.is_pending() && self.project.files.iter().any(|file| file.is_dirty()) {
+            if let Err(error) = self.autosave.run(&self.project, &self.project_folder) {
+                self.status_message = format!("Unsaved changes could not be autosaved: {}", error);
+                return;
+            }
+        }
+        let (project, globals) = match load_project(&folder) {
+            Ok(loaded) => loaded,
+            Err(error) => {
+                self.status_message = format!("Could not open the project: {}", error);
+                return;
+            }
+        };
+        self.stop_runtime();
+        self.workspace = Workspace::default();
+        self.find.invalidate();
+        self.analyzed = None;
+        self.workspace.open(&project, Document::File(project.files[0].id.clone()));
+        self.project = project;
+        self.globals = globals;
+        self.canvas = CanvasState::default();
+        self.autosave = Autosave::default();
+        self.recovery = RecoveryPrompt::new(find_recoveries(&folder));
+        self.project_folder = folder.clone();
+        add_recent(&mut self.preferences.recent_projects, &folder);
+        self.preferences.last_project = Some(folder.clone());
+        let environment = self.properties.environment.clone();
+        self.load_properties(&environment);
+        self.status_message = format!("Opened {}", folder.display());
+    }
+
+    // Preferences as they are now
+    fn preferences(&self) -> Preferences {
+        Preferences {
+            left_panel_open: self.left_panel_open,
+            right_panel_open: self.right_panel_open,
+            bottom_panel_open: self.bottom_panel_open,
+            ..self.preferences.clone()
+        }
+    }
+
+    // Panels, panel sizes, theme, zoom and window back to their defaults
+    fn reset_layout(&mut self, ctx: &Context) {
+        self.preferences.reset();
+        self.left_panel_open = self.preferences.left_panel_open;
+        self.right_panel_open = self.preferences.right_panel_open;
+        self.bottom_panel_open = self.preferences.bottom_panel_open;
+        // Panel sizes and collapsed sections live in the egui memory
+        ctx.memory_mut(|memory| memory.data.clear());
+        ctx.set_theme(self.preferences.theme);
+        ctx.set_zoom_factor(self.preferences.zoom);
+        ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(self.preferences.maximized));
+        self.status_message = "Layout and preferences reset to their defaults".to_string();
+    }
+
+    // Selects an environment and loads its property file
+    fn load_properties(&mut self, environment: &str) {
+        let folder = properties_folder(&self.project_folder);
+        self.environments = environments(&folder);
+        match Properties::load(&folder, environment) {
+            Ok(properties) => self.properties = properties,
+            Err(error) => {
+                self.properties = Properties::empty(environment);
+                self.status_message = format!("Could not read the {} properties: {}", environment, error);
+            }
+        }
+    }
+
+    // Root configurations of every flow and sub-flow of the project
+    fn flow_configs(&self) -> Vec<Arc<Mutex<BaseConfiguration>>> {
+        self.project.flow_configs()
+    }
+
+    // Snapshots of every flow on the canvas, in canvas order, with global elements and
+    // properties of the selected environment applied
+    fn flow_snapshots(&self, mode: SecureMode) -> Result<Vec<ComponentSnapshot>, String> {
+        let globals: HashMap<String, ComponentSnapshot> = self.globals.iter()
+            .filter_map(|global| global.lock().ok().map(|global| (global.name.clone(), global.snapshot())))
+            .collect();
+        let mut flows: Vec<ComponentSnapshot> = self.flow_configs().iter()
+            .filter_map(|config| config.lock().ok().map(|config| config.snapshot()))
+            .collect();
+        for flow in &mut flows {
+            resolve_snapshot(flow, &globals, &self.properties, mode)?;
+        }
+        Ok(flows)
+    }
+
+    // Runs the current flows in the headless runtime, starting their sources
+    fn start_runtime(&mut self) {
+        // Property files may have been edited since they were loaded
+        let environment = self.properties.environment.clone();
+        self.load_properties(&environment);
+        let flows = match self.flow_snapshots(SecureMode::Decrypt) {
+            Ok(flows) => flows,
+            Err(error) => {
+                self.status_message = format!("Run failed: {}", error);
+                return;
+            }
+        };
+        let runtime = Runtime::new(flows)
+            .with_log(self.run_log.clone())
+            .with_history(self.run_history.clone())
+            .with_project_folder(self.project_folder.clone())
+            .into_shared();
+        match runtime.start_sources() {
+            Ok(started) if started.is_empty() => {
+                self.status_message = "Running, no flow has a source".to_string();
+            }
+            Ok(started) => {
+                self.status_message = format!("Running ({}): {}", environment, started.join(", "));
+            }
+            Err(error) => {
+                runtime.stop();
+                self.status_message = format!("Run failed: {}", error);
+                return;
+            }
+        }
+        // Stores that cannot be opened are reported now rather than at their first use
+        for (name, store) in runtime.object_stores() {
+            if let Err(error) = store {
+                self.status_message = format!("Cannot open object store '{}': {}", name, error);
+                runtime.report("ERROR", self.status_message.clone());
+            }
+        }
+        self.runtime = Some(runtime);
+        self.bottom_panel_open = true;
+    }
+
+    // Empties the object stores of the running flows, or only the one named; failures go to the
+    // log and the status bar
+    fn clear_object_stores(&mut self, only: Option<&str>) {
+        let runtime = match &self.runtime {
+            Some(runtime) => runtime,
+            None => return,
+        };
+        let mut cleared = 0;
+        let mut failure = None;
+        for (name, store) in runtime.object_stores() {
+            if only.is_some_and(|only| only != name) {
+                continue;
+            }
+            match store.and_then(|store| store.clear()) {
+                Ok(()) => cleared += 1,
+                Err(error) => {
+                    let message = format!("Cannot clear object store '{}': {}", name, error);
+                    runtime.report("ERROR", message.clone());
+                    failure = Some(message);
+                }
+            }
+        }
+        self.status_message = failure.unwrap_or_else(|| format!("Cleared {} object stores", cleared));
+    }
+
+    fn stop_runtime(&mut self) {
+        if let Some(runtime) = self.runtime.take() {
+            runtime.stop();
+            self.status_message = "Stopped".to_string();
+        }
+    }
+
+    // Writes a standalone Cargo project for the current flows into `generated-service`
+    fn generate_rust_project(&mut self) {
+        let output_folder = self.project_folder.join("generated-service");
+        let environment = self.properties.environment.clone();
+        self.load_properties(&environment);
+        // Secure values stay encrypted; the service decrypts them with its own key
+        let secure_properties = std::fs::read_to_string(secure_file(&properties_folder(&self.project_folder), &environment)).ok();
+        let result = self.flow_snapshots(SecureMode::Keep)
+            .and_then(|flows| {
+                codegen::generate_project("integration-service", &flows, secure_properties.as_deref()).map_err(|error| error.to_string())
+            })
+            .and_then(|files| codegen::write_project(&output_folder, &files).map_err(|error| error.to_string()));
+        self.status_message = match result {
+            Ok(()) => format!("Rust project for {} generated in {}", environment, output_folder.display()),
+            Err(error) => format!("Code generation failed: {}", error),
+        };
+    }
+
+    // Saves the configuration file of the active tab
+    fn save(&mut self) {
+        let document = match self.workspace.active_document() {
+            Some(document) => document.clone(),
+            None => return,
+        };
+        self.status_message = match save_document(&mut self.project, &self.globals, &self.project_folder, &document) {
+            Ok(name) => format!("Saved {}", name),
+            Err(error) => format!("Save failed: {}", error),
+        };
+    }
+
+    fn save_all(&mut self) {
+        self.status_message = match save_all(&mut self.project, &self.globals, &self.project_folder) {
+            Ok(()) => format!("Saved the project in {}", self.project_folder.display()),
+            Err(error) => format!("Save failed: {}", error),
+        };
+    }
+
+    // Writes autosaved copies of unsaved files once the interval has passed; not while the copies
+    // of the last session wait for an answer
+    fn autosave(&mut self, ctx: &Context) {
+        if self.recovery.is_pending() {
+            return;
+        }
+        if self.autosave.is_due() {
+            if let Err(error) = self.autosave.run(&self.project, &self.project_folder) {
+                self.status_message = format!("Autosave failed: {}", error);
+            }
+        }
+        // Idle editors still autosave their last changes
+        if self.workspace.has_unsaved() {
+            ctx.request_repaint_after(AUTOSAVE_INTERVAL);
+        }
+    }
+
+    fn undo(&mut self, tab: usize) {
+        self.workspace.undo(tab, &mut self.project, &self.project_folder);
+    }
+
+    fn redo(&mut self, tab: usize) {
+        self.workspace.redo(tab, &mut self.project, &self.project_folder);
+    }
+
+    // Renames a flow variable as one undo step of the flow's tab, which opens if needed
+    fn rename_variable(&mut self, rename: VariableRename) {
+        let flow = match self.project.find_flow(&rename.flow) {
+            Some(flow) => flow.config.clone(),
+            None => return,
+        };
+        self.workspace.edit(&self.project, Document::Flow(rename.flow.clone()), || rename_variable(&flow, &rename.old, &rename.new));
+        self.status_message = format!("Renamed vars.{} to vars.{}", rename.old, rename.new);
+    }
+
+    // Applies a find and replace, each flow's values as one undo step of its tab
+    fn replace(&mut self, replaces: Vec<FlowReplace>) {
+        let mut changes = 0;
+        for replace in &replaces {
+            let flow = match self.project.find_flow(&replace.flow) {
+                Some(flow) => flow.config.clone(),
+                None => continue,
+            };
+            self.workspace.edit(&self.project, Document::Flow(replace.flow.clone()), || replace.apply(&flow));
+            changes += replace.len();
+        }
+        self.status_message = format!("Replaced {} values in {} flows", changes, replaces.len());
+    }
+
+    // Opening a project folder, typed in or picked from the recent projects
+    fn show_project_menu(&mut self, ui: &mut Ui) {
+        ui.label(RichText::new(self.project_folder.display().to_string()).small().weak());
+        ui.horizontal(|ui| {
+            let response = ui.add(egui::TextEdit::singleline(&mut self.open_folder).hint_text("Project folder"));
+            let entered = response.lost_focus() && ui.input(|input| input.key_pressed(egui::Key::Enter));
+            if (ui.button("Open").clicked() || entered) && !self.open_folder.trim().is_empty() {
+                let folder = PathBuf::from(self.open_folder.trim());
+                self.open_folder.clear();
+                self.open_project(folder);
+                ui.close_menu();
+            }
+        });
+        ui.separator();
+        ui.label("Recent projects");
+        let mut opened = None;
+        for folder in &self.preferences.recent_projects {
+            let current = *folder == self.project_folder;
+            if ui.add_enabled(!current, egui::Button::new(folder.display().to_string())).clicked() {
+                opened = Some(folder.clone());
+            }
+        }
+        if self.preferences.recent_projects.is_empty() {
+            ui.label(RichText::new("None yet").small().weak());
+        }
+        if let Some(folder) = opened {
+            self.open_project(folder);
+            ui.close_menu();
+        }
+    }
+
+    // Panel visibility, theme and zoom; sizes of the panels follow their edges
+    fn show_view_menu(&mut self, ui: &mut Ui) {
+        ui.checkbox(&mut self.left_panel_open, "Palette and project explorer");
+        ui.checkbox(&mut self.right_panel_open, "Inspector");
+        ui.checkbox(&mut self.bottom_panel_open, "Log, runs and object stores");
+        ui.checkbox(&mut self.preferences.minimap, "Minimap");
+        ui.separator();
+        ui.horizontal(|ui| {
+            ui.label("Theme");
+            egui::widgets::global_theme_preference_buttons(ui);
+        });
+        ui.horizontal(|ui| {
+            ui.label("Zoom");
+            let zoom = ui.ctx().zoom_factor();
+            if ui.small_button("\u{2212}").on_hover_text("Ctrl+-").clicked() {
+                ui.ctx().set_zoom_factor((zoom - 0.1).max(0.5));
+            }
+            if ui.small_button(format!("{:.0}%", zoom * 100.0)).on_hover_text("Reset zoom (Ctrl+0)").clicked() {
+                ui.ctx().set_zoom_factor(1.0);
+            }
+            if ui.small_button("+").on_hover_text("Ctrl++").clicked() {
+                ui.ctx().set_zoom_factor((zoom + 0.1).min(3.0));
+            }
+        });
+        ui.separator();
+        if ui.button("Command palette\u{2026}").on_hover_text(self.hover_text("Search every action", &Command::ShowPalette)).clicked() {
+            self.command_palette.show_all();
+            ui.close_menu();
+        }
+        if ui.button("Keyboard shortcuts\u{2026}").clicked() {
+            self.shortcuts_open = true;
+            ui.close_menu();
+        }
+        ui.separator();
+        if ui.button("Reset layout and preferences").clicked() {
+            self.reset_layout(ui.ctx());
+            ui.close_menu();
+        }
+    }
+
+    // Every command of the command palette, with those about the current flows
+    fn commands(&self) -> Vec<Command> {
+        let mut commands = vec![
+            Command::ShowPalette,
+            Command::KeyboardShortcuts,
+            Command::Find,
+            Command::Save,
+            Command::SaveAll,
+            Command::Undo,
+            Command::Redo,
+            Command::ToggleSplit,
+            Command::Run,
+            Command::Stop,
+            Command::GenerateRustProject,
+            Command::ToggleLeftPanel,
+            Command::ToggleRightPanel,
+            Command::ToggleBottomPanel,
+            Command::ToggleMinimap,
+            Command::ResetLayout,
+            Command::NewFlow,
+            Command::NewSubFlow,
+            Command::DeleteSelection,
+        ];
+        commands.extend(self.palette_items.iter()
+            .filter(|item| item.component_level != ComponentLevel::ParentOnly.as_str())
+            .map(|item| Command::AddComponent(item.name.clone())));
+        for flow in self.flow_configs() {
+            let (uuid, name, widget_type) = match flow.lock() {
+                Ok(config) => (config.uuid.clone(), config.name.clone(), config.widget_type.clone()),
+                Err(_) => continue,
+            };
+            if let Some(state) = self.runtime.as_ref().and_then(|runtime| runtime.flow_state(&name)).filter(|_| widget_type != "SubFlow") {
+                commands.push(if state.running { Command::StopFlow(name.clone()) } else { Command::StartFlow(name.clone()) });
+            }
+            commands.push(Command::OpenFlow(uuid, name));
+        }
+        commands
+    }
+
+    fn run_command(&mut self, ctx: &Context, command: Command) {
+        match command {
+            Command::ShowPalette => self.command_palette.show_all(),
+            Command::KeyboardShortcuts => self.shortcuts_open = true,
+            Command::Find => {
+                self.left_panel_open = true;
+                self.left_tab = LeftTab::Search;
+                self.find.focus_query = true;
+            }
+            Command::Save => self.save(),
+            Command::SaveAll => self.save_all(),
+            Command::Undo => self.undo(self.workspace.active),
+            Command::Redo => self.redo(self.workspace.active),
+            Command::ToggleSplit => self.toggle_split(),
+            Command::Run => {
+                if self.runtime.is_none() {
+                    self.start_runtime();
+                }
+            }
+            Command::Stop => self.stop_runtime(),
+            Command::StartFlow(name) => {
+                if let Some(runtime) = &self.runtime {
+                    runtime.start_flow(&name);
+                }
+            }
+            Command::StopFlow(name) => {
+                if let Some(runtime) = &self.runtime {
+                    runtime.stop_flow(&name);
+                }
+            }
+            Command::GenerateRustProject => self.generate_rust_project(),
+            Command::ToggleLeftPanel => self.left_panel_open = !self.left_panel_open,
+            Command::ToggleRightPanel => self.right_panel_open = !self.right_panel_open,
+            Command::ToggleBottomPanel => self.bottom_panel_open = !self.bottom_panel_open,
+            Command::ToggleMinimap => self.preferences.minimap = !self.preferences.minimap,
+            Command::ResetLayout => self.reset_layout(ctx),
+            Command::NewFlow => self.add_flow(false),
+            Command::NewSubFlow => self.add_flow(true),
+            Command::OpenFlow(uuid, _) => {
+                self.workspace.open(&self.project, Document::Flow(uuid.clone()));
+                self.canvas.selected = Some(uuid);
+            }
+            Command::AddComponent(name) => self.insert_component(&name),
+            Command::DeleteSelection => self.delete_selection(),
+        }
+    }
+
+    // Hover text of a button, naming the shortcut of its command
+    fn hover_text(&self, text: &str, command: &Command) -> String {
+        match shortcut(command, &self.preferences.shortcuts) {
+            Some(shortcut) => format!("{} ({})", text, format_shortcut(&shortcut)),
+            None => text.to_string(),
+        }
+    }
+
+    fn toggle_split(&mut self) {
+        let workspace = &mut self.workspace;
+        workspace.split = match workspace.split {
+            Some(_) => None,
+            // The right pane starts with the tab after the active one
+            None if workspace.tabs.len() > 1 => Some((workspace.active + 1) % workspace.tabs.len()),
+            None => workspace.tabs.first().map(|_| workspace.active),
+        };
+    }
+
+    // Adds a flow to the configuration file of the active tab and selects it
+    fn add_flow(&mut self, sub_flow: bool) {
+        let main_flow = if sub_flow {
+            MainFlow::new_sub_flow(self.project.unique_flow_name("SubFlow"))
+        } else {
+            MainFlow::new(self.project.unique_flow_name("MainFlow"))
+        };
+        let uuid = match main_flow.config.lock() {
+            Ok(mut config) => {
+                config.project_folder = self.project_folder.display().to_string();
+                config.uuid.clone()
+            }
+            Err(_) => return,
+        };
+        let file = self.workspace.active_file(&self.project);
+        self.project.add_flow(file, main_flow);
+        // A tab showing a single flow cannot show the new one
+        if let Some(Document::Flow(_)) | None = self.workspace.active_document() {
+            self.workspace.open(&self.project, Document::Flow(uuid.clone()));
+        }
+        self.canvas.selected = Some(uuid.clone());
+        self.canvas.reveal = Some(uuid);
+    }
+
+    // Inserts a component after the selection, or at the end of the flow of the active tab
+    fn insert_component(&mut self, name: &str) {
+        let target = self.canvas.selected.clone().or_else(|| match self.workspace.active_document() {
+            Some(Document::Flow(uuid)) => Some(uuid.clone()),
+            _ => None,
+        });
+        let (target, component) = match (target, components::create_component(name)) {
+            (Some(target), Some(component)) => (target, component),
+            (None, _) => {
+                self.status_message = "Select a component or flow first".to_string();
+                return;
+            }
+            (_, None) => return,
+        };
+        match components::insert_after(&self.flow_configs(), &target, component) {
+            Ok(uuid) => self.reveal(uuid),
+            Err(error) => self.status_message = error,
+        }
+    }
+
+    // Selects a component and scrolls to it, expanding the containers it is collapsed in
+    fn reveal(&mut self, uuid: String) {
+        components::expand_to(&self.flow_configs(), &uuid);
+        self.canvas.selected = Some(uuid.clone());
+        self.canvas.reveal = Some(uuid);
+    }
+
+    // Shows a component of a flow: in the active tab if it shows the flow's configuration file,
+    // else in the flow's own tab
+    fn focus_component(&mut self, flow: &str, component: String) {
+        let file = self.project.locate(flow).map(|(file, _)| file);
+        let shown = match self.workspace.active_document() {
+            Some(Document::Flow(uuid)) => uuid == flow,
+            Some(Document::File(id)) => file.is_some() && self.project.file_index(id) == file,
+            None => false,
+        };
+        if !shown {
+            self.workspace.open(&self.project, Document::Flow(flow.to_string()));
+        }
+        self.reveal(component);
+    }
+
+    // Deletes the selected component; the one before it is selected next
+    fn delete_selection(&mut self) {
+        let selected = match self.canvas.selected.clone() {
+            Some(selected) => selected,
+            None => return,
+        };
+        let flows = self.flow_configs();
+        let previous = components::step_from(&flows, &selected, components::Step::Previous);
+        match components::remove_component(&flows, &selected) {
+            Ok(()) => self.canvas.selected = previous,
+            Err(error) => self.status_message = error,
+        }
+    }
+
+    // Keyboard shortcuts of the commands, and keyboard editing of the canvas while no text field
+    // has focus: arrows move the selection, Delete removes it and typing a component's name
+    // inserts one after it
+    fn handle_shortcuts(&mut self, ctx: &Context) {
+        if self.command_palette.open {
+            return;
+        }
+        if let Some(command) = pressed_command(ctx, &self.commands(), &self.preferences.shortcuts) {
+            self.run_command(ctx, command);
+            return;
+        }
+        let selected = match &self.canvas.selected {
+            Some(selected) if ctx.memory(|memory| memory.focused().is_none()) => selected.clone(),
+            _ => return,
+        };
+        let step = ctx.input_mut(|input| {
+            [
+                (egui::Key::ArrowUp, components::Step::Previous),
+                (egui::Key::ArrowDown, components::Step::Next),
+                (egui::Key::ArrowLeft, components::Step::Container),
+                (egui::Key::ArrowRight, components::Step::FirstChild),
+            ]
+            .iter()
+            .find(|(key, _)| input.consume_key(egui::Modifiers::NONE, *key))
+            .map(|(_, step)| *step)
+        });
+        if let Some(step) = step {
+            if let Some(next) = components::step_from(&self.flow_configs(), &selected, step) {
+                self.reveal(next);
+            }
+            return;
+        }
+        let typed: String = ctx.input(|input| input.events.iter()
+            .filter_map(|event| match event {
+                egui::Event::Text(text) => Some(text.clone()),
+                _ => None,
+            })
+            .collect());
+        if typed.chars().next().is_some_and(char::is_alphabetic) {
+            self.command_palette.show_insert(typed);
         }
     }
 
@@ -75,6 +747,49 @@ impl Panels {
     }
 
 
+    // One pane of the central area: the active tab on the left, the split tab on the right
+    fn show_pane(&mut self, ui: &mut Ui, pane: usize) {
+        let tab = match pane {
+            0 => self.workspace.active,
+            _ => self.workspace.split.unwrap_or(self.workspace.active),
+        };
+        let document = match self.workspace.tabs.get(tab) {
+            Some(tab) => tab.document.clone(),
+            None => {
+                ui.label("Drop items here");
+                ui.allocate_space(ui.available_size());
+                return;
+            }
+        };
+        ui.push_id(("pane", pane), |ui| {
+            if pane == 1 {
+                // The right pane picks its own tab and has its own undo buttons
+                ui.horizontal(|ui| {
+                    let mut split = tab;
+                    egui::ComboBox::from_id_salt("split_tab")
+                        .selected_text(format!("Tab {}", tab + 1))
+                        .show_index(ui, &mut split, self.workspace.tabs.len(), |index| format!("Tab {}", index + 1));
+                    self.workspace.split = Some(split);
+                    if ui.add_enabled(self.workspace.can_undo(tab), egui::Button::new("\u{21B6}").small()).clicked() {
+                        self.undo(tab);
+                    }
+                    if ui.add_enabled(self.workspace.can_redo(tab), egui::Button::new("\u{21B7}").small()).clicked() {
+                        self.redo(tab);
+                    }
+                });
+            }
+            self.canvas.blocks.clear();
+            let scroll = ScrollArea::vertical().show(ui, |ui| {
+                show_document(ui, &mut self.project, &document, &mut self.canvas, self.runtime.as_ref());
+                ui.label("Drop items here");
+                ui.allocate_space(ui.available_size());
+            });
+            if self.preferences.minimap {
+                show_minimap(ui, &scroll, &self.canvas);
+            }
+        });
+    }
+
     pub fn ui(&mut self, ui: &mut egui::Ui) {
         ui.vertical_centered(|ui| {
             ui.heading("Main Application - Central Panel");
@@ -96,9 +811,120 @@ struct AppWrapper {
 
 
 impl App for AppWrapper {
+    fn save(&mut self, storage: &mut dyn eframe::Storage) {
+        self.app.preferences().save(storage);
+    }
+
     fn update(&mut self, ctx: &Context, _frame: &mut eframe::Frame) {
+        // Zoom (Ctrl +/-), theme and window state are saved as they are when the editor closes
+        self.app.preferences.zoom = ctx.zoom_factor();
+        self.app.preferences.theme = ctx.options(|options| options.theme_preference);
+        if let Some(maximized) = ctx.input(|input| input.viewport().maximized) {
+            self.app.preferences.maximized = maximized;
+        }
+
+        // Edits of the last frame go into the undo history of the tabs showing them. Only frames
+        // with input that can edit a document lead to a new record, not every repaint.
+        self.app.workspace.record(&self.app.project);
+        let editing = ctx.input(|input| input.events.iter().any(|event| matches!(
+            event,
+            egui::Event::Key { .. } | egui::Event::Text(_) | egui::Event::Paste(_) | egui::Event::Cut | egui::Event::Ime(_)
+                | egui::Event::PointerButton { pressed: false, .. }
+        )));
+        if editing {
+            self.app.workspace.touch();
+            ctx.request_repaint();
+        }
+        self.app.handle_shortcuts(ctx);
+        self.app.autosave(ctx);
+        if let Some(message) = show_recovery_prompt(ctx, &mut self.app.recovery, &mut self.app.project, &mut self.app.workspace, &self.app.project_folder) {
+            self.app.status_message = message;
+        }
+        let commands = self.app.commands();
+        if let Some(command) = show_command_palette(ctx, &mut self.app.command_palette, &commands, &self.app.preferences.shortcuts) {
+            self.app.run_command(ctx, command);
+        }
+        show_shortcuts_window(ctx, &mut self.app.shortcuts_open, &commands, &mut self.app.preferences.shortcuts);
+
+        // Variable dependencies drive the canvas highlights and the Variables view; problems mark
+        // components on the canvas and on the minimap. Both lock every component, so they only run
+        // again once an edit was recorded.
+        let revision = self.app.workspace.recorded_revision();
+        if self.app.analyzed != Some(revision) {
+            self.app.analyzed = Some(revision);
+            self.app.variable_usages = analyze(&self.app.flow_configs());
+            self.app.canvas.problems = find_problems(&self.app.flow_configs(), &self.app.globals);
+        }
+        let (writers, readers) = match &self.app.canvas.selected {
+            Some(selected) => related_components(&self.app.variable_usages, selected),
+            None => Default::default(),
+        };
+        self.app.canvas.variable_writers = writers;
+        self.app.canvas.variable_readers = readers;
+        self.app.canvas.replay_component = self.app.replay.current_component(&self.app.run_history);
+        // Flow states, log entries and runs change on runtime threads, keep redrawing while flows run
+        if self.app.runtime.is_some() {
+            ctx.request_repaint_after(Duration::from_millis(250));
+        }
+
+        // Toolbar with project wide actions
+        TopBottomPanel::top("toolbar").show(ctx, |ui| {
+            ui.horizontal(|ui| {
+                ui.menu_button("Project", |ui| self.app.show_project_menu(ui));
+                ui.menu_button("View", |ui| self.app.show_view_menu(ui));
+                ui.separator();
+                if self.app.runtime.is_none() {
+                    if ui.button("\u{25B6} Run").on_hover_text(self.app.hover_text("Start every flow", &Command::Run)).clicked() {
+                        self.app.start_runtime();
+                    }
+                } else if ui.button("\u{23F9} Stop").on_hover_text(self.app.hover_text("Stop every flow", &Command::Stop)).clicked() {
+                    self.app.stop_runtime();
+                }
+                if ui.button("Generate Rust Project").clicked() {
+                    self.app.generate_rust_project();
+                }
+                ui.separator();
+                if ui.button("\u{1F4BE} Save").on_hover_text(self.app.hover_text("Save the configuration file of the active tab", &Command::Save)).clicked() {
+                    self.app.save();
+                }
+                if ui.button("Save all").on_hover_text(self.app.hover_text("Save every configuration file and the global elements", &Command::SaveAll)).clicked() {
+                    self.app.save_all();
+                }
+                let active = self.app.workspace.active;
+                if ui.add_enabled(self.app.workspace.can_undo(active), egui::Button::new("\u{21B6}")).on_hover_text(self.app.hover_text("Undo", &Command::Undo)).clicked() {
+                    self.app.undo(active);
+                }
+                if ui.add_enabled(self.app.workspace.can_redo(active), egui::Button::new("\u{21B7}")).on_hover_text(self.app.hover_text("Redo", &Command::Redo)).clicked() {
+                    self.app.redo(active);
+                }
+                let hover = self.app.hover_text("Show a second tab side by side", &Command::ToggleSplit);
+                if ui.selectable_label(self.app.workspace.split.is_some(), "\u{25EB} Split").on_hover_text(hover).clicked() {
+                    self.app.toggle_split();
+                }
+                ui.separator();
+                ui.label("Environment");
+                let mut environment = self.app.properties.environment.clone();
+                egui::ComboBox::from_id_salt("environment")
+                    .selected_text(environment.clone())
+                    .show_ui(ui, |ui| {
+                        for name in &self.app.environments {
+                            ui.selectable_value(&mut environment, name.clone(), name);
+                        }
+                    });
+                // A running runtime keeps the values it was started with
+                if environment != self.app.properties.environment || ui.button("\u{27F3}").on_hover_text("Reload property files").clicked() {
+                    self.app.load_properties(&environment);
+                }
+                ui.label(RichText::new(&self.app.status_message).small());
+            });
+        });
 
         CentralPanel::default().show(ctx, |ui| {
+            if let Some(message) = show_tab_strip(ui, &mut self.app.workspace, &mut self.app.project, &self.app.globals, &self.app.project_folder) {
+                self.app.status_message = message;
+            }
+            ui.separator();
+
             // Drag-and-drop area
             let frame = egui::Frame {
                 fill: Color32::from_rgba_premultiplied(0, 0, 0, 0),
@@ -107,49 +933,41 @@ impl App for AppWrapper {
 
             let (_response, dropped_payload) =
                 ui.dnd_drop_zone::<DraggableItem, ()>(frame, |ui| {
-                // Handle the drop zone but do not draw MainFlow components here
-                // ui.layer_id().order = Order::Background;
-                ui.label("Drop items here").layer_id = LayerId::new(Order::Foreground, Id::new("drop_label"));
-                ui.allocate_space(ui.available_size());
-
-                // For testing purposes, add a label
-
+                // Flows are drawn inside the drop zone; each flow has its own zone for child components
+                match self.app.workspace.split {
+                    Some(_) => {
+                        ui.columns(2, |columns| {
+                            self.app.show_pane(&mut columns[0], 0);
+                            self.app.show_pane(&mut columns[1], 1);
+                        });
+                    }
+                    None => self.app.show_pane(ui, 0),
+                }
             });
 
+            if let Some(refused) = self.app.canvas.refused.take() {
+                self.app.status_message = refused;
+            }
+            // Components dragged by their handle move once the canvas is drawn
+            if let Some((uuid, target)) = self.app.canvas.moved.take() {
+                if let Err(error) = components::move_component(&self.app.flow_configs(), &uuid, &target) {
+                    self.app.status_message = error;
+                }
+            }
+
             if let Some(dropped_item) = dropped_payload {
                 println!("Item dropped: {:?}", dropped_item.name); // Debug print
                 let item = dropped_item;
                 if item.component_level == ComponentLevel::ParentOnly.as_str() {
                     match item.name.as_str() {
                         "MainFlow" => {
-                            let main_flow = MainFlow::new("MainFlow Title".to_string());
-                            self.app.widget_list.push(ComponentType::MainFlow(main_flow));
-                            println!("MainFlow added to widget_list"); // Debug print
+                            // New flows go to the configuration file of the active tab
+                            self.app.add_flow(false);
                         },
                         _ => {},
                     }
                 }
             }
-
-
-            // Render MainFlow components on a specific layer
-            // ui.columns(self.app.widget_list.len(), |ui| {
-                ui.with_layer_id( LayerId::new(Order::Foreground, Id::new("mainflow_layer") ), |ui| {
-                    for widget in &mut self.app.widget_list {
-                        match widget {
-                            ComponentType::MainFlow(main_flow) => {
-                                let title = main_flow.title.clone();
-                                main_flow.show_in_panel(ui, vec2(150.0/*ui.available_width()*/, 100.0), &title);
-                            },
-                            // Handle other components
-                        }
-                    }
-                });
-            // });
-
-
-            // For testing purposes, add a label
-            ui.label("Central Panel is working!");
         });
 
 
@@ -163,7 +981,7 @@ impl App for AppWrapper {
                 SidePanel::left("left_panel")
                     .resizable(true)
                     .default_width(150.0)
-                    .width_range(80.0..=200.0)
+                    .width_range(80.0..=320.0)
                     .show(ctx, |ui| {
                         ui.with_layout(egui::Layout::right_to_left(egui::Align::TOP), |ui| {
                             if ui.button("\u{2B05}").clicked() {
@@ -171,76 +989,72 @@ impl App for AppWrapper {
                             }
                         });
 
+                        ui.horizontal(|ui| {
+                            ui.selectable_value(&mut self.app.left_tab, LeftTab::Palette, "Palette");
+                            ui.selectable_value(&mut self.app.left_tab, LeftTab::Project, "Project");
+                            ui.selectable_value(&mut self.app.left_tab, LeftTab::Search, "Search");
+                        });
+                        ui.separator();
+
+                        if self.app.left_tab == LeftTab::Search {
+                            let revision = self.app.workspace.recorded_revision();
+                            match show_find_replace(ui, &self.app.project, &mut self.app.find, revision) {
+                                Some(FindAction::Focus { flow, component }) => self.app.focus_component(&flow, component),
+                                Some(FindAction::Replace(replaces)) => self.app.replace(replaces),
+                                None => {}
+                            }
+                            return;
+                        }
+
+                        if self.app.left_tab == LeftTab::Project {
+                            ScrollArea::vertical().show(ui, |ui| {
+                                show_project_explorer(
+                                    ui,
+                                    &mut self.app.project,
+                                    &mut self.app.globals,
+                                    &mut self.app.canvas,
+                                    &mut self.app.workspace,
+                                    &self.app.project_folder,
+                                    &mut self.app.explorer,
+                                );
+                            });
+                            return;
+                        }
+
                         // Add draggable items and other UI elements
                         ui.horizontal_wrapped(|ui| {
-                            // Clone draggable items
-                            let (main_flow_draggable, choice_draggable) = (
-                                self.app.main_flow_draggable_item.clone(),
-                                self.app.choice_draggable_item.clone(),
-                            );
-
-                            // Allow dragging `choice_draggable`
-                            if let Some(choice_draggable) = choice_draggable {
-                                if let Some(texture) = choice_draggable.texture.as_ref() {
-                                    let texture_id = texture.id();
-                                    let sized_texture = egui::load::SizedTexture::from((
-                                        texture_id,
-                                        egui::vec2(48.0, 48.0),
-                                    ));
-                                    let the_image = egui::Image::from(sized_texture);
-
-                                    let response = ui
-                                        .dnd_drag_source(
-                                            egui::Id::new("choice_texture"),
-                                            choice_draggable.clone(),
-                                            move |ui| {
-                                                ui.add(egui::ImageButton::new(the_image));
-                                            },
-                                        )
-                                        .response;
-
-                                    if response.drag_started() {
-                                        println!("Dragging Choice started!");
-                                    }
-                                    if response.drag_stopped() {
-                                        println!("Dragging Choice stopped!");
-                                    }
-                                }
-                            }
+                            for draggable in self.app.palette_items.clone() {
+                                let drag_id = egui::Id::new(("palette_item", draggable.name.clone()));
+                                let texture = draggable.texture.clone();
+                                let name = draggable.name.clone();
 
-                            // Allow dragging `main_flow_draggable` similarly
-                            if let Some(main_flow_draggable) = main_flow_draggable {
-                                if let Some(texture) = main_flow_draggable.texture.as_ref() {
-                                    let texture_id = texture.id();
-                                    let sized_texture = egui::load::SizedTexture::from((
-                                        texture_id,
-                                        egui::vec2(48.0, 48.0),
-                                    ));
-                                    let the_image = egui::Image::from(sized_texture);
-
-                                    let response = ui
-                                        .dnd_drag_source(
-                                            egui::Id::new("main_flow_texture"),
-                                            main_flow_draggable.clone(),
-                                            move |ui| {
-                                                ui.add(egui::ImageButton::new(the_image));
-                                            },
-                                        )
-                                        .response;
-
-                                    if response.drag_started() {
-                                        println!("Dragging Main Flow started!");
-                                    }
-                                    if response.drag_stopped() {
-                                        println!("Dragging Main Flow stopped!");
-                                    }
+                                let response = ui
+                                    .dnd_drag_source(drag_id, draggable, move |ui| {
+                                        match texture {
+                                            Some(texture) => {
+                                                let sized_texture = egui::load::SizedTexture::from((
+                                                    texture.id(),
+                                                    egui::vec2(48.0, 48.0),
+                                                ));
+                                                ui.add(egui::ImageButton::new(egui::Image::from(sized_texture)))
+                                                    .on_hover_text(name);
+                                            }
+                                            // Components without an icon are shown by name
+                                            None => {
+                                                ui.add(egui::Button::new(name));
+                                            }
+                                        }
+                                    })
+                                    .response;
+
+                                if response.drag_started() {
+                                    println!("Dragging started!");
+                                }
+                                if response.drag_stopped() {
+                                    println!("Dragging stopped!");
                                 }
                             }
                         });
-
-                        ScrollArea::vertical().show(ui, |ui| {
-                            ui.label("Left panel content here.");
-                        });
                     });
             } else {
                 SidePanel::left("left_panel_toggler")
@@ -261,8 +1075,8 @@ impl App for AppWrapper {
             if right_panel_open {
                 SidePanel::right("right_panel")
                     .resizable(true)
-                    .default_width(150.0)
-                    .width_range(80.0..=200.0)
+                    .default_width(250.0)
+                    .width_range(150.0..=400.0)
                     .show(ctx, |ui| {
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                             if ui.button("\u{27A1}").clicked() {
@@ -270,10 +1084,27 @@ impl App for AppWrapper {
                             }
                         });
                         ui.vertical_centered(|ui| {
-                            ui.heading("Right Panel");
+                            ui.heading("Inspector");
                         });
+                        let flows = self.app.flow_configs();
+                        let selected = self.app.canvas.selected.clone()
+                            .and_then(|uuid| components::find_component(&flows, &uuid).or_else(|| components::find_component(&self.app.globals, &uuid)));
                         ScrollArea::vertical().show(ui, |ui| {
-                            lorem_ipsum(ui);
+                            match selected {
+                                Some(component) => {
+                                    let context = InspectorContext { globals: &self.app.globals, properties: &self.app.properties };
+                                    show_inspector(ui, &component, &context);
+                                }
+                                None => inspector_hint(ui),
+                            }
+                            ui.separator();
+                            ui.heading("Global Elements");
+                            show_global_elements(ui, &mut self.app.globals, &mut self.app.canvas);
+                            ui.separator();
+                            ui.heading("Variables");
+                            if let Some(rename) = show_variables(ui, &self.app.variable_usages, &mut self.app.canvas) {
+                                self.app.rename_variable(rename);
+                            }
                         });
                     });
             } else {
@@ -294,19 +1125,41 @@ impl App for AppWrapper {
 
             if bottom_panel_open {
                 TopBottomPanel::bottom("bottom_panel")
-                    .resizable(false)
+                    .resizable(true)
+                    .default_height(160.0)
                     .min_height(0.0)
                     .show(ctx, |ui| {
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::TOP), |ui| {
                             if ui.button("\u{2B07}").clicked() {
                                 self.app.bottom_panel_open = !self.app.bottom_panel_open;
                             }
+                            ui.selectable_value(&mut self.app.bottom_tab, BottomTab::Log, "Log");
+                            ui.selectable_value(&mut self.app.bottom_tab, BottomTab::Runs, "Runs");
+                            ui.selectable_value(&mut self.app.bottom_tab, BottomTab::ObjectStores, "Object Stores");
+                            if ui.button("Clear").clicked() {
+                                match self.app.bottom_tab {
+                                    BottomTab::Log => {
+                                        if let Ok(mut log) = self.app.run_log.lock() {
+                                            log.clear();
+                                        }
+                                    }
+                                    BottomTab::Runs => {
+                                        if let Ok(mut history) = self.app.run_history.lock() {
+                                            history.clear();
+                                        }
+                                    }
+                                    BottomTab::ObjectStores => self.app.clear_object_stores(None),
+                                }
+                            }
                         });
-                        ui.vertical_centered(|ui| {
-                            ui.heading("Bottom Panel");
-                        });
-                        if ui.button("Close Bottom Panel").clicked() {
-                            self.app.bottom_panel_open = !self.app.bottom_panel_open;
+                        match self.app.bottom_tab {
+                            BottomTab::Log => show_run_log(ui, &self.app.run_log),
+                            BottomTab::Runs => show_run_history(ui, &self.app.run_history, &mut self.app.replay),
+                            BottomTab::ObjectStores => {
+                                if let Some(name) = show_object_stores(ui, self.app.runtime.as_deref()) {
+                                    self.app.clear_object_stores(Some(&name));
+                                }
+                            }
                         }
                     });
             } else {
@@ -328,20 +1181,78 @@ impl App for AppWrapper {
 
 
 
-// Helper function for placeholder text
-fn lorem_ipsum(ui: &mut Ui) {
+// Logger output of editor runs, newest at the bottom
+fn show_run_log(ui: &mut Ui, run_log: &LogBuffer) {
+    let records = run_log.lock().map(|log| log.clone()).unwrap_or_default();
+    ScrollArea::vertical().stick_to_bottom(true).auto_shrink([false, false]).show(ui, |ui| {
+        if records.is_empty() {
+            ui.label(RichText::new("Logger output of running flows appears here.").small().weak());
+        }
+        for record in &records {
+            let color = match record.level.as_str() {
+                "ERROR" => Color32::RED,
+                "WARN" => Color32::from_rgb(230, 160, 0),
+                "DEBUG" | "TRACE" => Color32::GRAY,
+                _ => ui.visuals().text_color(),
+            };
+            ui.label(RichText::new(record.to_line()).monospace().color(color));
+        }
+    });
+}
+
+// Helper function for the empty inspector
+fn inspector_hint(ui: &mut Ui) {
     ui.with_layout(Layout::top_down(Align::LEFT).with_cross_justify(true), |ui| {
-        ui.label(RichText::new(LOREM_IPSUM_LONG).small().weak());
+        ui.label(RichText::new(INSPECTOR_HINT).small().weak());
     });
 }
 
-pub const LOREM_IPSUM_LONG: &str = "Lorem ipsum dolor sit amet, consectetur adipiscing elit.";
+pub const INSPECTOR_HINT: &str = "Select a component on the canvas to edit its properties.";
+
+// `encrypt` and `decrypt <value>` print a secure property value, using the key from
+// `SECURE_PROPERTIES_KEY` or `SECURE_PROPERTIES_KEY_FILE`. The value to encrypt is read from a
+// prompt that does not echo it, or from standard input when that is not a terminal, so that it
+// stays out of the shell history and the process list.
+fn run_secure_command(command: &str, arguments: &[String]) -> Result<String, String> {
+    let cipher = Cipher::from_environment().map_err(|error| error.description)?;
+    match (command, arguments) {
+        ("encrypt", []) => Ok(cipher.encrypt(&read_secret()?)),
+        ("encrypt", _) => Err("Usage: integration-builder encrypt (the value is read from standard input)".to_string()),
+        (_, [value]) => cipher.decrypt(value).map_err(|error| error.description),
+        _ => Err(format!("Usage: integration-builder {} <value>", command)),
+    }
+}
+
+fn read_secret() -> Result<String, String> {
+    use std::io::{IsTerminal, Read};
+    let secret = if std::io::stdin().is_terminal() {
+        rpassword::prompt_password("Value to encrypt: ").map_err(|error| error.to_string())?
+    } else {
+        let mut text = String::new();
+        std::io::stdin().read_to_string(&mut text).map_err(|error| error.to_string())?;
+        text.strip_suffix('\n').map(|text| text.strip_suffix('\r').unwrap_or(text)).unwrap_or(&text).to_string()
+    };
+    Ok(secret)
+}
 
 // Main function to run the application
 fn main() -> eframe::Result<()> {
+    let arguments: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(command) = arguments.first().filter(|command| *command == "encrypt" || *command == "decrypt") {
+        match run_secure_command(command, &arguments[1..]) {
+            Ok(output) => println!("{}", output),
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+        return Ok(());
+    }
 
+    // eframe restores the window position and size of the last session; whether it was
+    // maximized is part of the preferences
     let options = NativeOptions {
-        viewport: egui::ViewportBuilder::default().with_maximized(true) , // Start the window maximized to act like full screen
+        viewport: egui::ViewportBuilder::default().with_app_id("integration-builder"),
         ..Default::default()
     };
 
@@ -349,8 +1260,14 @@ fn main() -> eframe::Result<()> {
         "Egui Panels Example",
         options,
         Box::new(|cc| {
-            // Create the `Panels` instance with the `Context`
-            let app = Panels::new(&cc.egui_ctx);
+            // Create the `Panels` instance with the `Context` and the preferences of the last session
+            let preferences = Preferences::load(cc.storage);
+            cc.egui_ctx.set_theme(preferences.theme);
+            cc.egui_ctx.set_zoom_factor(preferences.zoom);
+            if preferences.maximized {
+                cc.egui_ctx.send_viewport_cmd(egui::ViewportCommand::Maximized(true));
+            }
+            let app = Panels::new(&cc.egui_ctx, preferences);
             // Return the `AppWrapper`
             Ok(Box::new(AppWrapper { app }))
         }),
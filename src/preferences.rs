@@ -0,0 +1,125 @@
+// Editor preferences kept across sessions in eframe's storage, next to the window geometry and
+// the egui memory (panel sizes, collapsed sections) that eframe persists by itself.
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use egui::ThemePreference;
+use serde_json::{json, Value};
+
+pub(crate) const STORAGE_KEY: &str = "preferences";
+pub(crate) const RECENT_LIMIT: usize = 10;
+
+#[derive(Clone, PartialEq)]
+pub(crate) struct Preferences {
+    pub(crate) left_panel_open: bool,
+    pub(crate) right_panel_open: bool,
+    pub(crate) bottom_panel_open: bool,
+    pub(crate) minimap: bool, // Overview in the corner of the canvas
+    pub(crate) theme: ThemePreference,
+    pub(crate) zoom: f32,
+    pub(crate) maximized: bool,
+    pub(crate) last_project: Option<PathBuf>,
+    pub(crate) recent_projects: Vec<PathBuf>, // Most recent first
+    pub(crate) shortcuts: BTreeMap<String, String>, // Keyboard shortcuts changed from their default, by command
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            left_panel_open: true,
+            right_panel_open: true,
+            bottom_panel_open: false,
+            minimap: true,
+            theme: ThemePreference::System,
+            zoom: 1.0,
+            maximized: true,
+            last_project: None,
+            recent_projects: Vec::new(),
+            shortcuts: BTreeMap::new(),
+        }
+    }
+}
+
+fn theme_name(theme: ThemePreference) -> &'static str {
+    match theme {
+        ThemePreference::Dark => "dark",
+        ThemePreference::Light => "light",
+        ThemePreference::System => "system",
+    }
+}
+
+fn theme_from_name(name: &str) -> ThemePreference {
+    match name {
+        "dark" => ThemePreference::Dark,
+        "light" => ThemePreference::Light,
+        _ => ThemePreference::System,
+    }
+}
+
+impl Preferences {
+    pub(crate) fn to_json(&self) -> Value {
+        let path = |path: &PathBuf| Value::String(path.display().to_string());
+        json!({
+            "leftPanelOpen": self.left_panel_open,
+            "rightPanelOpen": self.right_panel_open,
+            "bottomPanelOpen": self.bottom_panel_open,
+            "minimap": self.minimap,
+            "theme": theme_name(self.theme),
+            "zoom": self.zoom,
+            "maximized": self.maximized,
+            "lastProject": self.last_project.as_ref().map(path),
+            "recentProjects": self.recent_projects.iter().map(path).collect::<Vec<Value>>(),
+            "shortcuts": self.shortcuts,
+        })
+    }
+
+    // Missing or malformed entries keep their default
+    pub(crate) fn from_json(value: &Value) -> Self {
+        let defaults = Self::default();
+        let flag = |key: &str, default: bool| value.get(key).and_then(Value::as_bool).unwrap_or(default);
+        Self {
+            left_panel_open: flag("leftPanelOpen", defaults.left_panel_open),
+            right_panel_open: flag("rightPanelOpen", defaults.right_panel_open),
+            bottom_panel_open: flag("bottomPanelOpen", defaults.bottom_panel_open),
+            minimap: flag("minimap", defaults.minimap),
+            theme: value.get("theme").and_then(Value::as_str).map(theme_from_name).unwrap_or(defaults.theme),
+            zoom: value.get("zoom").and_then(Value::as_f64).map(|zoom| zoom as f32).filter(|zoom| (0.5..=3.0).contains(zoom)).unwrap_or(defaults.zoom),
+            maximized: flag("maximized", defaults.maximized),
+            last_project: value.get("lastProject").and_then(Value::as_str).map(PathBuf::from),
+            recent_projects: value.get("recentProjects").and_then(Value::as_array).into_iter().flatten()
+                .filter_map(Value::as_str)
+                .map(PathBuf::from)
+                .collect(),
+            shortcuts: value.get("shortcuts").and_then(Value::as_object).into_iter().flatten()
+                .filter_map(|(command, shortcut)| shortcut.as_str().map(|shortcut| (command.clone(), shortcut.to_string())))
+                .collect(),
+        }
+    }
+
+    pub(crate) fn load(storage: Option<&dyn eframe::Storage>) -> Self {
+        storage.and_then(|storage| storage.get_string(STORAGE_KEY))
+            .and_then(|text| serde_json::from_str::<Value>(&text).ok())
+            .map(|value| Self::from_json(&value))
+            .unwrap_or_default()
+    }
+
+    pub(crate) fn save(&self, storage: &mut dyn eframe::Storage) {
+        storage.set_string(STORAGE_KEY, self.to_json().to_string());
+    }
+
+    // Layout and appearance back to their defaults; the project history and keyboard shortcuts stay
+    pub(crate) fn reset(&mut self) {
+        *self = Self {
+            last_project: self.last_project.take(),
+            recent_projects: std::mem::take(&mut self.recent_projects),
+            shortcuts: std::mem::take(&mut self.shortcuts),
+            ..Self::default()
+        };
+    }
+}
+
+// Moves a project folder to the front of the recent projects
+pub(crate) fn add_recent(recent_projects: &mut Vec<PathBuf>, folder: &Path) {
+    recent_projects.retain(|recent| recent != folder);
+    recent_projects.insert(0, folder.to_path_buf());
+    recent_projects.truncate(RECENT_LIMIT);
+}
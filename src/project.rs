@@ -0,0 +1,420 @@
+// The project: flows and sub-flows grouped into configuration files, plus the test suites kept
+// as files in `tests/` of the project folder. Flow names are unique across the project because
+// the runtime, HTTP paths and generated code refer to flows by name.
+//
+// Each configuration file is saved as `flows/<name>.json` and the global elements as
+// `globals.json`, both in the project folder.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use serde_json::{json, Map, Value};
+use uuid::Uuid;
+use crate::base_configuration::{BaseConfiguration, ComponentLevel, TransformScriptConfig};
+use crate::components::inspector::PropertyKind;
+use crate::components::main_flow::main_flow::MainFlow;
+use crate::components::property_fields;
+
+pub(crate) const TESTS_FOLDER: &str = "tests";
+pub(crate) const FLOWS_FOLDER: &str = "flows";
+pub(crate) const GLOBALS_FILE: &str = "globals.json";
+pub(crate) const DEFAULT_FILE: &str = "main";
+
+// Saved form of a component and its children
+pub(crate) fn component_to_json(config: &BaseConfiguration) -> Value {
+    let properties: Map<String, Value> = config.properties.iter()
+        .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+        .collect();
+    let scripts: Vec<Value> = config.transform_script_configs.iter()
+        .filter_map(|script| script.lock().ok().map(|script| json!({"target": script.target, "script": script.script})))
+        .collect();
+    let children: Vec<Value> = config.children.iter()
+        .filter_map(|child| child.lock().ok())
+        .filter(|child| !child.is_deleted)
+        .map(|child| component_to_json(&child))
+        .collect();
+    json!({
+        "uuid": config.uuid,
+        "widgetType": config.widget_type,
+        "componentLevel": config.component_level.as_str(),
+        "name": config.name,
+        "label": config.label,
+        "initialState": config.initial_state,
+        "maxConcurrency": config.max_concurrency,
+        "trackingEnabled": config.is_tracking_enabled,
+        "collapsed": !config.is_expanded,
+        "properties": properties,
+        "transformScripts": scripts,
+        "children": children,
+    })
+}
+
+pub(crate) fn component_from_json(value: &Value) -> BaseConfiguration {
+    let text = |key: &str| value.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+    let mut config = BaseConfiguration::default();
+    if let Some(uuid) = value.get("uuid").and_then(Value::as_str) {
+        config.uuid = uuid.to_string();
+    }
+    config.widget_type = text("widgetType");
+    config.component_level = ComponentLevel::from_str(&text("componentLevel")).unwrap_or(ComponentLevel::Other);
+    config.name = text("name");
+    config.label = text("label");
+    config.initial_state = text("initialState");
+    config.max_concurrency = value.get("maxConcurrency").and_then(Value::as_i64).unwrap_or(5) as i32;
+    config.is_tracking_enabled = value.get("trackingEnabled").and_then(Value::as_bool).unwrap_or(false);
+    // Files written before containers could collapse have no "collapsed" key and open expanded
+    config.is_expanded = !value.get("collapsed").and_then(Value::as_bool).unwrap_or(false);
+    config.new_component = false;
+    if let Some(properties) = value.get("properties").and_then(Value::as_object) {
+        for (key, property) in properties {
+            config.properties.insert(key.clone(), property.as_str().unwrap_or_default().to_string());
+        }
+    }
+    for script in value.get("transformScripts").and_then(Value::as_array).into_iter().flatten() {
+        let field = |key: &str| script.get(key).and_then(Value::as_str).unwrap_or_default().to_string();
+        config.transform_script_configs.push(Arc::new(Mutex::new(TransformScriptConfig::new(&field("target"), &field("script")))));
+    }
+    for child in value.get("children").and_then(Value::as_array).into_iter().flatten() {
+        config.children.push(Arc::new(Mutex::new(component_from_json(child))));
+    }
+    config
+}
+
+// Replaces a flow's contents with a saved state, keeping its handle so open views stay attached
+pub(crate) fn restore_flow(flow: &MainFlow, state: &Value) {
+    if let Ok(mut config) = flow.config.lock() {
+        let project_folder = config.project_folder.clone();
+        *config = component_from_json(state);
+        config.project_folder = project_folder;
+    }
+}
+
+fn flow_from_json(state: &Value, project_folder: &Path) -> MainFlow {
+    let mut config = component_from_json(state);
+    config.project_folder = project_folder.display().to_string();
+    MainFlow::from_base_config(Arc::new(Mutex::new(config)))
+}
+
+pub(crate) fn flow_to_json(flow: &MainFlow) -> Value {
+    flow.config.lock().map(|config| component_to_json(&config)).unwrap_or(Value::Null)
+}
+
+// Names that become file names must stay one plain file inside their folder on every platform
+pub(crate) fn file_name_problem(name: &str) -> Option<String> {
+    const RESERVED: &[&str] = &[
+        "CON", "PRN", "AUX", "NUL", "COM1", "COM2", "COM3", "COM4", "COM5", "COM6", "COM7", "COM8", "COM9",
+        "LPT1", "LPT2", "LPT3", "LPT4", "LPT5", "LPT6", "LPT7", "LPT8", "LPT9",
+    ];
+    let name = name.trim();
+    if name.is_empty() {
+        Some("Names cannot be empty".to_string())
+    } else if let Some(c) = name.chars().find(|c| c.is_control() || r#"/\:*?"<>|"#.contains(*c)) {
+        let shown = if c.is_control() { c.escape_default().to_string() } else { c.to_string() };
+        Some(format!("Names cannot contain '{}'", shown))
+    } else if name.starts_with('.') || name.ends_with('.') {
+        Some("Names cannot start or end with a dot".to_string())
+    } else if RESERVED.iter().any(|reserved| name.split('.').next().is_some_and(|stem| stem.eq_ignore_ascii_case(reserved))) {
+        Some(format!("'{}' is reserved for devices on Windows", name))
+    } else {
+        None
+    }
+}
+
+fn check_file_name(name: &str) -> io::Result<()> {
+    match file_name_problem(name) {
+        Some(problem) => Err(io::Error::new(io::ErrorKind::InvalidInput, problem)),
+        None => Ok(()),
+    }
+}
+
+pub(crate) fn flow_file(project_folder: &Path, name: &str) -> PathBuf {
+    project_folder.join(FLOWS_FOLDER).join(format!("{}.json", name.trim()))
+}
+
+pub(crate) struct ConfigFile {
+    pub(crate) id: String, // Stays the same when the file is renamed
+    pub(crate) name: String,
+    pub(crate) flows: Vec<MainFlow>, // Flows and sub-flows, in canvas order
+    pub(crate) saved: Value, // Contents when last saved or loaded, null if never saved
+    pub(crate) saved_name: Option<String>, // Name the file was last saved under
+}
+
+impl ConfigFile {
+    pub(crate) fn new(name: String) -> Self {
+        Self { id: Uuid::new_v4().to_string(), name, flows: Vec::new(), saved: Value::Null, saved_name: None }
+    }
+
+    pub(crate) fn to_json(&self) -> Value {
+        json!({"name": self.name, "flows": self.flows.iter().map(flow_to_json).collect::<Vec<Value>>()})
+    }
+
+    // A file never saved counts as changed once it has flows
+    pub(crate) fn is_dirty(&self) -> bool {
+        match self.saved {
+            Value::Null => !self.flows.is_empty(),
+            _ => self.to_json() != self.saved,
+        }
+    }
+
+    // The saved state of one of its flows
+    pub(crate) fn saved_flow(&self, uuid: &str) -> Option<&Value> {
+        self.saved.get("flows")?.as_array()?.iter().find(|flow| flow.get("uuid").and_then(Value::as_str) == Some(uuid))
+    }
+
+    // Replaces its flows with a saved state
+    pub(crate) fn restore(&mut self, state: &Value, project_folder: &Path) {
+        self.flows = state.get("flows").and_then(Value::as_array).into_iter().flatten()
+            .map(|flow| flow_from_json(flow, project_folder))
+            .collect();
+    }
+
+    // Writes `flows/<name>.json`. A file saved under an earlier name is renamed first rather than
+    // written anew and deleted, so that a change of case cannot delete the file on case-insensitive
+    // filesystems.
+    pub(crate) fn save(&mut self, project_folder: &Path) -> io::Result<()> {
+        let state = self.to_json();
+        let path = flow_file(project_folder, &self.name);
+        fs::create_dir_all(project_folder.join(FLOWS_FOLDER))?;
+        if let Some(old_name) = self.saved_name.as_ref().filter(|old_name| **old_name != self.name) {
+            let old_path = flow_file(project_folder, old_name);
+            if old_path.exists() {
+                fs::rename(&old_path, &path)?;
+            }
+        }
+        fs::write(&path, serde_json::to_string_pretty(&state).unwrap_or_default())?;
+        self.saved_name = Some(self.name.clone());
+        self.saved = state;
+        Ok(())
+    }
+}
+
+pub(crate) struct Project {
+    pub(crate) files: Vec<ConfigFile>,
+    pub(crate) active_file: usize, // File that receives flows dropped on the canvas
+}
+
+impl Default for Project {
+    fn default() -> Self {
+        Self { files: vec![ConfigFile::new(DEFAULT_FILE.to_string())], active_file: 0 }
+    }
+}
+
+pub(crate) fn globals_to_json(globals: &[Arc<Mutex<BaseConfiguration>>]) -> Value {
+    Value::Array(globals.iter().filter_map(|global| global.lock().ok().map(|global| component_to_json(&global))).collect())
+}
+
+pub(crate) fn save_globals(project_folder: &Path, globals: &[Arc<Mutex<BaseConfiguration>>]) -> io::Result<()> {
+    fs::create_dir_all(project_folder)?;
+    fs::write(project_folder.join(GLOBALS_FILE), serde_json::to_string_pretty(&globals_to_json(globals)).unwrap_or_default())
+}
+
+fn read_json(path: &Path) -> io::Result<Value> {
+    let text = fs::read_to_string(path)?;
+    serde_json::from_str(&text).map_err(|error| io::Error::new(io::ErrorKind::InvalidData, format!("{}: {}", path.display(), error)))
+}
+
+// Loads the configuration files in `flows/` and the global elements of a project folder; a
+// folder without configuration files gives an empty project
+pub(crate) fn load_project(project_folder: &Path) -> io::Result<(Project, Vec<Arc<Mutex<BaseConfiguration>>>)> {
+    let mut paths: Vec<PathBuf> = match fs::read_dir(project_folder.join(FLOWS_FOLDER)) {
+        Ok(entries) => entries.flatten().map(|entry| entry.path())
+            .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+            .collect(),
+        Err(_) => Vec::new(),
+    };
+    paths.sort();
+    let mut project = Project { files: Vec::new(), active_file: 0 };
+    for path in paths {
+        let state = read_json(&path)?;
+        let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or(DEFAULT_FILE).to_string();
+        let mut file = ConfigFile::new(name.clone());
+        file.restore(&state, project_folder);
+        // Saved as loaded, whatever the stored name field says
+        file.saved = file.to_json();
+        file.saved_name = Some(name);
+        project.files.push(file);
+    }
+    if project.files.is_empty() {
+        project = Project::default();
+    }
+    let globals_path = project_folder.join(GLOBALS_FILE);
+    let globals = if globals_path.exists() {
+        read_json(&globals_path)?.as_array().into_iter().flatten()
+            .map(|global| Arc::new(Mutex::new(component_from_json(global))))
+            .collect()
+    } else {
+        Vec::new()
+    };
+    Ok((project, globals))
+}
+
+fn uuid_of(flow: &MainFlow) -> String {
+    flow.config.lock().map(|config| config.uuid.clone()).unwrap_or_default()
+}
+
+fn name_of(flow: &MainFlow) -> String {
+    flow.config.lock().map(|config| config.name.clone()).unwrap_or_default()
+}
+
+// Whether `name` is in `taken` ignoring case; names stored as files must differ by more than case,
+// since Windows and macOS filesystems treat such names as one file
+pub(crate) fn contains_ignoring_case(taken: &[String], name: &str) -> bool {
+    taken.iter().any(|taken| taken.to_lowercase() == name.to_lowercase())
+}
+
+// First name of the form "<prefix> <n>" not in `taken`
+fn unique_name(taken: &[String], prefix: &str) -> String {
+    (1..).map(|number| format!("{} {}", prefix, number)).find(|name| !contains_ignoring_case(taken, name)).unwrap_or_default()
+}
+
+impl Project {
+    // Root configurations of every flow and sub-flow, file by file
+    pub(crate) fn flow_configs(&self) -> Vec<Arc<Mutex<BaseConfiguration>>> {
+        self.files.iter().flat_map(|file| file.flows.iter().map(|flow| flow.config.clone())).collect()
+    }
+
+    pub(crate) fn flow_names(&self) -> Vec<String> {
+        self.files.iter().flat_map(|file| file.flows.iter().map(name_of)).collect()
+    }
+
+    pub(crate) fn file_names(&self) -> Vec<String> {
+        self.files.iter().map(|file| file.name.clone()).collect()
+    }
+
+    // A flow or sub-flow name no other flow uses
+    pub(crate) fn unique_flow_name(&self, prefix: &str) -> String {
+        unique_name(&self.flow_names(), prefix)
+    }
+
+    pub(crate) fn unique_file_name(&self) -> String {
+        unique_name(&self.file_names(), "config")
+    }
+
+    pub(crate) fn add_file(&mut self, name: String) -> usize {
+        self.files.push(ConfigFile::new(name));
+        self.files.len() - 1
+    }
+
+    pub(crate) fn file_index(&self, id: &str) -> Option<usize> {
+        self.files.iter().position(|file| file.id == id)
+    }
+
+    // Removes a file with its flows, and its saved copy; the last file stays
+    pub(crate) fn remove_file(&mut self, index: usize, project_folder: &Path) -> io::Result<()> {
+        if self.files.len() <= 1 || index >= self.files.len() {
+            return Ok(());
+        }
+        let file = self.files.remove(index);
+        self.active_file = self.active_file.min(self.files.len() - 1);
+        match file.saved_name {
+            Some(name) => fs::remove_file(flow_file(project_folder, &name)),
+            None => Ok(()),
+        }
+    }
+
+    pub(crate) fn find_flow(&self, uuid: &str) -> Option<&MainFlow> {
+        let (file, position) = self.locate(uuid)?;
+        Some(&self.files[file].flows[position])
+    }
+
+    pub(crate) fn add_flow(&mut self, file: usize, flow: MainFlow) {
+        if let Some(file) = self.files.get_mut(file) {
+            file.flows.push(flow);
+        }
+    }
+
+    // File index and position of a flow
+    pub(crate) fn locate(&self, uuid: &str) -> Option<(usize, usize)> {
+        self.files.iter().enumerate().find_map(|(file_index, file)| {
+            file.flows.iter().position(|flow| uuid_of(flow) == uuid).map(|position| (file_index, position))
+        })
+    }
+
+    pub(crate) fn remove_flow(&mut self, uuid: &str) -> Option<MainFlow> {
+        let (file, position) = self.locate(uuid)?;
+        Some(self.files[file].flows.remove(position))
+    }
+
+    // Moves a flow to the end of another file
+    pub(crate) fn move_flow(&mut self, uuid: &str, target: usize) {
+        if target >= self.files.len() {
+            return;
+        }
+        if let Some(flow) = self.remove_flow(uuid) {
+            self.files[target].flows.push(flow);
+        }
+    }
+
+    // Renames a global element and every component setting that references it
+    pub(crate) fn rename_global(&self, global: &Arc<Mutex<BaseConfiguration>>, new_name: &str) {
+        let (widget_type, old_name) = match global.lock() {
+            Ok(mut global) => (global.widget_type.clone(), std::mem::replace(&mut global.name, new_name.to_string())),
+            Err(_) => return,
+        };
+        fn rename(components: &[Arc<Mutex<BaseConfiguration>>], widget_type: &str, old_name: &str, new_name: &str) {
+            for component in components {
+                let children = match component.lock() {
+                    Ok(mut config) => {
+                        for field in property_fields(&config.widget_type) {
+                            let references = matches!(field.kind, PropertyKind::GlobalRef(kind) if kind == widget_type);
+                            if references && config.get_property(field.key) == old_name {
+                                config.properties.insert(field.key.to_string(), new_name.to_string());
+                            }
+                        }
+                        config.children.clone()
+                    }
+                    Err(_) => continue,
+                };
+                rename(&children, widget_type, old_name, new_name);
+            }
+        }
+        rename(&self.flow_configs(), &widget_type, &old_name, new_name);
+    }
+}
+
+// Test suites of a project: the JSON files in its `tests` folder, by name
+pub(crate) fn test_suites(project_folder: &Path) -> Vec<String> {
+    let mut suites: Vec<String> = fs::read_dir(project_folder.join(TESTS_FOLDER))
+        .map(|entries| {
+            entries.flatten()
+                .map(|entry| entry.path())
+                .filter(|path| path.extension().and_then(|extension| extension.to_str()) == Some("json"))
+                .filter_map(|path| path.file_stem().and_then(|stem| stem.to_str()).map(str::to_string))
+                .collect()
+        })
+        .unwrap_or_default();
+    suites.sort();
+    suites
+}
+
+pub(crate) fn test_suite_file(project_folder: &Path, name: &str) -> PathBuf {
+    project_folder.join(TESTS_FOLDER).join(format!("{}.json", name.trim()))
+}
+
+// Creates an empty test suite, failing if one of that name exists
+pub(crate) fn create_test_suite(project_folder: &Path, name: &str) -> io::Result<()> {
+    check_file_name(name)?;
+    let path = test_suite_file(project_folder, name);
+    if contains_ignoring_case(&test_suites(project_folder), name) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Test suite '{}' exists", name)));
+    }
+    fs::create_dir_all(project_folder.join(TESTS_FOLDER))?;
+    fs::write(path, "{\n  \"tests\": []\n}\n")
+}
+
+pub(crate) fn rename_test_suite(project_folder: &Path, name: &str, new_name: &str) -> io::Result<()> {
+    check_file_name(new_name)?;
+    if new_name == name {
+        return Ok(());
+    }
+    // A change of case only renames the suite itself
+    let others: Vec<String> = test_suites(project_folder).into_iter().filter(|suite| suite != name).collect();
+    if contains_ignoring_case(&others, new_name) {
+        return Err(io::Error::new(io::ErrorKind::AlreadyExists, format!("Test suite '{}' exists", new_name)));
+    }
+    fs::rename(test_suite_file(project_folder, name), test_suite_file(project_folder, new_name))
+}
+
+pub(crate) fn delete_test_suite(project_folder: &Path, name: &str) -> io::Result<()> {
+    fs::remove_file(test_suite_file(project_folder, name))
+}
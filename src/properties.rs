@@ -0,0 +1,304 @@
+// Project properties: `${name}` placeholders in component settings are replaced with values from
+// the property file of the selected environment, `properties/<environment>.properties`, and
+// `${secure::name}` placeholders with decrypted values from `properties/<environment>.secure.properties`.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use crate::base_configuration::ComponentSnapshot;
+use crate::components::inspector::PropertyKind;
+use crate::components::property_fields;
+use crate::expression::embedded_expression;
+use crate::support::secure::{parse_properties, SecureProperties};
+
+pub(crate) const PROPERTIES_FOLDER: &str = "properties";
+pub(crate) const DEFAULT_ENVIRONMENTS: &[&str] = &["dev", "test", "prod"];
+// Shown instead of secure values in the editor
+pub(crate) const MASK: &str = "••••••";
+
+pub(crate) fn properties_folder(project_folder: &Path) -> PathBuf {
+    project_folder.join(PROPERTIES_FOLDER)
+}
+
+pub(crate) fn secure_file(folder: &Path, environment: &str) -> PathBuf {
+    folder.join(format!("{}.secure.properties", environment))
+}
+
+// The default environments plus every environment that has a property file
+pub(crate) fn environments(folder: &Path) -> Vec<String> {
+    let mut environments: Vec<String> = DEFAULT_ENVIRONMENTS.iter().map(|name| name.to_string()).collect();
+    if let Ok(entries) = fs::read_dir(folder) {
+        for entry in entries.flatten() {
+            let path = entry.path();
+            if path.extension().and_then(|extension| extension.to_str()) != Some("properties") {
+                continue;
+            }
+            let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+                Some(name) => name.strip_suffix(".secure").unwrap_or(name),
+                None => continue,
+            };
+            if !environments.iter().any(|environment| environment == name) {
+                environments.push(name.to_string());
+            }
+        }
+    }
+    environments
+}
+
+// What happens to `${secure::name}` placeholders
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub(crate) enum SecureMode {
+    // Replaced with the decrypted value, for editor runs
+    Decrypt,
+    // Replaced with a mask once the property is known to exist, for display
+    Mask,
+    // Left in place for generated services, which decrypt them when used
+    Keep,
+}
+
+#[derive(Default)]
+pub(crate) struct Properties {
+    pub(crate) environment: String,
+    values: BTreeMap<String, String>,
+    secure: Option<SecureProperties>,
+}
+
+impl Properties {
+    pub(crate) fn empty(environment: &str) -> Self {
+        Self { environment: environment.to_string(), ..Default::default() }
+    }
+
+    // Missing property files mean an environment without properties
+    pub(crate) fn load(folder: &Path, environment: &str) -> io::Result<Self> {
+        let values = match read_optional(&folder.join(format!("{}.properties", environment)))? {
+            Some(text) => parse_properties(&text),
+            None => BTreeMap::new(),
+        };
+        let secure = read_optional(&secure_file(folder, environment))?.map(|text| SecureProperties::parse(&text));
+        Ok(Self { environment: environment.to_string(), values, secure })
+    }
+
+    pub(crate) fn get(&self, key: &str) -> Option<&str> {
+        self.values.get(key).map(String::as_str)
+    }
+
+    fn secure_value(&self, name: &str, mode: SecureMode) -> Result<String, String> {
+        let secure = self.secure.as_ref().filter(|secure| secure.contains(name)).ok_or_else(|| {
+            format!("Secure property '{}' is not defined for environment '{}'", name, self.environment)
+        })?;
+        match mode {
+            SecureMode::Decrypt => secure.get(name).map_err(|error| error.description),
+            SecureMode::Mask => Ok(MASK.to_string()),
+            SecureMode::Keep => Ok(format!("${{secure::{}}}", name)),
+        }
+    }
+
+    // Value of the placeholder `${key}`
+    fn placeholder_value(&self, key: &str, mode: SecureMode) -> Result<String, String> {
+        match key.strip_prefix("secure::") {
+            Some(name) => self.secure_value(name.trim(), mode),
+            None => self.get(key).map(str::to_string).ok_or_else(|| {
+                format!("Property '{}' is not defined for environment '{}'", key, self.environment)
+            }),
+        }
+    }
+
+    // Replaces every `${name}` and `${secure::name}` in `text`; unknown names are an error
+    pub(crate) fn resolve(&self, text: &str, mode: SecureMode) -> Result<String, String> {
+        let mut resolved = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("${") {
+            let end = rest[start..].find('}').ok_or_else(|| format!("Unclosed placeholder in '{}'", text))? + start;
+            resolved.push_str(&rest[..start]);
+            resolved.push_str(&self.placeholder_value(rest[start + 2..end].trim(), mode)?);
+            rest = &rest[end + 1..];
+        }
+        resolved.push_str(rest);
+        Ok(resolved)
+    }
+
+    // Replaces the placeholders of an expression source so that their values can only be data:
+    // inside a string literal a value is escaped for that literal, anywhere else it becomes a
+    // literal of its own, a number or boolean as such and anything else as a string
+    pub(crate) fn resolve_expression(&self, source: &str, mode: SecureMode) -> Result<String, String> {
+        let mut resolved = String::new();
+        let mut quote = None;
+        let mut rest = source;
+        while let Some(c) = rest.chars().next() {
+            if c == '\\' && quote.is_some() {
+                let escaped = rest.chars().nth(1).map(char::len_utf8).unwrap_or(0);
+                resolved.push_str(&rest[..1 + escaped]);
+                rest = &rest[1 + escaped..];
+                continue;
+            }
+            if rest.starts_with("${") {
+                let end = rest.find('}').ok_or_else(|| format!("Unclosed placeholder in '{}'", source))?;
+                let value = self.placeholder_value(rest[2..end].trim(), mode)?;
+                match quote {
+                    Some(quote) => resolved.push_str(&escape_literal(&value, quote)),
+                    None => resolved.push_str(&value_literal(&value)),
+                }
+                rest = &rest[end + 1..];
+                continue;
+            }
+            quote = match quote {
+                Some(open) if open == c => None,
+                None if c == '"' || c == '\'' => Some(c),
+                quote => quote,
+            };
+            resolved.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        Ok(resolved)
+    }
+}
+
+// Text to put between `quote`s of an expression string literal so that it reads back as `value`
+fn escape_literal(value: &str, quote: char) -> String {
+    let mut escaped = String::new();
+    for c in value.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            _ if c == quote => {
+                escaped.push('\\');
+                escaped.push(c);
+            }
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+// An expression literal holding a placeholder value
+fn value_literal(value: &str) -> String {
+    match serde_json::from_str::<serde_json::Value>(value.trim()) {
+        Ok(literal @ (serde_json::Value::Number(_) | serde_json::Value::Bool(_))) => literal.to_string(),
+        _ => format!("\"{}\"", escape_literal(value, '"')),
+    }
+}
+
+fn read_optional(path: &Path) -> io::Result<Option<String>> {
+    match fs::read_to_string(path) {
+        Ok(text) => Ok(Some(text)),
+        Err(error) if error.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(error) => Err(error),
+    }
+}
+
+// Applies global configurations and property placeholders to a component tree. A component takes
+// the non-empty settings of every global element it references, such as its config or retry policy.
+// Placeholders in expressions are resolved as literals, so property values cannot inject code.
+pub(crate) fn resolve_snapshot(
+    component: &mut ComponentSnapshot,
+    globals: &HashMap<String, ComponentSnapshot>,
+    properties: &Properties,
+    mode: SecureMode,
+) -> Result<(), String> {
+    let references: Vec<(String, &str)> = property_fields(&component.widget_type).iter()
+        .filter_map(|field| match field.kind {
+            PropertyKind::GlobalRef(kind) => Some((component.get_property(field.key).trim().to_string(), kind)),
+            _ => None,
+        })
+        .filter(|(name, _)| !name.is_empty())
+        .collect();
+    for (global_name, kind) in references {
+        let global = globals.get(&global_name).ok_or_else(|| {
+            format!("{}: global configuration '{}' does not exist", component.name, global_name)
+        })?;
+        if global.widget_type != kind {
+            return Err(format!("{}: global configuration '{}' has type {}, expected {}", component.name, global_name, global.widget_type, kind));
+        }
+        for (key, value) in &global.properties {
+            if !value.trim().is_empty() {
+                component.properties.insert(key.clone(), value.clone());
+            }
+        }
+    }
+    let name = component.name.clone();
+    let fields = property_fields(&component.widget_type);
+    let is_condition = component.widget_type == "When";
+    for (key, value) in component.properties.iter_mut() {
+        let kind = fields.iter().find(|field| field.key == key).map(|field| field.kind);
+        let resolved = match kind {
+            _ if is_condition && key == "expression" => properties.resolve_expression(value, mode),
+            Some(PropertyKind::Expression) => properties.resolve_expression(value, mode),
+            Some(PropertyKind::Text) if embedded_expression(value).is_some() => properties.resolve_expression(value, mode),
+            // A plain text stays text whatever the values put in it
+            Some(PropertyKind::Text) => properties.resolve(value, mode).and_then(|resolved| match embedded_expression(&resolved) {
+                Some(_) => Err(format!("a property value turns '{}' into an expression", key)),
+                None => Ok(resolved),
+            }),
+            _ => properties.resolve(value, mode),
+        };
+        *value = resolved.map_err(|error| format!("{}: {}", name, error))?;
+    }
+    for script in &mut component.transform_scripts {
+        script.script = properties.resolve_expression(&script.script, mode).map_err(|error| format!("{}: {}", name, error))?;
+    }
+    for child in &mut component.children {
+        resolve_snapshot(child, globals, properties, mode)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+    use crate::expression::eval::eval_source;
+    use crate::support::message::Message;
+
+    fn properties(values: &[(&str, &str)]) -> Properties {
+        Properties {
+            environment: "dev".to_string(),
+            values: values.iter().map(|(key, value)| (key.to_string(), value.to_string())).collect(),
+            secure: Some(SecureProperties::parse("db.password=![AAAA]")),
+        }
+    }
+
+    fn evaluate(properties: &Properties, source: &str) -> serde_json::Value {
+        let resolved = properties.resolve_expression(source, SecureMode::Decrypt).unwrap();
+        eval_source(&resolved, &Message::default()).unwrap()
+    }
+
+    #[test]
+    fn values_inside_string_literals_are_escaped_for_the_literal() {
+        let properties = properties(&[("greeting", "say \"hi\" \\ it's\nme")]);
+        assert_eq!(evaluate(&properties, "\"${greeting}!\""), json!("say \"hi\" \\ it's\nme!"));
+        assert_eq!(evaluate(&properties, "'${greeting}'"), json!("say \"hi\" \\ it's\nme"));
+    }
+
+    #[test]
+    fn values_outside_literals_become_literals_of_their_own() {
+        let properties = properties(&[("limit", "100"), ("strict", "true"), ("attack", "1 or payload.secret")]);
+        assert_eq!(properties.resolve_expression("payload.total > ${limit}", SecureMode::Decrypt).unwrap(), "payload.total > 100");
+        assert_eq!(evaluate(&properties, "${strict}"), json!(true));
+        // Code in a value stays a string
+        assert_eq!(evaluate(&properties, "${attack}"), json!("1 or payload.secret"));
+    }
+
+    #[test]
+    fn escapes_in_the_source_do_not_open_or_close_literals() {
+        let properties = properties(&[("name", "x")]);
+        let resolved = properties.resolve_expression("\"a\\\"${name}\" ++ ${name}", SecureMode::Decrypt).unwrap();
+        assert_eq!(resolved, "\"a\\\"x\" ++ \"x\"");
+    }
+
+    #[test]
+    fn unknown_or_unclosed_placeholders_are_errors() {
+        let properties = properties(&[]);
+        let error = properties.resolve_expression("${missing}", SecureMode::Decrypt).unwrap_err();
+        assert_eq!(error, "Property 'missing' is not defined for environment 'dev'");
+        assert!(properties.resolve_expression("\"${open\"", SecureMode::Decrypt).is_err());
+    }
+
+    #[test]
+    fn secure_placeholders_are_masked_or_kept() {
+        let properties = properties(&[]);
+        assert_eq!(properties.resolve_expression("\"${secure::db.password}\"", SecureMode::Mask).unwrap(), format!("\"{}\"", MASK));
+        assert_eq!(properties.resolve_expression("${secure::db.password}", SecureMode::Keep).unwrap(), "\"${secure::db.password}\"");
+        assert!(properties.resolve_expression("${secure::other}", SecureMode::Keep).is_err());
+    }
+}
@@ -0,0 +1,218 @@
+// Crash recovery: configuration files with unsaved changes are written to `.recovery/` in the
+// project folder every little while. A recovery file newer than the saved configuration file
+// means the editor stopped before saving, and is offered back on the next start.
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+use chrono::{DateTime, Local};
+use serde_json::Value;
+use crate::project::{flow_file, ConfigFile, Project};
+
+pub(crate) const RECOVERY_FOLDER: &str = ".recovery";
+pub(crate) const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(30);
+
+// Larger files are compared without aligning their lines
+const DIFF_LIMIT: usize = 2000;
+
+fn recovery_file(project_folder: &Path, name: &str) -> PathBuf {
+    project_folder.join(RECOVERY_FOLDER).join(format!("{}.json", name.trim()))
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|metadata| metadata.modified()).ok()
+}
+
+// Writes the dirty configuration files of a project on a timer
+pub(crate) struct Autosave {
+    last: Instant,
+    written: HashMap<String, Value>, // Recovery files written, by file name
+}
+
+impl Default for Autosave {
+    fn default() -> Self {
+        Self { last: Instant::now(), written: HashMap::new() }
+    }
+}
+
+impl Autosave {
+    pub(crate) fn is_due(&self) -> bool {
+        self.last.elapsed() >= AUTOSAVE_INTERVAL
+    }
+
+    // Writes every dirty file whose state changed since it was last written, and removes the
+    // recovery files of files that were saved, renamed or removed since
+    pub(crate) fn run(&mut self, project: &Project, project_folder: &Path) -> io::Result<usize> {
+        self.last = Instant::now();
+        let dirty: Vec<&ConfigFile> = project.files.iter().filter(|file| file.is_dirty()).collect();
+        let mut written = 0;
+        for file in &dirty {
+            let state = file.to_json();
+            if self.written.get(&file.name) == Some(&state) && recovery_file(project_folder, &file.name).exists() {
+                continue;
+            }
+            fs::create_dir_all(project_folder.join(RECOVERY_FOLDER))?;
+            fs::write(recovery_file(project_folder, &file.name), serde_json::to_string_pretty(&state).unwrap_or_default())?;
+            self.written.insert(file.name.clone(), state);
+            written += 1;
+        }
+        let entries = match fs::read_dir(project_folder.join(RECOVERY_FOLDER)) {
+            Ok(entries) => entries,
+            Err(_) => return Ok(written),
+        };
+        for path in entries.flatten().map(|entry| entry.path()) {
+            let name = path.file_stem().and_then(|stem| stem.to_str()).unwrap_or_default().to_string();
+            if !dirty.iter().any(|file| file.name == name) {
+                fs::remove_file(&path)?;
+                self.written.remove(&name);
+            }
+        }
+        Ok(written)
+    }
+}
+
+// A recovery file offered back at startup
+pub(crate) struct Recovery {
+    pub(crate) name: String,
+    pub(crate) path: PathBuf,
+    pub(crate) written: DateTime<Local>,
+    pub(crate) state: Value,
+    pub(crate) saved: Option<Value>, // The saved configuration file, if there is one
+}
+
+impl Recovery {
+    pub(crate) fn discard(&self) -> io::Result<()> {
+        fs::remove_file(&self.path)
+    }
+
+    // Lines of the saved file and of the recovered one, marked '-' when only saved, '+' when
+    // only recovered and ' ' when both have them
+    pub(crate) fn diff(&self) -> Vec<(char, String)> {
+        let pretty = |value: &Value| serde_json::to_string_pretty(value).unwrap_or_default();
+        let saved = self.saved.as_ref().map(pretty).unwrap_or_default();
+        line_diff(&saved, &pretty(&self.state))
+    }
+
+    // Puts the recovered flows into the configuration file of the same name, adding the file if
+    // the project has none. They stay unsaved.
+    pub(crate) fn restore(&self, project: &mut Project, project_folder: &Path) -> usize {
+        let index = match project.files.iter().position(|file| file.name == self.name) {
+            Some(index) => index,
+            None => project.add_file(self.name.clone()),
+        };
+        // Flows moved to other files after the last save come back here only
+        let uuids: Vec<&str> = self.state.get("flows").and_then(Value::as_array).into_iter().flatten()
+            .filter_map(|flow| flow.get("uuid").and_then(Value::as_str))
+            .collect();
+        for (_, file) in project.files.iter_mut().enumerate().filter(|(other, _)| *other != index) {
+            file.flows.retain(|flow| flow.config.lock().map(|config| !uuids.contains(&config.uuid.as_str())).unwrap_or(true));
+        }
+        project.files[index].restore(&self.state, project_folder);
+        index
+    }
+}
+
+// Recovery files written after their configuration file was last saved
+pub(crate) fn find_recoveries(project_folder: &Path) -> Vec<Recovery> {
+    let entries = match fs::read_dir(project_folder.join(RECOVERY_FOLDER)) {
+        Ok(entries) => entries,
+        Err(_) => return Vec::new(),
+    };
+    let mut recoveries = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+            Some(name) if path.extension().and_then(|extension| extension.to_str()) == Some("json") => name.to_string(),
+            _ => continue,
+        };
+        let written = match modified(&path) {
+            Some(written) => written,
+            None => continue,
+        };
+        let saved_path = flow_file(project_folder, &name);
+        if modified(&saved_path).is_some_and(|saved| saved >= written) {
+            continue;
+        }
+        let state = match fs::read_to_string(&path).ok().and_then(|text| serde_json::from_str::<Value>(&text).ok()) {
+            Some(state) => state,
+            None => {
+                println!("Ignoring unreadable recovery file {}", path.display());
+                continue;
+            }
+        };
+        let saved = fs::read_to_string(&saved_path).ok().and_then(|text| serde_json::from_str(&text).ok());
+        recoveries.push(Recovery { name, path, written: written.into(), state, saved });
+    }
+    recoveries.sort_by(|a, b| a.name.cmp(&b.name));
+    recoveries
+}
+
+// Line diff through the longest common subsequence of the two texts
+pub(crate) fn line_diff(old: &str, new: &str) -> Vec<(char, String)> {
+    let old: Vec<&str> = old.lines().collect();
+    let new: Vec<&str> = new.lines().collect();
+    if old.len() > DIFF_LIMIT || new.len() > DIFF_LIMIT {
+        return old.iter().map(|line| ('-', line.to_string()))
+            .chain(new.iter().map(|line| ('+', line.to_string())))
+            .collect();
+    }
+    // common[i][j]: length of the longest common subsequence of old[i..] and new[j..]
+    let mut common = vec![vec![0usize; new.len() + 1]; old.len() + 1];
+    for i in (0..old.len()).rev() {
+        for j in (0..new.len()).rev() {
+            common[i][j] = if old[i] == new[j] { common[i + 1][j + 1] + 1 } else { common[i + 1][j].max(common[i][j + 1]) };
+        }
+    }
+    let (mut i, mut j) = (0, 0);
+    let mut lines = Vec::new();
+    while i < old.len() || j < new.len() {
+        if i < old.len() && j < new.len() && old[i] == new[j] {
+            lines.push((' ', old[i].to_string()));
+            i += 1;
+            j += 1;
+        } else if i < old.len() && (j == new.len() || common[i + 1][j] >= common[i][j + 1]) {
+            lines.push(('-', old[i].to_string()));
+            i += 1;
+        } else {
+            lines.push(('+', new[j].to_string()));
+            j += 1;
+        }
+    }
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn diff(old: &str, new: &str) -> Vec<String> {
+        line_diff(old, new).into_iter().map(|(marker, line)| format!("{}{}", marker, line)).collect()
+    }
+
+    #[test]
+    fn identical_texts_have_only_common_lines() {
+        assert_eq!(diff("a\nb", "a\nb"), vec![" a", " b"]);
+        assert!(line_diff("", "").is_empty());
+    }
+
+    #[test]
+    fn changed_lines_are_removed_then_added_around_the_common_ones() {
+        assert_eq!(diff("a\nb\nc\nd", "a\nx\nc\nd\ne"), vec![" a", "-b", "+x", " c", " d", "+e"]);
+        assert_eq!(diff("", "new"), vec!["+new"]);
+        assert_eq!(diff("old", ""), vec!["-old"]);
+    }
+
+    #[test]
+    fn moved_lines_keep_the_longest_common_run() {
+        assert_eq!(diff("a\nb\nc", "b\nc\na"), vec!["-a", " b", " c", "+a"]);
+    }
+
+    #[test]
+    fn large_texts_are_replaced_whole() {
+        let old: String = (0..=DIFF_LIMIT).map(|index| format!("{}\n", index)).collect();
+        let lines = line_diff(&old, "0");
+        assert_eq!(lines.len(), DIFF_LIMIT + 2);
+        assert!(lines[..DIFF_LIMIT + 1].iter().all(|(marker, _)| *marker == '-'));
+        assert_eq!(lines[DIFF_LIMIT + 1], ('+', "0".to_string()));
+    }
+}
@@ -0,0 +1,48 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use std::time::Duration;
+use crate::runtime::Runtime;
+use crate::support::files::{read_message, resolve_path, run_watcher, FileWatcher};
+use crate::support::message::FlowError;
+
+impl Runtime {
+    // Starts a polling thread for every flow triggered by On New File; it only polls while the flow
+    // is started, so files arriving while it is stopped are picked up once it starts again
+    pub(crate) fn start_file_watchers(self: &Arc<Self>) -> Result<Vec<String>, FlowError> {
+        let mut started = Vec::new();
+        for flow in &self.flows {
+            let source = match flow.source().filter(|source| source.widget_type == "OnNewFile") {
+                Some(source) => source,
+                _ => continue,
+            };
+            let directory = resolve_path(&self.project_folder, source.get_property("directory"));
+            if !directory.is_dir() {
+                return Err(FlowError::new(
+                    "FILE:ILLEGAL_PATH",
+                    format!("{}: {} is not a directory", flow.name, directory.display()),
+                ));
+            }
+            let interval = Duration::from_millis(source.get_property("polling_frequency").trim().parse().unwrap_or(1000));
+            let mut watcher = FileWatcher::new(directory, source.get_property("pattern"));
+            let running = Arc::new(AtomicBool::new(true));
+            if let Ok(mut flags) = self.running_flags.lock() {
+                flags.push(running.clone());
+            }
+
+            let runtime = self.clone();
+            let flow_name = flow.name.clone();
+            thread::spawn(move || {
+                let active = || runtime.flow_state(&flow_name).map(|state| state.running).unwrap_or(false);
+                run_watcher(&mut watcher, interval, &running, active, |path| {
+                    let result = read_message(&path).and_then(|message| runtime.run_flow(&flow_name, message));
+                    if let Err(error) = result {
+                        println!("Run of '{}' for {} failed: {}", flow_name, path.display(), error);
+                    }
+                });
+            });
+            started.push(format!("{} (file watcher)", flow.name));
+        }
+        Ok(started)
+    }
+}
@@ -0,0 +1,78 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::thread;
+use crate::base_configuration::ComponentSnapshot;
+use crate::expression::eval::eval_source;
+use crate::runtime::Runtime;
+use crate::support::http::{error_response, message_from_request, method_allowed, path_matches, response_from_message, HttpRequest, HttpResponse, HttpServer};
+use crate::support::message::FlowError;
+
+// Address a listener binds to, e.g. `0.0.0.0:8081`
+pub(crate) fn listener_address(listener: &ComponentSnapshot) -> String {
+    format!("{}:{}", listener.get_property("host").trim(), listener.get_property("port").trim())
+}
+
+impl Runtime {
+    // Binds one server per distinct listener address and serves the flows behind it on
+    // background threads. Returns the served `address/path` entries.
+    pub(crate) fn start_http_listeners(self: &Arc<Self>) -> Result<Vec<String>, FlowError> {
+        let mut by_address: BTreeMap<String, Vec<String>> = BTreeMap::new();
+        for flow in &self.flows {
+            if let Some(listener) = flow.source().filter(|source| source.widget_type == "HttpListener") {
+                by_address.entry(listener_address(listener)).or_default().push(flow.name.clone());
+            }
+        }
+
+        let mut servers = Vec::new();
+        for (address, flow_names) in by_address {
+            let server = HttpServer::bind(&address).map_err(|error| {
+                FlowError::new("HTTP:LISTENER", format!("Cannot listen on {}: {}", address, error))
+            })?;
+            servers.push((address, flow_names, server));
+        }
+
+        let mut served = Vec::new();
+        for (address, flow_names, server) in servers {
+            for name in &flow_names {
+                if let Some(listener) = self.find_flow(name).and_then(|flow| flow.source()) {
+                    served.push(format!("{}{}", address, listener.get_property("path")));
+                }
+            }
+            if let Ok(mut flags) = self.running_flags.lock() {
+                flags.push(server.running_flag());
+            }
+            let runtime = self.clone();
+            thread::spawn(move || {
+                let result = server.serve(move |request| runtime.handle_http_request(&flow_names, request));
+                if let Err(error) = result {
+                    println!("HTTP listener on {} failed: {}", address, error);
+                }
+            });
+        }
+        Ok(served)
+    }
+
+    // Dispatches a request to the first flow whose listener path matches
+    fn handle_http_request(&self, flow_names: &[String], request: HttpRequest) -> HttpResponse {
+        for name in flow_names {
+            let flow = match self.find_flow(name) {
+                Some(flow) => flow,
+                None => continue,
+            };
+            let listener = match flow.source() {
+                Some(listener) if path_matches(listener.get_property("path"), &request.path) => listener,
+                _ => continue,
+            };
+            if !method_allowed(listener.get_property("allowed_methods"), &request.method) {
+                return HttpResponse::new(405, "text/plain", b"Method not allowed".to_vec());
+            }
+            let result = self.run_flow(name, message_from_request(&request)).and_then(|message| {
+                let status = eval_source(listener.get_property("response_status"), &message)?;
+                let headers = eval_source(listener.get_property("response_headers"), &message)?;
+                Ok(response_from_message(&message, &status, &headers))
+            });
+            return result.unwrap_or_else(|error| error_response(&error));
+        }
+        HttpResponse::new(404, "text/plain", b"No listener for this path".to_vec())
+    }
+}
@@ -0,0 +1,538 @@
+// Headless runtime: interprets flow snapshots without the editor.
+// Generated service projects (see `codegen`) implement the same semantics in compiled form.
+pub(crate) mod file_watcher;
+pub(crate) mod http;
+pub(crate) mod queue_listener;
+pub(crate) mod scheduler;
+pub(crate) mod tracking;
+
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::panic::{self, AssertUnwindSafe};
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex, Weak};
+use std::time::{Duration, Instant};
+use chrono::Utc;
+use uuid::Uuid;
+use serde_json::Value;
+use crate::base_configuration::ComponentSnapshot;
+use crate::expression::eval::{eval_source, eval_text};
+use crate::runtime::tracking::{record_run, variable_changes, RunHistory, RunRecord, RunTrace, TrackingEvent};
+use crate::support::logging::{self, LogRecord};
+use crate::support::message::{FlowError, Message};
+use crate::support::supervisor::{Backpressure, FlowControl, FlowState};
+use crate::support::broker::{self, BrokerRegistry, Destination};
+use crate::support::object_store::{self, ObjectStore, ObjectStoreRegistry, StoreSettings};
+use crate::support::retry::{self, RetryPolicy};
+use crate::support::scopes::AsyncQueue;
+use crate::support::{files, ops, scopes};
+
+// Log entries written by Logger components, shared with the editor's log panel
+pub(crate) type LogBuffer = Arc<Mutex<Vec<LogRecord>>>;
+
+// Oldest entries are dropped beyond this many
+const MAX_LOG_RECORDS: usize = 1000;
+
+// An object store, or why it cannot be opened
+pub(crate) type OpenedStore = Result<Arc<dyn ObjectStore>, FlowError>;
+
+// Category of the log entries the runtime writes itself
+const RUNTIME_CATEGORY: &str = "runtime";
+
+// Timeout of a Scatter-Gather route; projects saved before routes had their own timeout keep
+// the one set on the router
+pub(crate) fn route_timeout(router: &ComponentSnapshot, route: &ComponentSnapshot) -> Option<Duration> {
+    let timeout = match route.get_property("timeout").trim() {
+        "" => router.get_property("timeout").trim(),
+        timeout => timeout,
+    };
+    timeout.parse().ok().map(Duration::from_millis)
+}
+
+// The retry policy an operation references, with the settings merged in from the global element
+pub(crate) fn retry_policy_of(component: &ComponentSnapshot) -> Option<RetryPolicy> {
+    if component.get_property("retry_policy").trim().is_empty() {
+        return None;
+    }
+    let number = |key: &str, default: f64| component.get_property(key).trim().parse().unwrap_or(default);
+    Some(RetryPolicy::new(
+        component.get_property("retry_backoff"),
+        number("retry_max_retries", 3.0) as u32,
+        number("retry_delay", 500.0) as u64,
+        number("retry_max_delay", 10000.0) as u64,
+        number("retry_multiplier", 2.0),
+        number("retry_jitter", 0.0),
+        component.get_property("retry_errors"),
+    ))
+}
+
+pub(crate) struct Runtime {
+    pub(crate) flows: Vec<ComponentSnapshot>,
+    running_flags: Mutex<Vec<Arc<AtomicBool>>>, // One per started source; cleared by `stop`
+    log: LogBuffer,
+    print_log: bool, // Headless runs also print records to stdout; editor runs only fill the panel
+    history: RunHistory,
+    controls: HashMap<String, Arc<FlowControl>>, // Start/stop state and concurrency limit per flow name
+    project_folder: PathBuf, // Relative file paths resolve against it
+    brokers: BrokerRegistry, // Message brokers per broker config, emptied with the runtime
+    async_queues: Mutex<HashMap<String, Arc<AsyncQueue>>>, // Per Async scope uuid
+    object_stores: ObjectStoreRegistry, // Per object store name, or component uuid for private stores
+    this: Weak<Runtime>, // Set by `into_shared`; Async scopes hand it to their threads
+}
+
+impl Runtime {
+    pub(crate) fn new(flows: Vec<ComponentSnapshot>) -> Self {
+        let controls = flows.iter()
+            .map(|flow| {
+                let backpressure = Backpressure::from_name(flow.get_property("backpressure"));
+                let control = FlowControl::new(&flow.name, flow.max_concurrency.max(1) as usize, backpressure, !flow.is_initially_stopped());
+                (flow.name.clone(), control)
+            })
+            .collect();
+        Self {
+            flows,
+            controls,
+            running_flags: Mutex::new(Vec::new()),
+            log: LogBuffer::default(),
+            print_log: true,
+            history: RunHistory::default(),
+            project_folder: std::env::current_dir().unwrap_or_default(),
+            brokers: BrokerRegistry::default(),
+            async_queues: Mutex::new(HashMap::new()),
+            object_stores: ObjectStoreRegistry::default(),
+            this: Weak::new(),
+        }
+    }
+
+    // Shares the runtime between threads; Async and parallel scopes only run in a shared runtime
+    pub(crate) fn into_shared(self) -> Arc<Self> {
+        Arc::new_cyclic(|this| Self { this: this.clone(), ..self })
+    }
+
+    // The shared runtime, for scopes whose work outlives the call that started it
+    fn shared(&self, scope: &ComponentSnapshot) -> Result<Arc<Self>, FlowError> {
+        self.this.upgrade().ok_or_else(|| {
+            FlowError::new("RUNTIME:UNSUPPORTED", format!("Scope '{}' needs a shared runtime", scope.name))
+        })
+    }
+
+    // Editor runs pass the log panel's buffer; their records then go only there
+    pub(crate) fn with_log(mut self, log: LogBuffer) -> Self { self.log = log;self.print_log = false;self }
+    pub(crate) fn with_history(mut self, history: RunHistory) -> Self { self.history = history;self }
+    pub(crate) fn with_project_folder(mut self, folder: PathBuf) -> Self { self.project_folder = folder;self }
+
+    // Keeps a Logger entry for the log panel; headless runs also print it as structured JSON
+    pub(crate) fn log(&self, record: LogRecord) {
+        if self.print_log {
+            logging::log(&record);
+        }
+        if let Ok(mut log) = self.log.lock() {
+            log.push(record);
+            if log.len() > MAX_LOG_RECORDS {
+                let excess = log.len() - MAX_LOG_RECORDS;
+                log.drain(..excess);
+            }
+        }
+    }
+
+    // Logs a problem of the runtime itself, such as a failed Async scope, under the runtime category
+    pub(crate) fn report(&self, level: &str, message: String) {
+        self.log(LogRecord::new(level, RUNTIME_CATEGORY, message));
+    }
+
+    // Stops every source started by this runtime
+    pub(crate) fn stop(&self) {
+        if let Ok(mut flags) = self.running_flags.lock() {
+            for flag in flags.drain(..) {
+                flag.store(false, Ordering::SeqCst);
+            }
+        }
+    }
+
+    pub(crate) fn start_flow(&self, name: &str) {
+        if let Some(control) = self.controls.get(name) {
+            control.start();
+        }
+    }
+
+    // The flow stops accepting runs; runs in flight finish
+    pub(crate) fn stop_flow(&self, name: &str) {
+        if let Some(control) = self.controls.get(name) {
+            control.stop();
+        }
+    }
+
+    pub(crate) fn flow_state(&self, name: &str) -> Option<FlowState> {
+        self.controls.get(name).map(|control| control.state())
+    }
+
+    pub(crate) fn find_flow(&self, name: &str) -> Option<&ComponentSnapshot> {
+        self.flows.iter().find(|flow| flow.name == name)
+    }
+
+    pub(crate) fn run_flow(&self, name: &str, message: Message) -> Result<Message, FlowError> {
+        let flow = self.find_flow(name).ok_or_else(|| {
+            FlowError::new("RUNTIME:FLOW_NOT_FOUND", format!("No flow named '{}'", name))
+        })?;
+        // Held until the run finishes
+        let _permit = match self.controls.get(name) {
+            Some(control) => Some(control.acquire()?),
+            None => None,
+        };
+        if !flow.is_tracking_enabled {
+            return self.execute_all(&flow.children, message, None);
+        }
+
+        let trace = RunTrace::new(&flow.name);
+        let started = Utc::now();
+        let clock = Instant::now();
+        let result = self.execute_all(&flow.children, message.clone(), Some(&trace));
+        record_run(&self.history, RunRecord {
+            id: trace.run_id.clone(),
+            flow: flow.name.clone(),
+            started,
+            duration: clock.elapsed(),
+            input: message,
+            events: trace.into_events(),
+            error: result.as_ref().err().cloned(),
+            parent_run: None,
+            scope: None,
+        });
+        result
+    }
+
+    // Runs the components of an Async scope. A failure is only reported; runs started by a
+    // tracked run are tracked as well, linked to that run (`parent` holds its id and flow).
+    fn run_async(&self, scope: &ComponentSnapshot, message: Message, parent: Option<(String, String)>) {
+        let (parent_run, flow) = match parent {
+            Some(parent) => parent,
+            None => {
+                if let Err(error) = self.execute_all(&scope.children, message, None) {
+                    self.report("ERROR", format!("Async scope '{}' failed: {}", scope.name, error));
+                }
+                return;
+            }
+        };
+        let trace = RunTrace::new(&flow);
+        let started = Utc::now();
+        let clock = Instant::now();
+        let result = self.execute_all(&scope.children, message.clone(), Some(&trace));
+        if let Err(error) = &result {
+            self.report("ERROR", format!("Async scope '{}' of '{}' failed: {}", scope.name, flow, error));
+        }
+        record_run(&self.history, RunRecord {
+            id: trace.run_id.clone(),
+            flow,
+            started,
+            duration: clock.elapsed(),
+            input: message,
+            events: trace.into_events(),
+            error: result.err(),
+            parent_run: Some(parent_run),
+            scope: Some(scope.name.clone()),
+        });
+    }
+
+    // The queue of an Async scope, created on its first run
+    fn async_queue(&self, scope: &ComponentSnapshot) -> Arc<AsyncQueue> {
+        let mut queues = self.async_queues.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+        queues.entry(scope.uuid.clone())
+            .or_insert_with(|| AsyncQueue::new(scope.get_property("max_concurrency").trim().parse().unwrap_or(4)))
+            .clone()
+    }
+
+    // The object store a component references, or its own in-memory store
+    fn object_store(&self, component: &ComponentSnapshot) -> Result<Arc<dyn ObjectStore>, FlowError> {
+        let name = component.get_property("object_store").trim();
+        if name.is_empty() {
+            return self.object_stores.get(&component.uuid, StoreSettings::default());
+        }
+        let number = |key: &str| component.get_property(key).trim().parse::<f64>().unwrap_or(0.0).max(0.0);
+        let file = object_store::store_file(name, component.get_property("store_file"));
+        let settings = StoreSettings::new(
+            component.get_property("store_persistence"),
+            number("store_ttl") as u64,
+            number("store_max_entries") as usize,
+            files::resolve_path(&self.project_folder, &file),
+        );
+        self.object_stores.get(name, settings)
+    }
+
+    // Every object store the flows use, opened if need be, with the name the editor shows for it
+    // and the error of a store that cannot be opened
+    pub(crate) fn object_stores(&self) -> Vec<(String, OpenedStore)> {
+        fn collect<'a>(components: &'a [ComponentSnapshot], users: &mut Vec<&'a ComponentSnapshot>) {
+            for component in components {
+                if matches!(component.widget_type.as_str(), "Cache" | "IdempotentMessageValidator") {
+                    users.push(component);
+                }
+                collect(&component.children, users);
+            }
+        }
+        let mut users = Vec::new();
+        collect(&self.flows, &mut users);
+        let mut stores: Vec<(String, OpenedStore)> = Vec::new();
+        for component in users {
+            let label = match component.get_property("object_store").trim() {
+                "" => format!("{} (private)", component.name),
+                name => name.to_string(),
+            };
+            let store = self.object_store(component);
+            let listed = match &store {
+                Ok(store) => stores.iter().any(|(_, listed)| listed.as_ref().is_ok_and(|listed| Arc::ptr_eq(listed, store))),
+                Err(_) => stores.iter().any(|(name, _)| *name == label),
+            };
+            if !listed {
+                stores.push((label, store));
+            }
+        }
+        stores
+    }
+
+    // Records a run that failed before any component ran, such as a message whose redeliveries
+    // are exhausted, so it shows up in the run history of a tracked flow
+    pub(crate) fn record_failure(&self, name: &str, input: Message, error: FlowError) {
+        if !self.find_flow(name).is_some_and(|flow| flow.is_tracking_enabled) {
+            return;
+        }
+        record_run(&self.history, RunRecord {
+            id: Uuid::new_v4().to_string(),
+            flow: name.to_string(),
+            started: Utc::now(),
+            duration: Duration::ZERO,
+            input,
+            events: Vec::new(),
+            error: Some(error),
+            parent_run: None,
+            scope: None,
+        });
+    }
+
+    // Runs components one after the other, each receiving the message produced by the previous one
+    pub(crate) fn execute_all(&self, components: &[ComponentSnapshot], mut message: Message, trace: Option<&RunTrace>) -> Result<Message, FlowError> {
+        for component in components {
+            message = self.execute(component, message, trace)?;
+        }
+        Ok(message)
+    }
+
+    // Executes one component, recording a tracking event when the run is traced
+    pub(crate) fn execute(&self, component: &ComponentSnapshot, message: Message, trace: Option<&RunTrace>) -> Result<Message, FlowError> {
+        let trace = match trace {
+            Some(trace) => trace,
+            None => return self.execute_component(component, message, None),
+        };
+        let sequence = trace.next_sequence();
+        let input_payload = message.payload.clone();
+        let input_variables = message.variables.clone();
+        let started = Utc::now();
+        let clock = Instant::now();
+        let result = self.execute_component(component, message, Some(trace));
+        let (payload, changes, error) = match &result {
+            Ok(output) => (output.payload.clone(), variable_changes(&input_variables, &output.variables), None),
+            Err(error) => (input_payload, Vec::new(), Some(error.clone())),
+        };
+        trace.record(TrackingEvent {
+            sequence,
+            component_uuid: component.uuid.clone(),
+            component_name: component.name.clone(),
+            widget_type: component.widget_type.clone(),
+            started,
+            duration: clock.elapsed(),
+            payload,
+            variable_changes: changes,
+            error,
+        });
+        result
+    }
+
+    // Runs an operation, retrying it as its retry policy allows
+    fn execute_component(&self, component: &ComponentSnapshot, message: Message, trace: Option<&RunTrace>) -> Result<Message, FlowError> {
+        match retry_policy_of(component) {
+            Some(policy) => retry::with_policy(&policy, |retry| self.report("WARN", retry), || self.execute_operation(component, message.clone(), trace)),
+            None => self.execute_operation(component, message, trace),
+        }
+    }
+
+    fn execute_operation(&self, component: &ComponentSnapshot, mut message: Message, trace: Option<&RunTrace>) -> Result<Message, FlowError> {
+        match component.widget_type.as_str() {
+            // Sources only trigger the flow; the message they produce is the flow input
+            "HttpListener" | "Scheduler" | "OnNewFile" | "QueueListener" => Ok(message),
+            "HttpRequest" => {
+                let url = ops::to_text(&eval_text(component.get_property("url"), &message)?);
+                let url = crate::support::http::join_url(component.get_property("base_url"), &url);
+                let headers = eval_source(component.get_property("headers"), &message)?;
+                let body = eval_source(component.get_property("body"), &message)?;
+                let timeout = component.get_property("timeout").trim().parse().unwrap_or(30000);
+                crate::support::http::execute_request(&message, component.get_property("method"), &url, &headers, &body, timeout)
+            }
+            "Database" => {
+                let parameters = eval_source(component.get_property("input_parameters"), &message)?;
+                crate::support::database::execute(
+                    &message,
+                    component.get_property("database_file").trim(),
+                    component.get_property("operation"),
+                    component.get_property("sql"),
+                    &parameters,
+                )
+            }
+            "File" => {
+                let path = ops::to_text(&eval_text(component.get_property("path"), &message)?);
+                match component.get_property("operation") {
+                    "Write" => {
+                        let content = eval_source(component.get_property("content"), &message)?;
+                        files::write(&self.project_folder, &path, &content, component.get_property("write_mode"))?;
+                        Ok(message)
+                    }
+                    "List" => files::list(&message, &self.project_folder, &path, component.get_property("pattern")),
+                    "Move" => {
+                        let target = ops::to_text(&eval_text(component.get_property("target_path"), &message)?);
+                        files::move_file(&self.project_folder, &path, &target, component.get_property("overwrite") == "true")?;
+                        Ok(message)
+                    }
+                    _ => files::read(&message, &self.project_folder, &path),
+                }
+            }
+            "Publish" => {
+                let broker = self.brokers.get(component.get_property("config"), component.get_property("broker"))?;
+                let destination = Destination::new(component.get_property("destination_type"), component.get_property("destination"))?;
+                let body = eval_source(component.get_property("body"), &message)?;
+                let properties = eval_source(component.get_property("properties"), &message)?;
+                broker::publish(&*broker, &destination, &message, body, &properties)?;
+                Ok(message)
+            }
+            "Consume" => {
+                let broker = self.brokers.get(component.get_property("config"), component.get_property("broker"))?;
+                let timeout = component.get_property("timeout").trim().parse().unwrap_or(10000);
+                broker::consume(&*broker, component.get_property("queue"), timeout, &message)
+            }
+            "Transformer" => {
+                // All scripts see the incoming message; results are applied afterwards
+                let mut results = Vec::new();
+                for script in &component.transform_scripts {
+                    results.push((script.target.clone(), eval_source(&script.script, &message)?));
+                }
+                for (target, value) in results {
+                    if target.is_empty() {
+                        message.payload = value;
+                    } else {
+                        message.variables.insert(target, value);
+                    }
+                }
+                Ok(message)
+            }
+            "Choice" => {
+                for route in &component.children {
+                    let matches = match route.widget_type.as_str() {
+                        "When" => ops::truthy(&eval_source(route.get_property("expression"), &message)?),
+                        _ => true,
+                    };
+                    if matches {
+                        return self.execute_all(&route.children, message, trace);
+                    }
+                }
+                Ok(message)
+            }
+            "SetVariable" => {
+                let value = eval_source(component.get_property("value"), &message)?;
+                message.variables.insert(component.get_property("variable_name").trim().to_string(), value);
+                Ok(message)
+            }
+            "RemoveVariable" => {
+                message.variables.remove(component.get_property("variable_name").trim());
+                Ok(message)
+            }
+            "SetPayload" => {
+                message.payload = eval_source(component.get_property("value"), &message)?;
+                if !component.get_property("mime_type").trim().is_empty() {
+                    message.mime_type = component.get_property("mime_type").trim().to_string();
+                }
+                Ok(message)
+            }
+            "Logger" => {
+                let text = match component.get_property("message") {
+                    "" => ops::to_text(&message.payload),
+                    source => ops::to_text(&eval_text(source, &message)?),
+                };
+                self.log(LogRecord::new(component.get_property("level"), component.get_property("category"), text));
+                Ok(message)
+            }
+            "ForEach" => {
+                let collection = eval_source(component.get_property("collection"), &message)?;
+                let batch_size = component.get_property("batch_size").trim().parse().unwrap_or(1);
+                let counter = match component.get_property("counter_variable").trim() {
+                    "" => "counter",
+                    counter => counter,
+                };
+                let original_payload = message.payload.clone();
+                for (index, item) in scopes::batches(scopes::collection_items(&collection), batch_size).into_iter().enumerate() {
+                    message.payload = item;
+                    message.variables.insert(counter.to_string(), Value::from(index + 1));
+                    message = self.execute_all(&component.children, message, trace)?;
+                }
+                message.payload = original_payload;
+                Ok(message)
+            }
+            "ParallelForEach" => {
+                let collection = eval_source(component.get_property("collection"), &message)?;
+                let max_concurrency = component.get_property("max_concurrency").trim().parse().unwrap_or(4);
+                let timeout = component.get_property("timeout").trim().parse().ok().map(Duration::from_millis);
+                let routes: Vec<Message> = scopes::collection_items(&collection)
+                    .into_iter()
+                    .map(|item| Message { payload: item, ..message.clone() })
+                    .collect();
+                let timeouts = vec![timeout; routes.len()];
+                let (runtime, trace) = (self.shared(component)?, trace.cloned());
+                let children = component.children.clone();
+                let results = scopes::run_parallel(routes, max_concurrency, timeouts, move |_, route| {
+                    runtime.execute_all(&children, route, trace.as_ref())
+                })?;
+                message.payload = Value::Array(results.into_iter().map(|result| result.payload).collect());
+                Ok(message)
+            }
+            "Async" => {
+                let runtime = self.shared(component)?;
+                let scope = component.clone();
+                let copy = message.clone();
+                let parent = trace.map(|trace| (trace.run_id.clone(), trace.flow.clone()));
+                self.async_queue(component).submit(Box::new(move || {
+                    let name = scope.name.clone();
+                    if panic::catch_unwind(AssertUnwindSafe(|| runtime.run_async(&scope, copy, parent))).is_err() {
+                        runtime.report("ERROR", format!("Async scope '{}' panicked", name));
+                    }
+                }));
+                Ok(message)
+            }
+            "UntilSuccessful" => {
+                let max_retries = component.get_property("max_retries").trim().parse().unwrap_or(5);
+                let delay = component.get_property("milliseconds_between_retries").trim().parse().unwrap_or(1000);
+                retry::until_successful(max_retries, delay, |retry| self.report("WARN", retry), || self.execute_all(&component.children, message.clone(), trace))
+            }
+            "Cache" => {
+                let key = ops::to_text(&eval_source(component.get_property("key"), &message)?);
+                let store = self.object_store(component)?;
+                object_store::cached(&*store, &key, message, |message| self.execute_all(&component.children, message, trace))
+            }
+            "IdempotentMessageValidator" => {
+                let id = ops::to_text(&eval_source(component.get_property("id_expression"), &message)?);
+                object_store::validate_unique(&*self.object_store(component)?, &id)?;
+                Ok(message)
+            }
+            "ScatterGather" => {
+                let max_concurrency = component.get_property("max_concurrency").trim().parse().unwrap_or(4);
+                let timeouts = component.children.iter().map(|route| route_timeout(component, route)).collect();
+                let routes = vec![message.clone(); component.children.len()];
+                let (runtime, trace) = (self.shared(component)?, trace.cloned());
+                let lanes: Vec<Vec<ComponentSnapshot>> = component.children.iter().map(|route| route.children.clone()).collect();
+                let results = scopes::run_parallel(routes, max_concurrency, timeouts, move |index, route| {
+                    runtime.execute_all(&lanes[index], route, trace.as_ref())
+                })?;
+                Ok(scopes::gather(message, results))
+            }
+            other => Err(FlowError::new(
+                "RUNTIME:UNSUPPORTED",
+                format!("Component '{}' ({}) is not supported by the runtime", component.name, other),
+            )),
+        }
+    }
+}
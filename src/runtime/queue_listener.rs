@@ -0,0 +1,55 @@
+use std::sync::atomic::AtomicBool;
+use std::sync::Arc;
+use std::thread;
+use crate::runtime::Runtime;
+use crate::support::broker::{listener_queue, run_listener, Destination, ListenerSettings};
+use crate::support::message::FlowError;
+
+impl Runtime {
+    // Starts a receiving thread for every flow triggered by a Queue Listener. It only receives
+    // while the flow is started, so messages published while it is stopped wait in the queue.
+    pub(crate) fn start_queue_listeners(self: &Arc<Self>) -> Result<Vec<String>, FlowError> {
+        let mut started = Vec::new();
+        for flow in &self.flows {
+            let source = match flow.source().filter(|source| source.widget_type == "QueueListener") {
+                Some(source) => source,
+                _ => continue,
+            };
+            let broker = self.brokers.get(source.get_property("config"), source.get_property("broker"))?;
+            let destination = Destination::new(source.get_property("destination_type"), source.get_property("destination"))
+                .map_err(|error| FlowError::new(&error.error_type, format!("{}: {}", flow.name, error.description)))?;
+            // Subscribing now keeps topic messages published before the first receive
+            let queue = listener_queue(&*broker, &destination, &flow.name)?;
+            let settings = ListenerSettings::new(
+                source.get_property("ack_mode"),
+                source.get_property("max_redelivery").trim().parse().unwrap_or(3),
+                source.get_property("dead_letter_queue"),
+            );
+            let running = Arc::new(AtomicBool::new(true));
+            if let Ok(mut flags) = self.running_flags.lock() {
+                flags.push(running.clone());
+            }
+
+            let runtime = self.clone();
+            let flow_name = flow.name.clone();
+            let description = format!("{} (queue listener on {})", flow.name, queue);
+            thread::spawn(move || {
+                let active = || runtime.flow_state(&flow_name).map(|state| state.running).unwrap_or(false);
+                run_listener(
+                    &*broker,
+                    &queue,
+                    &settings,
+                    &running,
+                    active,
+                    |message| runtime.run_flow(&flow_name, message),
+                    |message, error| {
+                        println!("{}: {}", flow_name, error);
+                        runtime.record_failure(&flow_name, message, error);
+                    },
+                );
+            });
+            started.push(description);
+        }
+        Ok(started)
+    }
+}
@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::thread;
+use crate::runtime::Runtime;
+use crate::support::message::{FlowError, Message};
+use crate::support::scheduler::{run_schedule, OverlapPolicy, RunLimiter, Schedule};
+
+// Builds the schedule described by a Scheduler's properties
+pub(crate) fn schedule_of(properties: &HashMap<String, String>) -> Result<Schedule, String> {
+    let property = |key: &str| properties.get(key).cloned().unwrap_or_default();
+    if property("mode") == "Cron" {
+        Schedule::cron(&property("expression"), &property("timezone"))
+    } else {
+        Schedule::fixed_frequency(&property("frequency"), &property("start_delay"), &property("time_unit"))
+    }
+}
+
+impl Runtime {
+    // Starts a timer thread for every flow triggered by a Scheduler; it only fires while the flow
+    // is started. Returns a description of each started scheduler.
+    pub(crate) fn start_schedulers(self: &Arc<Self>) -> Result<Vec<String>, FlowError> {
+        let mut started = Vec::new();
+        for flow in &self.flows {
+            let scheduler = match flow.source().filter(|source| source.widget_type == "Scheduler") {
+                Some(scheduler) => scheduler,
+                _ => continue,
+            };
+            let schedule = schedule_of(&scheduler.properties).map_err(|error| {
+                FlowError::new("SCHEDULER:INVALID", format!("{}: {}", flow.name, error))
+            })?;
+            let limiter = RunLimiter::new(flow.max_concurrency.max(1) as usize, OverlapPolicy::from_name(scheduler.get_property("overlap")));
+            let running = Arc::new(std::sync::atomic::AtomicBool::new(true));
+            if let Ok(mut flags) = self.running_flags.lock() {
+                flags.push(running.clone());
+            }
+
+            let runtime = self.clone();
+            let flow_name = flow.name.clone();
+            let job: Arc<dyn Fn() + Send + Sync> = Arc::new(move || {
+                if let Err(error) = runtime.run_flow(&flow_name, Message::default()) {
+                    runtime.report("ERROR", format!("Scheduled run of '{}' failed: {}", flow_name, error));
+                }
+            });
+            let flow_name = flow.name.clone();
+            let runtime = self.clone();
+            thread::spawn(move || {
+                run_schedule(&schedule, &running, || {
+                    let started = runtime.flow_state(&flow_name).map(|state| state.running).unwrap_or(false);
+                    if started && !limiter.submit(job.clone()) {
+                        runtime.report("WARN", format!("Skipped a scheduled run of '{}', max concurrency reached", flow_name));
+                    }
+                });
+            });
+            started.push(format!("{} (scheduler)", flow.name));
+        }
+        Ok(started)
+    }
+
+    // Starts every source: HTTP listeners, schedulers, file watchers and queue listeners
+    pub(crate) fn start_sources(self: &Arc<Self>) -> Result<Vec<String>, FlowError> {
+        let mut started = self.start_http_listeners()?;
+        started.extend(self.start_schedulers()?);
+        started.extend(self.start_file_watchers()?);
+        started.extend(self.start_queue_listeners()?);
+        Ok(started)
+    }
+}
@@ -0,0 +1,131 @@
+// Message tracking: flows with `is_tracking_enabled` record one event per executed component,
+// and every finished run is kept in the run history the editor browses and replays.
+use std::collections::VecDeque;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use uuid::Uuid;
+use crate::support::message::{FlowError, Message};
+
+// Oldest runs are dropped beyond this many
+const MAX_RUNS: usize = 50;
+
+// How one variable changed while a component ran; `None` means unset
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VariableChange {
+    pub(crate) name: String,
+    pub(crate) before: Option<Value>,
+    pub(crate) after: Option<Value>,
+}
+
+// One executed component. Events are ordered by `sequence`, the order components started in.
+#[derive(Debug, Clone)]
+pub(crate) struct TrackingEvent {
+    pub(crate) sequence: usize,
+    pub(crate) component_uuid: String,
+    pub(crate) component_name: String,
+    pub(crate) widget_type: String,
+    pub(crate) started: DateTime<Utc>,
+    pub(crate) duration: Duration,
+    pub(crate) payload: Value, // After the component ran, or before it when it failed
+    pub(crate) variable_changes: Vec<VariableChange>,
+    pub(crate) error: Option<FlowError>,
+}
+
+// A finished run of a tracked flow
+#[derive(Debug, Clone)]
+pub(crate) struct RunRecord {
+    pub(crate) id: String,
+    pub(crate) flow: String,
+    pub(crate) started: DateTime<Utc>,
+    pub(crate) duration: Duration,
+    pub(crate) input: Message,
+    pub(crate) events: Vec<TrackingEvent>,
+    pub(crate) error: Option<FlowError>,
+    pub(crate) parent_run: Option<String>, // Run whose Async scope started this one
+    pub(crate) scope: Option<String>, // Name of that Async scope
+}
+
+impl RunRecord {
+    // Variables as they were after `step` events, rebuilt from the input and the recorded changes.
+    // Changes made inside parallel routes are included although they do not leave the route.
+    pub(crate) fn variables_at(&self, step: usize) -> Map<String, Value> {
+        let mut variables = self.input.variables.clone();
+        for event in self.events.iter().take(step + 1) {
+            for change in &event.variable_changes {
+                match &change.after {
+                    Some(value) => variables.insert(change.name.clone(), value.clone()),
+                    None => variables.remove(&change.name),
+                };
+            }
+        }
+        variables
+    }
+}
+
+// Finished runs, newest last; shared with the editor's run history panel
+pub(crate) type RunHistory = Arc<Mutex<VecDeque<RunRecord>>>;
+
+pub(crate) fn record_run(history: &RunHistory, run: RunRecord) {
+    if let Ok(mut history) = history.lock() {
+        history.push_back(run);
+        while history.len() > MAX_RUNS {
+            history.pop_front();
+        }
+    }
+}
+
+// Events of the run in progress; shared by every thread the run uses. Clones record into the
+// same run, so routes that outlive their scope can keep one.
+#[derive(Clone)]
+pub(crate) struct RunTrace {
+    pub(crate) run_id: String,
+    pub(crate) flow: String,
+    next_sequence: Arc<AtomicUsize>,
+    events: Arc<Mutex<Vec<TrackingEvent>>>,
+}
+
+impl RunTrace {
+    pub(crate) fn new(flow: &str) -> Self {
+        Self {
+            run_id: Uuid::new_v4().to_string(),
+            flow: flow.to_string(),
+            next_sequence: Arc::new(AtomicUsize::new(0)),
+            events: Arc::new(Mutex::new(Vec::new())),
+        }
+    }
+
+    pub(crate) fn next_sequence(&self) -> usize {
+        self.next_sequence.fetch_add(1, Ordering::SeqCst)
+    }
+
+    pub(crate) fn record(&self, event: TrackingEvent) {
+        if let Ok(mut events) = self.events.lock() {
+            events.push(event);
+        }
+    }
+
+    // Recorded events in the order their components started
+    pub(crate) fn into_events(self) -> Vec<TrackingEvent> {
+        let mut events = self.events.lock().map(|mut events| std::mem::take(&mut *events)).unwrap_or_default();
+        events.sort_by_key(|event| event.sequence);
+        events
+    }
+}
+
+pub(crate) fn variable_changes(before: &Map<String, Value>, after: &Map<String, Value>) -> Vec<VariableChange> {
+    let mut changes = Vec::new();
+    for (name, value) in after {
+        if before.get(name) != Some(value) {
+            changes.push(VariableChange { name: name.clone(), before: before.get(name).cloned(), after: Some(value.clone()) });
+        }
+    }
+    for (name, value) in before {
+        if !after.contains_key(name) {
+            changes.push(VariableChange { name: name.clone(), before: Some(value.clone()), after: None });
+        }
+    }
+    changes
+}
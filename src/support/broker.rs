@@ -0,0 +1,341 @@
+// Message queues and topics behind a `Broker` trait. A queue hands each message to one consumer; a
+// topic copies it to the queue of every subscription. The in-memory broker chains flows inside one
+// process without external infrastructure.
+use std::collections::{HashMap, VecDeque};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde_json::{Map, Value};
+use super::message::{FlowError, Message};
+
+pub const BROKERS: &[&str] = &["InMemory"];
+pub const DESTINATION_TYPES: &[&str] = &["Queue", "Topic"];
+pub const ACK_MODES: &[&str] = &["Auto", "Immediate"];
+
+// How long a listener waits for a message before checking whether it should stop
+const RECEIVE_INTERVAL: Duration = Duration::from_millis(100);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Destination {
+    Queue(String),
+    Topic(String),
+}
+
+impl Destination {
+    pub fn new(destination_type: &str, name: &str) -> Result<Self, FlowError> {
+        let name = name.trim();
+        if name.is_empty() {
+            return Err(FlowError::new("MQ:DESTINATION", "No destination configured"));
+        }
+        if destination_type.eq_ignore_ascii_case("Topic") {
+            Ok(Destination::Topic(name.to_string()))
+        } else {
+            Ok(Destination::Queue(name.to_string()))
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Destination::Queue(name) | Destination::Topic(name) => name,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BrokerMessage {
+    pub id: String,
+    pub body: Value,
+    pub mime_type: String,
+    pub properties: Map<String, Value>,
+    // How often the message was handed out before without being acknowledged
+    pub redelivery_count: u32,
+}
+
+// A received message; the broker keeps it until it is acknowledged or recovered
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delivery {
+    pub tag: u64,
+    pub queue: String,
+    pub message: BrokerMessage,
+}
+
+pub trait Broker: Send + Sync {
+    // Queues the message, or copies it to every subscription of a topic; the broker assigns its id
+    fn publish(&self, destination: &Destination, message: BrokerMessage) -> Result<(), FlowError>;
+    // Queue that receives the messages of `topic` for `subscriber`, created on first use
+    fn subscribe(&self, topic: &str, subscriber: &str) -> Result<String, FlowError>;
+    // Next message of a queue, waiting up to `timeout` for one
+    fn receive(&self, queue: &str, timeout: Duration) -> Result<Option<Delivery>, FlowError>;
+    fn ack(&self, delivery: &Delivery) -> Result<(), FlowError>;
+    // Puts an unacknowledged message back at the front of its queue with its redelivery count raised
+    fn recover(&self, delivery: &Delivery) -> Result<(), FlowError>;
+}
+
+#[derive(Default)]
+struct BrokerState {
+    queues: HashMap<String, VecDeque<BrokerMessage>>,
+    subscriptions: HashMap<String, Vec<String>>, // Topic -> subscription queues
+    unacknowledged: HashMap<u64, Delivery>,
+    next_tag: u64,
+    next_id: u64,
+}
+
+#[derive(Default)]
+pub struct InMemoryBroker {
+    state: Mutex<BrokerState>,
+    available: Condvar,
+}
+
+impl InMemoryBroker {
+    fn lock(&self) -> Result<MutexGuard<'_, BrokerState>, FlowError> {
+        self.state.lock().map_err(|_| FlowError::new("MQ:CONNECTIVITY", "The broker is unavailable"))
+    }
+}
+
+impl Broker for InMemoryBroker {
+    fn publish(&self, destination: &Destination, mut message: BrokerMessage) -> Result<(), FlowError> {
+        let mut state = self.lock()?;
+        state.next_id += 1;
+        message.id = format!("ID:{}", state.next_id);
+        let queues = match destination {
+            Destination::Queue(name) => vec![name.clone()],
+            // Without subscriptions a topic message reaches nobody
+            Destination::Topic(name) => state.subscriptions.get(name).cloned().unwrap_or_default(),
+        };
+        for queue in queues {
+            state.queues.entry(queue).or_default().push_back(message.clone());
+        }
+        self.available.notify_all();
+        Ok(())
+    }
+
+    fn subscribe(&self, topic: &str, subscriber: &str) -> Result<String, FlowError> {
+        let queue = format!("{}::{}", topic, subscriber);
+        let mut state = self.lock()?;
+        let subscriptions = state.subscriptions.entry(topic.to_string()).or_default();
+        if !subscriptions.contains(&queue) {
+            subscriptions.push(queue.clone());
+        }
+        Ok(queue)
+    }
+
+    fn receive(&self, queue: &str, timeout: Duration) -> Result<Option<Delivery>, FlowError> {
+        let deadline = Instant::now() + timeout;
+        let mut state = self.lock()?;
+        loop {
+            if let Some(message) = state.queues.get_mut(queue).and_then(VecDeque::pop_front) {
+                state.next_tag += 1;
+                let delivery = Delivery { tag: state.next_tag, queue: queue.to_string(), message };
+                state.unacknowledged.insert(delivery.tag, delivery.clone());
+                return Ok(Some(delivery));
+            }
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(None);
+            }
+            state = self.available.wait_timeout(state, remaining)
+                .map_err(|_| FlowError::new("MQ:CONNECTIVITY", "The broker is unavailable"))?
+                .0;
+        }
+    }
+
+    fn ack(&self, delivery: &Delivery) -> Result<(), FlowError> {
+        self.lock()?.unacknowledged.remove(&delivery.tag);
+        Ok(())
+    }
+
+    fn recover(&self, delivery: &Delivery) -> Result<(), FlowError> {
+        let mut state = self.lock()?;
+        if let Some(mut delivery) = state.unacknowledged.remove(&delivery.tag) {
+            delivery.message.redelivery_count += 1;
+            state.queues.entry(delivery.queue).or_default().push_front(delivery.message);
+            self.available.notify_all();
+        }
+        Ok(())
+    }
+}
+
+pub fn create_broker(kind: &str) -> Result<Arc<dyn Broker>, FlowError> {
+    match kind.trim() {
+        "" | "InMemory" => Ok(Arc::new(InMemoryBroker::default())),
+        other => Err(FlowError::new("MQ:CONNECTIVITY", format!("Unknown broker '{}'", other))),
+    }
+}
+
+// One broker per broker config; components without a config share the unnamed one
+#[derive(Default)]
+pub struct BrokerRegistry {
+    brokers: Mutex<HashMap<String, Arc<dyn Broker>>>,
+}
+
+impl BrokerRegistry {
+    pub fn get(&self, config: &str, kind: &str) -> Result<Arc<dyn Broker>, FlowError> {
+        let mut brokers = self.brokers.lock().map_err(|_| FlowError::new("MQ:CONNECTIVITY", "The broker registry is unavailable"))?;
+        if let Some(broker) = brokers.get(config.trim()) {
+            return Ok(broker.clone());
+        }
+        let broker = create_broker(kind)?;
+        brokers.insert(config.trim().to_string(), broker.clone());
+        Ok(broker)
+    }
+}
+
+// Brokers of a generated service, shared by all of its flows
+pub fn shared_broker(config: &str, kind: &str) -> Result<Arc<dyn Broker>, FlowError> {
+    static REGISTRY: OnceLock<BrokerRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(BrokerRegistry::default).get(config, kind)
+}
+
+// Queue a listener of `destination` receives from; a topic gets a subscription named after the listener
+pub fn listener_queue(broker: &dyn Broker, destination: &Destination, subscriber: &str) -> Result<String, FlowError> {
+    match destination {
+        Destination::Queue(name) => Ok(name.clone()),
+        Destination::Topic(name) => broker.subscribe(name, subscriber),
+    }
+}
+
+// Sends `body` with the message's MIME type; `properties` is an object of message properties or null
+pub fn publish(broker: &dyn Broker, destination: &Destination, message: &Message, body: Value, properties: &Value) -> Result<(), FlowError> {
+    let properties = match properties {
+        Value::Object(properties) => properties.clone(),
+        Value::Null => Map::new(),
+        _ => return Err(FlowError::new("MQ:DESTINATION", "Message properties must be an object")),
+    };
+    broker.publish(destination, BrokerMessage { body, mime_type: message.mime_type.clone(), properties, ..Default::default() })
+}
+
+// Flow message for a delivery: the body as payload, the delivery details as attributes
+pub fn message_of(delivery: &Delivery) -> Message {
+    let mut attributes = Map::new();
+    attributes.insert("destination".to_string(), Value::String(delivery.queue.clone()));
+    attributes.insert("messageId".to_string(), Value::String(delivery.message.id.clone()));
+    attributes.insert("redeliveryCount".to_string(), Value::from(delivery.message.redelivery_count));
+    attributes.insert("properties".to_string(), Value::Object(delivery.message.properties.clone()));
+    Message {
+        payload: delivery.message.body.clone(),
+        mime_type: delivery.message.mime_type.clone(),
+        attributes,
+        variables: Map::new(),
+    }
+}
+
+// Takes the next message of `queue`, acknowledging it at once. Payload, MIME type and attributes
+// become the received message's; variables are kept.
+pub fn consume(broker: &dyn Broker, queue: &str, timeout_ms: u64, message: &Message) -> Result<Message, FlowError> {
+    let delivery = broker.receive(queue.trim(), Duration::from_millis(timeout_ms))?.ok_or_else(|| {
+        FlowError::new("MQ:TIMEOUT", format!("No message on '{}' within {} ms", queue.trim(), timeout_ms))
+    })?;
+    broker.ack(&delivery)?;
+    Ok(Message { variables: message.variables.clone(), ..message_of(&delivery) })
+}
+
+// When a listener acknowledges a message
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AckMode {
+    // After the run succeeds; a failed run recovers the message for redelivery
+    Auto,
+    // On receipt, before the run; a failed run loses the message
+    Immediate,
+}
+
+impl AckMode {
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("Immediate") {
+            AckMode::Immediate
+        } else {
+            AckMode::Auto
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ListenerSettings {
+    pub ack_mode: AckMode,
+    // Redeliveries allowed before a message counts as exhausted; None for no limit
+    pub max_redelivery: Option<u32>,
+    // Where exhausted messages go; empty to drop them
+    pub dead_letter_queue: String,
+}
+
+impl ListenerSettings {
+    // A negative `max_redelivery` means no limit
+    pub fn new(ack_mode: &str, max_redelivery: i64, dead_letter_queue: &str) -> Self {
+        Self {
+            ack_mode: AckMode::from_name(ack_mode),
+            max_redelivery: (max_redelivery >= 0).then_some(max_redelivery.min(u32::MAX as i64) as u32),
+            dead_letter_queue: dead_letter_queue.trim().to_string(),
+        }
+    }
+}
+
+pub fn redelivery_exhausted(delivery: &Delivery) -> FlowError {
+    FlowError::new(
+        "MQ:REDELIVERY_EXHAUSTED",
+        format!("Message {} on '{}' failed in {} attempts", delivery.message.id, delivery.queue, delivery.message.redelivery_count),
+    )
+}
+
+// Receives from `queue` until `running` turns false, calling `run` once per message. `active`
+// decides per message whether to receive at all, so messages wait while a flow is stopped. A
+// message redelivered more often than allowed is not run again: it is acknowledged, moved to the
+// dead letter queue if there is one, and handed to `on_exhausted` with `MQ:REDELIVERY_EXHAUSTED`.
+pub fn run_listener(
+    broker: &dyn Broker,
+    queue: &str,
+    settings: &ListenerSettings,
+    running: &AtomicBool,
+    active: impl Fn() -> bool,
+    mut run: impl FnMut(Message) -> Result<Message, FlowError>,
+    mut on_exhausted: impl FnMut(Message, FlowError),
+) {
+    while running.load(Ordering::SeqCst) {
+        if !active() {
+            thread::sleep(RECEIVE_INTERVAL);
+            continue;
+        }
+        let delivery = match broker.receive(queue, RECEIVE_INTERVAL) {
+            Ok(Some(delivery)) => delivery,
+            Ok(None) => continue,
+            Err(error) => {
+                println!("Cannot receive from '{}': {}", queue, error);
+                thread::sleep(RECEIVE_INTERVAL);
+                continue;
+            }
+        };
+        let result = if settings.max_redelivery.is_some_and(|max| delivery.message.redelivery_count > max) {
+            exhaust(broker, &delivery, settings).map(|error| on_exhausted(message_of(&delivery), error))
+        } else if settings.ack_mode == AckMode::Immediate {
+            broker.ack(&delivery).map(|_| {
+                if let Err(error) = run(message_of(&delivery)) {
+                    println!("Run for message {} on '{}' failed: {}", delivery.message.id, queue, error);
+                }
+            })
+        } else {
+            match run(message_of(&delivery)) {
+                Ok(_) => broker.ack(&delivery),
+                Err(error) => {
+                    println!("Run for message {} on '{}' failed, recovering it: {}", delivery.message.id, queue, error);
+                    broker.recover(&delivery)
+                }
+            }
+        };
+        if let Err(error) = result {
+            println!("Cannot settle message {} on '{}': {}", delivery.message.id, queue, error);
+        }
+    }
+}
+
+// Settles an exhausted message, returning the error that describes it
+fn exhaust(broker: &dyn Broker, delivery: &Delivery, settings: &ListenerSettings) -> Result<FlowError, FlowError> {
+    let error = redelivery_exhausted(delivery);
+    if !settings.dead_letter_queue.is_empty() {
+        let mut message = delivery.message.clone();
+        message.redelivery_count = 0;
+        message.properties.insert("errorType".to_string(), Value::String(error.error_type.clone()));
+        message.properties.insert("originalDestination".to_string(), Value::String(delivery.queue.clone()));
+        broker.publish(&Destination::Queue(settings.dead_letter_queue.clone()), message)?;
+    }
+    broker.ack(delivery)?;
+    Ok(error)
+}
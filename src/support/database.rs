@@ -0,0 +1,152 @@
+// Database operations backed by SQLite. SQL uses named (`:name`) or positional (`?`) parameters,
+// bound from an object or an array of input parameters.
+use std::path::Path;
+use std::time::Duration;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use rusqlite::types::{Value as SqlValue, ValueRef};
+use rusqlite::{Connection, Statement};
+use serde_json::{Map, Number, Value};
+use super::message::{FlowError, Message};
+
+pub const OPERATIONS: &[&str] = &["Select", "Insert", "Update", "Delete", "Bulk"];
+
+// How long a statement waits for a database locked by another run
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+
+fn connectivity_error(database_file: &str, error: impl std::fmt::Display) -> FlowError {
+    FlowError::new("DB:CONNECTIVITY", format!("Cannot open database {}: {}", database_file, error))
+}
+
+fn execution_error(error: rusqlite::Error) -> FlowError {
+    FlowError::new("DB:QUERY_EXECUTION", error.to_string())
+}
+
+// Opens the database file, creating it and its folder if needed
+pub fn open(database_file: &str) -> Result<Connection, FlowError> {
+    if database_file.trim().is_empty() {
+        return Err(FlowError::new("DB:CONNECTIVITY", "No database file configured"));
+    }
+    if let Some(parent) = Path::new(database_file).parent().filter(|parent| !parent.as_os_str().is_empty()) {
+        std::fs::create_dir_all(parent).map_err(|error| connectivity_error(database_file, error))?;
+    }
+    let connection = Connection::open(database_file).map_err(|error| connectivity_error(database_file, error))?;
+    connection.busy_timeout(BUSY_TIMEOUT).map_err(|error| connectivity_error(database_file, error))?;
+    Ok(connection)
+}
+
+fn to_sql(value: &Value) -> SqlValue {
+    match value {
+        Value::Null => SqlValue::Null,
+        Value::Bool(value) => SqlValue::Integer(*value as i64),
+        Value::Number(number) => match number.as_i64() {
+            Some(integer) => SqlValue::Integer(integer),
+            None => SqlValue::Real(number.as_f64().unwrap_or_default()),
+        },
+        Value::String(text) => SqlValue::Text(text.clone()),
+        other => SqlValue::Text(other.to_string()),
+    }
+}
+
+fn to_json(value: ValueRef) -> Value {
+    match value {
+        ValueRef::Null => Value::Null,
+        ValueRef::Integer(integer) => Value::from(integer),
+        ValueRef::Real(real) => Number::from_f64(real).map(Value::Number).unwrap_or(Value::Null),
+        ValueRef::Text(text) => Value::String(String::from_utf8_lossy(text).into_owned()),
+        ValueRef::Blob(bytes) => Value::String(STANDARD.encode(bytes)),
+    }
+}
+
+// Binds `:name` parameters from an object, or `?` parameters in order from an array
+fn bind(statement: &mut Statement, parameters: &Value) -> Result<(), FlowError> {
+    for index in 1..=statement.parameter_count() {
+        let value = match (statement.parameter_name(index), parameters) {
+            (Some(name), Value::Object(parameters)) => {
+                let key = name.trim_start_matches([':', '@', '$']);
+                parameters.get(key).cloned().ok_or_else(|| {
+                    FlowError::new("DB:BAD_SQL_SYNTAX", format!("No input parameter '{}'", key))
+                })?
+            }
+            (_, Value::Array(parameters)) => parameters.get(index - 1).cloned().unwrap_or(Value::Null),
+            (_, Value::Null) => Value::Null,
+            (_, _) => {
+                return Err(FlowError::new(
+                    "DB:BAD_SQL_SYNTAX",
+                    "Input parameters must be an object for named parameters or an array for positional ones",
+                ))
+            }
+        };
+        statement.raw_bind_parameter(index, to_sql(&value)).map_err(execution_error)?;
+    }
+    Ok(())
+}
+
+fn prepare<'a>(connection: &'a Connection, sql: &str) -> Result<Statement<'a>, FlowError> {
+    connection.prepare(sql).map_err(|error| FlowError::new("DB:BAD_SQL_SYNTAX", error.to_string()))
+}
+
+fn select(connection: &Connection, sql: &str, parameters: &Value) -> Result<Value, FlowError> {
+    let mut statement = prepare(connection, sql)?;
+    bind(&mut statement, parameters)?;
+    let columns: Vec<String> = statement.column_names().iter().map(|name| name.to_string()).collect();
+    let mut rows = statement.raw_query();
+    let mut result = Vec::new();
+    while let Some(row) = rows.next().map_err(execution_error)? {
+        let mut record = Map::new();
+        for (index, column) in columns.iter().enumerate() {
+            record.insert(column.clone(), to_json(row.get_ref(index).map_err(execution_error)?));
+        }
+        result.push(Value::Object(record));
+    }
+    Ok(Value::Array(result))
+}
+
+fn update(connection: &Connection, sql: &str, parameters: &Value) -> Result<usize, FlowError> {
+    let mut statement = prepare(connection, sql)?;
+    bind(&mut statement, parameters)?;
+    statement.raw_execute().map_err(execution_error)
+}
+
+// Runs the statement once per element of `parameters`, all or nothing
+fn bulk(connection: &mut Connection, sql: &str, parameters: &Value) -> Result<usize, FlowError> {
+    let items = match parameters {
+        Value::Array(items) => items,
+        _ => return Err(FlowError::new("DB:BAD_SQL_SYNTAX", "Bulk input parameters must be an array")),
+    };
+    let transaction = connection.transaction().map_err(execution_error)?;
+    let mut affected = 0;
+    {
+        let mut statement = prepare(&transaction, sql)?;
+        for item in items {
+            bind(&mut statement, item)?;
+            affected += statement.raw_execute().map_err(execution_error)?;
+        }
+    }
+    transaction.commit().map_err(execution_error)?;
+    Ok(affected)
+}
+
+// Result of one operation: rows for Select, affected rows (and the new row id for Insert) otherwise
+pub fn query(database_file: &str, operation: &str, sql: &str, parameters: &Value) -> Result<Value, FlowError> {
+    let mut connection = open(database_file)?;
+    let affected = match operation {
+        "Select" => return select(&connection, sql, parameters),
+        "Bulk" => bulk(&mut connection, sql, parameters)?,
+        _ => update(&connection, sql, parameters)?,
+    };
+    let mut result = Map::new();
+    result.insert("affectedRows".to_string(), Value::from(affected));
+    if operation == "Insert" {
+        result.insert("lastInsertId".to_string(), Value::from(connection.last_insert_rowid()));
+    }
+    Ok(Value::Object(result))
+}
+
+// The operation's result becomes the payload
+pub fn execute(message: &Message, database_file: &str, operation: &str, sql: &str, parameters: &Value) -> Result<Message, FlowError> {
+    let mut result = message.clone();
+    result.payload = query(database_file, operation, sql, parameters)?;
+    result.mime_type = "application/json".to_string();
+    Ok(result)
+}
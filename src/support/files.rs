@@ -0,0 +1,244 @@
+// File operations and the directory watcher behind On New File. Relative paths resolve against the
+// project folder; file contents become the payload with a MIME type inferred from the extension.
+use std::collections::HashMap;
+use std::fs::{self, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, SystemTime};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use chrono::{DateTime, Utc};
+use serde_json::{Map, Value};
+use super::message::{FlowError, Message};
+use super::ops;
+
+pub const OPERATIONS: &[&str] = &["Read", "Write", "List", "Move"];
+pub const WRITE_MODES: &[&str] = &["Overwrite", "Append", "CreateNew"];
+
+// Folder relative paths resolve against in generated services: `PROJECT_FOLDER`, or else the working directory
+pub fn project_folder() -> PathBuf {
+    match std::env::var("PROJECT_FOLDER") {
+        Ok(folder) if !folder.is_empty() => PathBuf::from(folder),
+        _ => std::env::current_dir().unwrap_or_default(),
+    }
+}
+
+pub fn resolve_path(project_folder: &Path, path: &str) -> PathBuf {
+    let path = Path::new(path.trim());
+    if path.is_absolute() {
+        path.to_path_buf()
+    } else {
+        project_folder.join(path)
+    }
+}
+
+pub fn mime_type_of(path: &Path) -> &'static str {
+    let extension = path.extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_ascii_lowercase();
+    match extension.as_str() {
+        "json" => "application/json",
+        "xml" => "application/xml",
+        "csv" => "text/csv",
+        "txt" | "log" => "text/plain",
+        "html" | "htm" => "text/html",
+        "yaml" | "yml" => "application/yaml",
+        "properties" => "text/x-java-properties",
+        "pdf" => "application/pdf",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "zip" => "application/zip",
+        _ => "application/octet-stream",
+    }
+}
+
+fn file_error(path: &Path, error: io::Error) -> FlowError {
+    let error_type = match error.kind() {
+        io::ErrorKind::NotFound => "FILE:ILLEGAL_PATH",
+        io::ErrorKind::AlreadyExists => "FILE:FILE_ALREADY_EXISTS",
+        io::ErrorKind::PermissionDenied => "FILE:ACCESS_DENIED",
+        _ => "FILE:CONNECTIVITY",
+    };
+    FlowError::new(error_type, format!("{}: {}", path.display(), error))
+}
+
+// `*` matches any run of characters and `?` any single character; an empty pattern matches everything
+pub fn pattern_matches(pattern: &str, name: &str) -> bool {
+    fn matches(pattern: &[char], name: &[char]) -> bool {
+        match pattern.split_first() {
+            None => name.is_empty(),
+            Some(('*', rest)) => (0..=name.len()).any(|skip| matches(rest, &name[skip..])),
+            Some(('?', rest)) => !name.is_empty() && matches(rest, &name[1..]),
+            Some((c, rest)) => name.first() == Some(c) && matches(rest, &name[1..]),
+        }
+    }
+    let pattern = pattern.trim();
+    pattern.is_empty() || matches(&pattern.chars().collect::<Vec<_>>(), &name.chars().collect::<Vec<_>>())
+}
+
+fn format_time(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time).to_rfc3339_opts(chrono::SecondsFormat::Millis, true)
+}
+
+// fileName, path, size, lastModified and directory of a file
+pub fn file_attributes(path: &Path) -> Result<Map<String, Value>, FlowError> {
+    let metadata = fs::metadata(path).map_err(|error| file_error(path, error))?;
+    let mut attributes = Map::new();
+    let file_name = path.file_name().map(|name| name.to_string_lossy().into_owned()).unwrap_or_default();
+    attributes.insert("fileName".to_string(), Value::String(file_name));
+    attributes.insert("path".to_string(), Value::String(path.display().to_string()));
+    attributes.insert("size".to_string(), Value::from(metadata.len()));
+    if let Ok(modified) = metadata.modified() {
+        attributes.insert("lastModified".to_string(), Value::String(format_time(modified)));
+    }
+    attributes.insert("directory".to_string(), Value::Bool(metadata.is_dir()));
+    Ok(attributes)
+}
+
+// JSON files are parsed, other text is kept as a string and binary content is base64 encoded
+fn payload_of(bytes: Vec<u8>, mime_type: &str) -> Value {
+    match String::from_utf8(bytes) {
+        Ok(text) if mime_type == "application/json" => serde_json::from_str(&text).unwrap_or(Value::String(text)),
+        Ok(text) => Value::String(text),
+        Err(error) => Value::String(STANDARD.encode(error.into_bytes())),
+    }
+}
+
+// Message carrying a file: its contents as payload and its details as attributes
+pub fn read_message(path: &Path) -> Result<Message, FlowError> {
+    let bytes = fs::read(path).map_err(|error| file_error(path, error))?;
+    let mime_type = mime_type_of(path);
+    Ok(Message {
+        payload: payload_of(bytes, mime_type),
+        mime_type: mime_type.to_string(),
+        attributes: file_attributes(path)?,
+        variables: Map::new(),
+    })
+}
+
+// Replaces payload, MIME type and attributes with the file's; variables are kept
+pub fn read(message: &Message, project_folder: &Path, path: &str) -> Result<Message, FlowError> {
+    let file = read_message(&resolve_path(project_folder, path))?;
+    Ok(Message { variables: message.variables.clone(), ..file })
+}
+
+// Writes text content as is and other values as JSON, creating missing folders
+pub fn write(project_folder: &Path, path: &str, content: &Value, mode: &str) -> Result<(), FlowError> {
+    let path = resolve_path(project_folder, path);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent).map_err(|error| file_error(parent, error))?;
+    }
+    let mut options = OpenOptions::new();
+    match mode {
+        "Append" => options.append(true).create(true),
+        "CreateNew" => options.write(true).create_new(true),
+        _ => options.write(true).create(true).truncate(true),
+    };
+    let mut file = options.open(&path).map_err(|error| file_error(&path, error))?;
+    file.write_all(ops::to_text(content).as_bytes()).map_err(|error| file_error(&path, error))
+}
+
+// The payload becomes the attributes of every entry of `directory` whose name matches `pattern`
+pub fn list(message: &Message, project_folder: &Path, directory: &str, pattern: &str) -> Result<Message, FlowError> {
+    let directory = resolve_path(project_folder, directory);
+    let mut paths = Vec::new();
+    for entry in fs::read_dir(&directory).map_err(|error| file_error(&directory, error))? {
+        let entry = entry.map_err(|error| file_error(&directory, error))?;
+        if pattern_matches(pattern, &entry.file_name().to_string_lossy()) {
+            paths.push(entry.path());
+        }
+    }
+    paths.sort();
+    let mut entries = Vec::new();
+    for path in paths {
+        entries.push(Value::Object(file_attributes(&path)?));
+    }
+    let mut result = message.clone();
+    result.payload = Value::Array(entries);
+    result.mime_type = "application/json".to_string();
+    Ok(result)
+}
+
+pub fn move_file(project_folder: &Path, source: &str, target: &str, overwrite: bool) -> Result<(), FlowError> {
+    let source = resolve_path(project_folder, source);
+    let target = resolve_path(project_folder, target);
+    if !source.exists() {
+        return Err(FlowError::new("FILE:ILLEGAL_PATH", format!("{} does not exist", source.display())));
+    }
+    if target.exists() && !overwrite {
+        return Err(FlowError::new("FILE:FILE_ALREADY_EXISTS", format!("{} already exists", target.display())));
+    }
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|error| file_error(parent, error))?;
+    }
+    fs::rename(&source, &target).map_err(|error| file_error(&source, error))
+}
+
+// Finds new files in a directory. Every file seen is remembered with its size and modification
+// time, so files moved in with an old timestamp are still new, and a file that is rewritten or
+// removed and added again is picked up once more.
+pub struct FileWatcher {
+    directory: PathBuf,
+    pattern: String,
+    seen: HashMap<PathBuf, (u64, SystemTime)>,
+}
+
+impl FileWatcher {
+    pub fn new(directory: PathBuf, pattern: &str) -> Self {
+        Self { directory, pattern: pattern.to_string(), seen: HashMap::new() }
+    }
+
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    // New files since the last poll, oldest first; the first poll returns every matching file
+    pub fn poll(&mut self) -> io::Result<Vec<PathBuf>> {
+        let mut found = Vec::new();
+        let mut present = HashMap::new();
+        for entry in fs::read_dir(&self.directory)? {
+            let entry = entry?;
+            let metadata = entry.metadata()?;
+            if !metadata.is_file() || !pattern_matches(&self.pattern, &entry.file_name().to_string_lossy()) {
+                continue;
+            }
+            let signature = (metadata.len(), metadata.modified()?);
+            let path = entry.path();
+            if self.seen.get(&path) != Some(&signature) {
+                found.push((signature.1, path.clone()));
+            }
+            present.insert(path, signature);
+        }
+        // Files that are gone are forgotten, so one added again under the same name is new
+        self.seen = present;
+        found.sort();
+        Ok(found.into_iter().map(|(_, path)| path).collect())
+    }
+}
+
+// Polls the watcher every `interval` until `running` turns false, calling `on_file` for each new
+// file. `active` decides per poll whether to look at all; skipped polls leave the watermark alone.
+pub fn run_watcher(
+    watcher: &mut FileWatcher,
+    interval: Duration,
+    running: &AtomicBool,
+    active: impl Fn() -> bool,
+    mut on_file: impl FnMut(PathBuf),
+) {
+    while running.load(Ordering::SeqCst) {
+        if active() {
+            match watcher.poll() {
+                Ok(paths) => paths.into_iter().for_each(&mut on_file),
+                Err(error) => println!("Cannot poll {}: {}", watcher.directory().display(), error),
+            }
+        }
+        // Sleep in short steps so a stop request is noticed quickly
+        let mut remaining = interval;
+        while !remaining.is_zero() && running.load(Ordering::SeqCst) {
+            let step = remaining.min(Duration::from_millis(100));
+            thread::sleep(step);
+            remaining -= step;
+        }
+    }
+}
@@ -0,0 +1,616 @@
+use std::io::{self, BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::fmt;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+use serde_json::{Map, Value};
+use super::message::{FlowError, Message};
+use super::ops;
+
+// Minimal HTTP/1.1 support on top of std: one request per connection, bodies delimited by Content-Length
+
+// Limits that keep one client from exhausting the process
+pub const MAX_BODY_SIZE: usize = 10 * 1024 * 1024;
+const MAX_LINE_LENGTH: u64 = 8 * 1024;
+const MAX_HEADERS: usize = 100;
+const MAX_CONNECTIONS: usize = 64; // Further connections wait in the listen backlog
+const READ_TIMEOUT: Duration = Duration::from_secs(30); // Longest wait for any one read or write
+const REQUEST_DEADLINE: Duration = Duration::from_secs(60); // Whole request head and body
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpRequest {
+    pub method: String,
+    pub path: String,
+    pub query: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpRequest {
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl HttpResponse {
+    pub fn new(status: u16, content_type: &str, body: Vec<u8>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body,
+        }
+    }
+
+    pub fn header(&self, name: &str) -> Option<&str> {
+        find_header(&self.headers, name)
+    }
+}
+
+fn find_header<'a>(headers: &'a [(String, String)], name: &str) -> Option<&'a str> {
+    headers.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, value)| value.as_str())
+}
+
+fn reason_phrase(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        401 => "Unauthorized",
+        403 => "Forbidden",
+        405 => "Method Not Allowed",
+        408 => "Request Timeout",
+        413 => "Payload Too Large",
+        429 => "Too Many Requests",
+        500 => "Internal Server Error",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+fn invalid(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
+
+// A body over `MAX_BODY_SIZE`; servers answer it with 413
+#[derive(Debug)]
+struct BodyTooLarge;
+
+impl fmt::Display for BodyTooLarge {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Body larger than {} bytes", MAX_BODY_SIZE)
+    }
+}
+
+impl std::error::Error for BodyTooLarge {}
+
+fn too_large() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, BodyTooLarge)
+}
+
+fn is_too_large(error: &io::Error) -> bool {
+    error.get_ref().is_some_and(|error| error.is::<BodyTooLarge>())
+}
+
+// Header names are tokens; values may not break out of their line
+fn check_header(name: &str, value: &str) -> io::Result<()> {
+    let token = |c: char| c.is_ascii_alphanumeric() || "!#$%&'*+-.^_`|~".contains(c);
+    if name.is_empty() || !name.chars().all(token) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid header name {:?}", name)));
+    }
+    if value.chars().any(|c| c == '\r' || c == '\n' || c == '\0') {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid value of header {}", name)));
+    }
+    Ok(())
+}
+
+fn check_headers(headers: &[(String, String)]) -> io::Result<()> {
+    headers.iter().try_for_each(|(name, value)| check_header(name, value))
+}
+
+// Reads a stream until a deadline: each read waits at most for what is left of the time, so a
+// peer sending a byte now and then cannot keep the connection past it
+struct DeadlineReader<'a> {
+    stream: &'a TcpStream,
+    deadline: Instant,
+    read_timeout: Duration,
+}
+
+impl<'a> DeadlineReader<'a> {
+    fn new(stream: &'a TcpStream, total: Duration, read_timeout: Duration) -> Self {
+        Self { stream, deadline: Instant::now() + total, read_timeout }
+    }
+}
+
+impl Read for DeadlineReader<'_> {
+    fn read(&mut self, buffer: &mut [u8]) -> io::Result<usize> {
+        let remaining = self.deadline.saturating_duration_since(Instant::now());
+        if remaining.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "Message not received in time"));
+        }
+        self.stream.set_read_timeout(Some(remaining.min(self.read_timeout)))?;
+        let mut stream = self.stream;
+        stream.read(buffer)
+    }
+}
+
+// Start line, headers and body of a request or response as read from the connection
+struct RawMessage {
+    start_line: String,
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+fn is_timeout(error: &io::Error) -> bool {
+    matches!(error.kind(), io::ErrorKind::TimedOut | io::ErrorKind::WouldBlock)
+}
+
+// Reads one line of the header block, refusing lines longer than `MAX_LINE_LENGTH`
+fn read_limited_line<R: Read>(reader: &mut BufReader<R>, line: &mut String) -> io::Result<usize> {
+    let read = reader.by_ref().take(MAX_LINE_LENGTH).read_line(line)?;
+    if read as u64 == MAX_LINE_LENGTH && !line.ends_with('\n') {
+        return Err(invalid("Header line too long"));
+    }
+    Ok(read)
+}
+
+// Reads the header block and the Content-Length delimited body shared by requests and responses
+fn read_head_and_body<R: Read>(reader: &mut BufReader<R>) -> io::Result<RawMessage> {
+    let mut start_line = String::new();
+    read_limited_line(reader, &mut start_line)?;
+    let mut headers = Vec::new();
+    loop {
+        let mut line = String::new();
+        if read_limited_line(reader, &mut line)? == 0 {
+            break;
+        }
+        let line = line.trim_end();
+        if line.is_empty() {
+            break;
+        }
+        if headers.len() == MAX_HEADERS {
+            return Err(invalid("Too many headers"));
+        }
+        if let Some((key, value)) = line.split_once(':') {
+            let (key, value) = (key.trim(), value.trim());
+            check_header(key, value).map_err(|error| invalid(&error.to_string()))?;
+            headers.push((key.to_string(), value.to_string()));
+        }
+    }
+    let mut body = Vec::new();
+    let chunked = find_header(&headers, "Transfer-Encoding").map(|value| value.eq_ignore_ascii_case("chunked")).unwrap_or(false);
+    match find_header(&headers, "Content-Length") {
+        _ if chunked => {
+            loop {
+                let mut size_line = String::new();
+                read_limited_line(reader, &mut size_line)?;
+                let size = usize::from_str_radix(size_line.trim().split(';').next().unwrap_or(""), 16)
+                    .map_err(|_| invalid("Invalid chunk size"))?;
+                if size > MAX_BODY_SIZE - body.len() {
+                    return Err(too_large());
+                }
+                let mut chunk = vec![0; size + 2];
+                reader.read_exact(&mut chunk)?;
+                if size == 0 {
+                    break;
+                }
+                body.extend_from_slice(&chunk[..size]);
+            }
+        }
+        Some(length) => {
+            let length: usize = length.parse().map_err(|_| invalid("Invalid Content-Length"))?;
+            if length > MAX_BODY_SIZE {
+                return Err(too_large());
+            }
+            body.resize(length, 0);
+            reader.read_exact(&mut body)?;
+        }
+        // Responses without a length run until the connection closes
+        None if !start_line.starts_with("HTTP/") => {}
+        None => {
+            reader.take(MAX_BODY_SIZE as u64 + 1).read_to_end(&mut body)?;
+            if body.len() > MAX_BODY_SIZE {
+                return Err(too_large());
+            }
+        }
+    }
+    Ok(RawMessage { start_line: start_line.trim_end().to_string(), headers, body })
+}
+
+// Reads a request that must arrive within `REQUEST_DEADLINE`
+pub fn read_request(stream: &TcpStream) -> io::Result<HttpRequest> {
+    let mut reader = BufReader::new(DeadlineReader::new(stream, REQUEST_DEADLINE, READ_TIMEOUT));
+    let RawMessage { start_line, headers, body } = read_head_and_body(&mut reader)?;
+    let mut parts = start_line.split_whitespace();
+    let method = parts.next().ok_or_else(|| invalid("Missing request method"))?.to_uppercase();
+    let target = parts.next().ok_or_else(|| invalid("Missing request target"))?;
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    Ok(HttpRequest {
+        method,
+        path: path.to_string(),
+        query: query.to_string(),
+        headers,
+        body,
+    })
+}
+
+pub fn write_response(mut stream: &TcpStream, response: &HttpResponse) -> io::Result<()> {
+    check_headers(&response.headers)?;
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason_phrase(response.status));
+    for (key, value) in &response.headers {
+        if !key.eq_ignore_ascii_case("Content-Length") && !key.eq_ignore_ascii_case("Connection") {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", response.body.len()));
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(&response.body)?;
+    stream.flush()
+}
+
+// Splits `http://host[:port]/path?query` into the socket address, host header and request target
+fn parse_url(url: &str) -> io::Result<(String, String, String)> {
+    let rest = url.strip_prefix("http://").ok_or_else(|| {
+        io::Error::new(io::ErrorKind::InvalidInput, format!("Only http:// URLs are supported: {}", url))
+    })?;
+    let (authority, target) = match rest.find('/') {
+        Some(index) => (&rest[..index], &rest[index..]),
+        None => (rest, "/"),
+    };
+    let address = if authority.contains(':') { authority.to_string() } else { format!("{}:80", authority) };
+    Ok((address, authority.to_string(), target.to_string()))
+}
+
+// Sends a request and waits for the complete response
+pub fn send(method: &str, url: &str, headers: &[(String, String)], body: &[u8], timeout: Duration) -> io::Result<HttpResponse> {
+    let (address, host, target) = parse_url(url)?;
+    check_headers(headers)?;
+    if method.is_empty() || !method.chars().all(|c| c.is_ascii_alphabetic()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid method {:?}", method)));
+    }
+    if target.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(io::Error::new(io::ErrorKind::InvalidInput, format!("Invalid URL {:?}", url)));
+    }
+    let socket_address = address.to_socket_addrs()?.next().ok_or_else(|| {
+        io::Error::new(io::ErrorKind::NotFound, format!("Cannot resolve {}", address))
+    })?;
+    let mut stream = TcpStream::connect_timeout(&socket_address, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+
+    let mut head = format!("{} {} HTTP/1.1\r\nHost: {}\r\n", method.to_uppercase(), target, host);
+    for (key, value) in headers {
+        if !key.eq_ignore_ascii_case("Content-Length") && !key.eq_ignore_ascii_case("Connection") && !key.eq_ignore_ascii_case("Host") {
+            head.push_str(&format!("{}: {}\r\n", key, value));
+        }
+    }
+    head.push_str(&format!("Content-Length: {}\r\nConnection: close\r\n\r\n", body.len()));
+    stream.write_all(head.as_bytes())?;
+    stream.write_all(body)?;
+    stream.flush()?;
+
+    // The timeout also bounds the whole response, however slowly it arrives
+    let mut reader = BufReader::new(DeadlineReader::new(&stream, timeout, timeout));
+    let RawMessage { start_line, headers, body } = read_head_and_body(&mut reader)?;
+    let status = start_line.split_whitespace().nth(1)
+        .and_then(|status| status.parse().ok())
+        .filter(|status| (100..=599).contains(status))
+        .ok_or_else(|| invalid("Invalid status line"))?;
+    Ok(HttpResponse { status, headers, body })
+}
+
+// Error type raised for an unsuccessful response status
+pub fn status_error_type(status: u16) -> &'static str {
+    match status {
+        400 => "HTTP:BAD_REQUEST",
+        401 => "HTTP:UNAUTHORIZED",
+        403 => "HTTP:FORBIDDEN",
+        404 => "HTTP:NOT_FOUND",
+        405 => "HTTP:METHOD_NOT_ALLOWED",
+        429 => "HTTP:TOO_MANY_REQUESTS",
+        503 => "HTTP:SERVICE_UNAVAILABLE",
+        500..=599 => "HTTP:INTERNAL_SERVER_ERROR",
+        _ => "HTTP:BAD_RESPONSE",
+    }
+}
+
+// HTTP Request operation: sends the request and replaces payload and attributes with the response.
+// Variables are kept; statuses of 400 and above raise an error.
+pub fn execute_request(message: &Message, method: &str, url: &str, headers: &Value, body: &Value, timeout_ms: u64) -> Result<Message, FlowError> {
+    let (bytes, content_type) = ops::encode_payload(body);
+    let mut request_headers: Vec<(String, String)> = match headers {
+        Value::Object(map) => map.iter().map(|(key, value)| (key.clone(), ops::to_text(value))).collect(),
+        _ => Vec::new(),
+    };
+    if !bytes.is_empty() && find_header(&request_headers, "Content-Type").is_none() {
+        request_headers.push(("Content-Type".to_string(), content_type.to_string()));
+    }
+
+    let response = send(method, url, &request_headers, &bytes, Duration::from_millis(timeout_ms)).map_err(|error| {
+        if is_timeout(&error) {
+            FlowError::new("HTTP:TIMEOUT", format!("{} {} timed out after {} ms", method, url, timeout_ms))
+        } else {
+            FlowError::new("HTTP:CONNECTIVITY", format!("{} {} failed: {}", method, url, error))
+        }
+    })?;
+    if response.status >= 400 {
+        return Err(FlowError::new(
+            status_error_type(response.status),
+            format!("{} {} returned {}: {}", method, url, response.status, String::from_utf8_lossy(&response.body)),
+        ));
+    }
+
+    let mime_type = response.header("Content-Type").unwrap_or("text/plain").to_string();
+    let mut response_headers = Map::new();
+    for (key, value) in &response.headers {
+        response_headers.insert(key.to_lowercase(), Value::String(value.clone()));
+    }
+    let mut result = message.clone();
+    result.payload = ops::decode_payload(&response.body, &mime_type);
+    result.mime_type = mime_type;
+    result.attributes = Map::new();
+    result.attributes.insert("statusCode".to_string(), Value::from(response.status));
+    result.attributes.insert("headers".to_string(), Value::Object(response_headers));
+    Ok(result)
+}
+
+// Resolves a request URL against the base URL of its request config; absolute URLs are kept
+pub fn join_url(base_url: &str, url: &str) -> String {
+    if base_url.trim().is_empty() || url.contains("://") {
+        return url.to_string();
+    }
+    format!("{}/{}", base_url.trim().trim_end_matches('/'), url.trim_start_matches('/'))
+}
+
+// Listener paths match exactly, or by prefix when they end in `/*`
+pub fn path_matches(pattern: &str, path: &str) -> bool {
+    match pattern.strip_suffix("/*") {
+        Some(prefix) => path == prefix || path.starts_with(&format!("{}/", prefix)),
+        None => pattern.trim_end_matches('/') == path.trim_end_matches('/'),
+    }
+}
+
+// `allowed` is a comma separated list of methods; an empty list allows every method
+pub fn method_allowed(allowed: &str, method: &str) -> bool {
+    let mut methods = allowed.split(',').map(|allowed| allowed.trim()).filter(|allowed| !allowed.is_empty()).peekable();
+    methods.peek().is_none() || methods.any(|allowed| allowed.eq_ignore_ascii_case(method))
+}
+
+// The message a flow receives for an incoming request
+pub fn message_from_request(request: &HttpRequest) -> Message {
+    let mime_type = request.header("Content-Type").unwrap_or("text/plain").to_string();
+    let mut message = Message::new(ops::decode_payload(&request.body, &mime_type));
+    message.mime_type = mime_type;
+    message.attributes = request_attributes(request);
+    message
+}
+
+// A response status from the status expression: a number or numeric text from 100 to 599, or
+// null for 200
+fn response_status(status: &Value) -> Option<u16> {
+    let status = match status {
+        Value::Null => return Some(200),
+        Value::Number(number) => number.as_u64()?,
+        Value::String(text) => text.trim().parse().ok()?,
+        _ => return None,
+    };
+    Some(status).filter(|status| (100..=599).contains(status)).map(|status| status as u16)
+}
+
+// Builds the listener response from the flow result and the evaluated status and headers expressions;
+// a `Content-Type` in the headers replaces the one derived from the payload. An invalid status
+// or header makes a 500 response.
+pub fn response_from_message(message: &Message, status: &Value, headers: &Value) -> HttpResponse {
+    let status = match response_status(status) {
+        Some(status) => status,
+        None => return HttpResponse::new(500, "text/plain", format!("Invalid response status {}", status).into_bytes()),
+    };
+    let (body, content_type) = ops::encode_payload(&message.payload);
+    let mut response = HttpResponse::new(status, content_type, body);
+    if let Value::Object(map) = headers {
+        for (key, value) in map {
+            response.headers.retain(|(existing, _)| !existing.eq_ignore_ascii_case(key));
+            response.headers.push((key.clone(), ops::to_text(value)));
+        }
+    }
+    match check_headers(&response.headers) {
+        Ok(()) => response,
+        Err(error) => HttpResponse::new(500, "text/plain", error.to_string().into_bytes()),
+    }
+}
+
+// Response for a flow that failed
+// Stopped and saturated flows answer 503 so clients can retry later
+pub fn error_response(error: &FlowError) -> HttpResponse {
+    let status = match error.error_type.as_str() {
+        "RUNTIME:FLOW_STOPPED" | "RUNTIME:BACKPRESSURE" => 503,
+        _ => 500,
+    };
+    HttpResponse::new(status, "text/plain", error.to_string().into_bytes())
+}
+
+// Message attributes describing an incoming request
+pub fn request_attributes(request: &HttpRequest) -> Map<String, Value> {
+    let mut headers = Map::new();
+    for (key, value) in &request.headers {
+        headers.insert(key.to_lowercase(), Value::String(value.clone()));
+    }
+    let mut query_params = Map::new();
+    for pair in request.query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        query_params.insert(key.to_string(), Value::String(value.to_string()));
+    }
+    let mut attributes = Map::new();
+    attributes.insert("method".to_string(), Value::String(request.method.clone()));
+    attributes.insert("requestPath".to_string(), Value::String(request.path.clone()));
+    attributes.insert("queryString".to_string(), Value::String(request.query.clone()));
+    attributes.insert("queryParams".to_string(), Value::Object(query_params));
+    attributes.insert("headers".to_string(), Value::Object(headers));
+    attributes
+}
+
+// Frees a connection slot when its thread ends, even by panicking
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+// Blocking server that handles every connection on its own thread, at most `MAX_CONNECTIONS` at
+// once, until it is stopped
+pub struct HttpServer {
+    listener: TcpListener,
+    running: Arc<AtomicBool>,
+}
+
+impl HttpServer {
+    pub fn bind(address: &str) -> io::Result<Self> {
+        let listener = TcpListener::bind(address)?;
+        // Non-blocking accept lets `serve` notice a stop request
+        listener.set_nonblocking(true)?;
+        Ok(Self {
+            listener,
+            running: Arc::new(AtomicBool::new(true)),
+        })
+    }
+
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    // Flag that keeps the server running; store `false` to stop it
+    pub fn running_flag(&self) -> Arc<AtomicBool> {
+        self.running.clone()
+    }
+
+    pub fn serve<F>(self, handler: F) -> io::Result<()>
+    where
+        F: Fn(HttpRequest) -> HttpResponse + Send + Sync + 'static,
+    {
+        let handler = Arc::new(handler);
+        let connections = Arc::new(AtomicUsize::new(0));
+        while self.running.load(Ordering::SeqCst) {
+            if connections.load(Ordering::SeqCst) >= MAX_CONNECTIONS {
+                thread::sleep(Duration::from_millis(10));
+                continue;
+            }
+            match self.listener.accept() {
+                Ok((stream, _)) => {
+                    let handler = handler.clone();
+                    connections.fetch_add(1, Ordering::SeqCst);
+                    let slot = ConnectionSlot(connections.clone());
+                    thread::spawn(move || {
+                        let _slot = slot;
+                        stream.set_nonblocking(false).ok();
+                        // A client that stops sending or reading does not hold the thread forever
+                        stream.set_write_timeout(Some(READ_TIMEOUT)).ok();
+                        let response = match read_request(&stream) {
+                            Ok(request) => handler(request),
+                            Err(error) if is_too_large(&error) => HttpResponse::new(413, "text/plain", error.to_string().into_bytes()),
+                            Err(error) if is_timeout(&error) => HttpResponse::new(408, "text/plain", "Request not received in time".as_bytes().to_vec()),
+                            Err(error) => HttpResponse::new(400, "text/plain", error.to_string().into_bytes()),
+                        };
+                        write_response(&stream, &response).ok();
+                    });
+                }
+                Err(error) if error.kind() == io::ErrorKind::WouldBlock => thread::sleep(Duration::from_millis(10)),
+                // A connection aborted before it was accepted, or no file descriptors left for
+                // now; the listener itself is fine
+                Err(error) => {
+                    println!("Cannot accept a connection on {}: {}", self.local_addr().map(|address| address.to_string()).unwrap_or_default(), error);
+                    thread::sleep(Duration::from_millis(100));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(raw: &[u8]) -> io::Result<RawMessage> {
+        read_head_and_body(&mut BufReader::new(raw))
+    }
+
+    #[test]
+    fn reads_headers_and_a_content_length_body() {
+        let message = parse(b"POST /orders?id=1 HTTP/1.1\r\nHost: localhost\r\nContent-Type: application/json\r\nContent-Length: 8\r\n\r\n{\"a\": 1}trailing").unwrap();
+        assert_eq!(message.start_line, "POST /orders?id=1 HTTP/1.1");
+        assert_eq!(find_header(&message.headers, "content-type"), Some("application/json"));
+        assert_eq!(message.body, b"{\"a\": 1}");
+    }
+
+    #[test]
+    fn joins_chunked_bodies() {
+        let raw = b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n5\r\nhello\r\n7;name=value\r\n, world\r\n0\r\n\r\n";
+        assert_eq!(parse(raw).unwrap().body, b"hello, world");
+    }
+
+    #[test]
+    fn refuses_malformed_or_oversized_requests() {
+        let error = parse(b"POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\nzz\r\n").err().unwrap();
+        assert_eq!(error.to_string(), "Invalid chunk size");
+        let error = parse(format!("POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n", MAX_BODY_SIZE + 1).as_bytes()).err().unwrap();
+        assert!(is_too_large(&error));
+        let error = parse(format!("POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n{:x}\r\n", MAX_BODY_SIZE + 1).as_bytes()).err().unwrap();
+        assert!(is_too_large(&error));
+        let error = parse(format!("GET /{} HTTP/1.1\r\n\r\n", "a".repeat(MAX_LINE_LENGTH as usize)).as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "Header line too long");
+        let headers: String = (0..=MAX_HEADERS).map(|index| format!("X-{}: 1\r\n", index)).collect();
+        let error = parse(format!("GET / HTTP/1.1\r\n{}\r\n", headers).as_bytes()).err().unwrap();
+        assert_eq!(error.to_string(), "Too many headers");
+        // The body ends before its announced length
+        assert!(parse(b"POST / HTTP/1.1\r\nContent-Length: 10\r\n\r\nshort").is_err());
+    }
+
+    #[test]
+    fn responses_without_a_length_run_to_the_end() {
+        let message = parse(b"HTTP/1.1 200 OK\r\nContent-Type: text/plain\r\n\r\nall of it").unwrap();
+        assert_eq!(message.body, b"all of it");
+        // A request without a length has no body
+        assert!(parse(b"GET / HTTP/1.1\r\n\r\nignored").unwrap().body.is_empty());
+    }
+
+    #[test]
+    fn a_request_round_trips_through_the_server() {
+        let server = HttpServer::bind("127.0.0.1:0").unwrap();
+        let address = server.local_addr().unwrap();
+        let running = server.running_flag();
+        let serving = std::thread::spawn(move || server.serve(|request| {
+            let body = format!("{} {} {} {}", request.method, request.path, request.query, String::from_utf8_lossy(&request.body));
+            HttpResponse::new(201, "text/plain", body.into_bytes())
+        }));
+        let url = format!("http://{}/orders?id=7", address);
+        let response = send("POST", &url, &[("X-Test".to_string(), "1".to_string())], b"body", Duration::from_secs(5)).unwrap();
+        running.store(false, std::sync::atomic::Ordering::SeqCst);
+        assert_eq!(response.status, 201);
+        assert_eq!(response.header("content-type"), Some("text/plain"));
+        assert_eq!(response.body, b"POST /orders id=7 body");
+        serving.join().unwrap().unwrap();
+    }
+
+    #[test]
+    fn listener_paths_and_methods_match() {
+        assert!(path_matches("/orders/", "/orders"));
+        assert!(path_matches("/orders/*", "/orders/7/items") && path_matches("/orders/*", "/orders"));
+        assert!(!path_matches("/orders/*", "/ordersx") && !path_matches("/orders", "/orders/7"));
+        assert!(method_allowed("", "DELETE") && method_allowed("get, post", "POST"));
+        assert!(!method_allowed("GET", "POST"));
+    }
+}
@@ -0,0 +1,47 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::json;
+
+pub const LEVELS: &[&str] = &["TRACE", "DEBUG", "INFO", "WARN", "ERROR"];
+
+// One Logger entry
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogRecord {
+    pub timestamp: DateTime<Utc>,
+    pub level: String,
+    pub category: String,
+    pub message: String,
+}
+
+impl LogRecord {
+    // Unknown levels are logged as INFO
+    pub fn new(level: &str, category: &str, message: impl Into<String>) -> Self {
+        let level = level.trim().to_ascii_uppercase();
+        Self {
+            timestamp: Utc::now(),
+            level: if LEVELS.contains(&level.as_str()) { level } else { "INFO".to_string() },
+            category: category.to_string(),
+            message: message.into(),
+        }
+    }
+
+    // Structured form: one JSON object per line
+    pub fn to_json(&self) -> String {
+        json!({
+            "timestamp": self.timestamp.to_rfc3339_opts(SecondsFormat::Millis, true),
+            "level": self.level,
+            "category": self.category,
+            "message": self.message,
+        })
+        .to_string()
+    }
+
+    // Human readable form for the editor's log panel
+    pub fn to_line(&self) -> String {
+        format!("{} {:<5} [{}] {}", self.timestamp.format("%H:%M:%S%.3f"), self.level, self.category, self.message)
+    }
+}
+
+// Writes a record to stdout as structured JSON
+pub fn log(record: &LogRecord) {
+    println!("{}", record.to_json());
+}
@@ -0,0 +1,44 @@
+use std::fmt;
+use serde_json::{Map, Value};
+
+// The message travelling through a flow: payload plus attributes and flow variables
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Message {
+    pub payload: Value,
+    pub mime_type: String,
+    pub attributes: Map<String, Value>,
+    pub variables: Map<String, Value>,
+}
+
+impl Message {
+    pub fn new(payload: Value) -> Self {
+        Self {
+            payload,
+            ..Default::default()
+        }
+    }
+}
+
+// Error raised by a component; `error_type` follows the `NAMESPACE:IDENTIFIER` convention (e.g. `HTTP:CONNECTIVITY`)
+#[derive(Debug, Clone, PartialEq)]
+pub struct FlowError {
+    pub error_type: String,
+    pub description: String,
+}
+
+impl FlowError {
+    pub fn new(error_type: &str, description: impl Into<String>) -> Self {
+        Self {
+            error_type: error_type.to_string(),
+            description: description.into(),
+        }
+    }
+}
+
+impl fmt::Display for FlowError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.error_type, self.description)
+    }
+}
+
+impl std::error::Error for FlowError {}
@@ -0,0 +1,64 @@
+// Code shared verbatim between the built-in runtime and generated service projects.
+// The code generator copies these files into the emitted support crate, so they may only
+// depend on `std` and the crates in `DEPENDENCIES`, and must refer to each other through `super::`.
+// Parts of these modules are only used by generated projects
+#[allow(dead_code)]
+pub(crate) mod broker;
+#[allow(dead_code)]
+pub(crate) mod database;
+#[allow(dead_code)]
+pub(crate) mod executor;
+#[allow(dead_code)]
+pub(crate) mod files;
+#[allow(dead_code)]
+pub(crate) mod http;
+#[allow(dead_code)]
+pub(crate) mod logging;
+#[allow(dead_code)]
+pub(crate) mod message;
+#[allow(dead_code)]
+pub(crate) mod object_store;
+#[allow(dead_code)]
+pub(crate) mod ops;
+#[allow(dead_code)]
+pub(crate) mod retry;
+#[allow(dead_code)]
+pub(crate) mod scheduler;
+#[allow(dead_code)]
+pub(crate) mod scopes;
+#[allow(dead_code)]
+pub(crate) mod secure;
+#[allow(dead_code)]
+pub(crate) mod supervisor;
+
+// Sources of the shared files, embedded for the code generator
+pub(crate) const SHARED_SOURCES: &[(&str, &str)] = &[
+    ("broker.rs", include_str!("broker.rs")),
+    ("database.rs", include_str!("database.rs")),
+    ("executor.rs", include_str!("executor.rs")),
+    ("files.rs", include_str!("files.rs")),
+    ("http.rs", include_str!("http.rs")),
+    ("logging.rs", include_str!("logging.rs")),
+    ("message.rs", include_str!("message.rs")),
+    ("object_store.rs", include_str!("object_store.rs")),
+    ("ops.rs", include_str!("ops.rs")),
+    ("retry.rs", include_str!("retry.rs")),
+    ("scheduler.rs", include_str!("scheduler.rs")),
+    ("scopes.rs", include_str!("scopes.rs")),
+    ("secure.rs", include_str!("secure.rs")),
+    ("supervisor.rs", include_str!("supervisor.rs")),
+];
+
+// Dependencies of the shared files, written into the generated support crate's manifest; a
+// requirement starting with `{` is written as an inline table
+pub(crate) const DEPENDENCIES: &[(&str, &str)] = &[
+    ("aes-gcm", "0.10"),
+    ("base64", "0.22"),
+    ("chrono", "0.4"),
+    ("chrono-tz", "0.10"),
+    ("cron", "0.15"),
+    ("pbkdf2", "{ version = \"0.12\", features = [\"hmac\"] }"),
+    ("rusqlite", "{ version = \"0.32\", features = [\"bundled\"] }"),
+    ("serde_json", "1"),
+    ("sha2", "0.10"),
+];
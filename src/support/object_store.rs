@@ -0,0 +1,420 @@
+// Key-value stores behind an `ObjectStore` trait, used by the Cache scope and the Idempotent
+// Message Validator. Entries may expire after a time to live, and the oldest entries make room
+// once a store is full. The file-backed store keeps its entries across restarts.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, OnceLock};
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use serde_json::{Map, Value};
+use super::message::{FlowError, Message};
+
+pub const PERSISTENCE: &[&str] = &["InMemory", "File"];
+
+// Where a file-backed store without a file keeps its entries, relative to the project folder
+pub const DEFAULT_FOLDER: &str = "data/object-stores";
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredEntry {
+    pub key: String,
+    pub value: Value,
+    pub stored_at: DateTime<Utc>,
+    pub expires_at: Option<DateTime<Utc>>,
+}
+
+impl StoredEntry {
+    fn is_expired(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now)
+    }
+
+    fn to_json(&self) -> Value {
+        let mut entry = Map::new();
+        entry.insert("key".to_string(), Value::String(self.key.clone()));
+        entry.insert("value".to_string(), self.value.clone());
+        entry.insert("storedAt".to_string(), Value::from(self.stored_at.timestamp_millis()));
+        entry.insert("expiresAt".to_string(), self.expires_at.map(|at| Value::from(at.timestamp_millis())).unwrap_or(Value::Null));
+        Value::Object(entry)
+    }
+
+    fn from_json(entry: &Value) -> Option<Self> {
+        let time = |key: &str| entry.get(key).and_then(Value::as_i64).and_then(|millis| Utc.timestamp_millis_opt(millis).single());
+        Some(Self {
+            key: entry.get("key")?.as_str()?.to_string(),
+            value: entry.get("value").cloned().unwrap_or(Value::Null),
+            stored_at: time("storedAt")?,
+            expires_at: time("expiresAt"),
+        })
+    }
+}
+
+pub trait ObjectStore: Send + Sync {
+    fn contains(&self, key: &str) -> Result<bool, FlowError>;
+    fn retrieve(&self, key: &str) -> Result<Option<Value>, FlowError>;
+    // Stores the value under the key, replacing an earlier value
+    fn store(&self, key: &str, value: Value) -> Result<(), FlowError>;
+    // Stores the value unless the key is present; returns whether it was stored
+    fn store_if_absent(&self, key: &str, value: Value) -> Result<bool, FlowError>;
+    // Returns whether the key was present
+    fn remove(&self, key: &str) -> Result<bool, FlowError>;
+    // Entries that have not expired, oldest first
+    fn entries(&self) -> Result<Vec<StoredEntry>, FlowError>;
+    fn clear(&self) -> Result<(), FlowError>;
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StoreSettings {
+    // Entries expire this long after they were stored; none keeps them until removed
+    pub ttl: Option<Duration>,
+    // Beyond this many entries the oldest are evicted; none keeps every entry
+    pub max_entries: Option<usize>,
+    // File the entries persist in; none keeps them in memory only
+    pub file: Option<PathBuf>,
+}
+
+impl StoreSettings {
+    // A time to live or maximum of 0 means no limit; the file is only used with `File` persistence
+    pub fn new(persistence: &str, ttl_ms: u64, max_entries: usize, file: PathBuf) -> Self {
+        Self {
+            ttl: (ttl_ms > 0).then(|| Duration::milliseconds(ttl_ms.min(i64::MAX as u64) as i64)),
+            max_entries: (max_entries > 0).then_some(max_entries),
+            file: persistence.eq_ignore_ascii_case("File").then_some(file),
+        }
+    }
+}
+
+// File a store keeps its entries in: the configured one, or one named after the store
+pub fn store_file(name: &str, file: &str) -> String {
+    match file.trim() {
+        "" => format!("{}/{}.json", DEFAULT_FOLDER, name.trim()),
+        file => file.to_string(),
+    }
+}
+
+fn unavailable(description: impl Into<String>) -> FlowError {
+    FlowError::new("OS:STORE_NOT_AVAILABLE", description)
+}
+
+// Entries with the order they were stored in, so eviction does not depend on clock resolution
+#[derive(Default)]
+struct StoreState {
+    entries: HashMap<String, (u64, StoredEntry)>,
+    next_sequence: u64,
+}
+
+impl StoreState {
+    fn purge_expired(&mut self) {
+        let now = Utc::now();
+        self.entries.retain(|_, (_, entry)| !entry.is_expired(now));
+    }
+
+    // Entries oldest first
+    fn sorted(&self) -> Vec<StoredEntry> {
+        let mut entries: Vec<&(u64, StoredEntry)> = self.entries.values().collect();
+        entries.sort_by_key(|(sequence, _)| *sequence);
+        entries.into_iter().map(|(_, entry)| entry.clone()).collect()
+    }
+
+    fn put(&mut self, entry: StoredEntry) {
+        self.next_sequence += 1;
+        self.entries.insert(entry.key.clone(), (self.next_sequence, entry));
+    }
+
+    fn insert(&mut self, key: &str, value: Value, settings: &StoreSettings) {
+        let stored_at = Utc::now();
+        let expires_at = settings.ttl.map(|ttl| stored_at + ttl);
+        self.put(StoredEntry { key: key.to_string(), value, stored_at, expires_at });
+        if let Some(max_entries) = settings.max_entries {
+            let excess = self.entries.len().saturating_sub(max_entries);
+            for evicted in self.sorted().into_iter().take(excess) {
+                self.entries.remove(&evicted.key);
+            }
+        }
+    }
+}
+
+// Keeps its entries in memory; they are gone once the process ends
+pub struct InMemoryObjectStore {
+    settings: StoreSettings,
+    state: Mutex<StoreState>,
+}
+
+impl InMemoryObjectStore {
+    pub fn new(settings: StoreSettings) -> Self {
+        Self { settings, state: Mutex::new(StoreState::default()) }
+    }
+
+    // Expired entries are dropped whenever the store is used
+    fn state(&self) -> Result<MutexGuard<'_, StoreState>, FlowError> {
+        let mut state = self.state.lock().map_err(|_| unavailable("The object store is unavailable"))?;
+        state.purge_expired();
+        Ok(state)
+    }
+}
+
+impl ObjectStore for InMemoryObjectStore {
+    fn contains(&self, key: &str) -> Result<bool, FlowError> {
+        Ok(self.state()?.entries.contains_key(key))
+    }
+
+    fn retrieve(&self, key: &str) -> Result<Option<Value>, FlowError> {
+        Ok(self.state()?.entries.get(key).map(|(_, entry)| entry.value.clone()))
+    }
+
+    fn store(&self, key: &str, value: Value) -> Result<(), FlowError> {
+        self.state()?.insert(key, value, &self.settings);
+        Ok(())
+    }
+
+    fn store_if_absent(&self, key: &str, value: Value) -> Result<bool, FlowError> {
+        let mut state = self.state()?;
+        if state.entries.contains_key(key) {
+            return Ok(false);
+        }
+        state.insert(key, value, &self.settings);
+        Ok(true)
+    }
+
+    fn remove(&self, key: &str) -> Result<bool, FlowError> {
+        Ok(self.state()?.entries.remove(key).is_some())
+    }
+
+    fn entries(&self) -> Result<Vec<StoredEntry>, FlowError> {
+        Ok(self.state()?.sorted())
+    }
+
+    fn clear(&self) -> Result<(), FlowError> {
+        self.state()?.entries.clear();
+        Ok(())
+    }
+}
+
+// Keeps its entries in memory and writes them to a JSON file after every change; the file is
+// read when the store is opened, so entries outlive the process
+pub struct FileObjectStore {
+    memory: InMemoryObjectStore,
+    path: PathBuf,
+}
+
+impl FileObjectStore {
+    pub fn open(path: &Path, settings: StoreSettings) -> Result<Self, FlowError> {
+        let memory = InMemoryObjectStore::new(settings);
+        if path.exists() {
+            let text = fs::read_to_string(path)
+                .map_err(|error| unavailable(format!("Cannot read {}: {}", path.display(), error)))?;
+            let entries: Value = serde_json::from_str(&text)
+                .map_err(|error| unavailable(format!("Invalid object store file {}: {}", path.display(), error)))?;
+            if let Ok(mut state) = memory.state.lock() {
+                for entry in entries.as_array().into_iter().flatten().filter_map(StoredEntry::from_json) {
+                    state.put(entry);
+                }
+            }
+        }
+        Ok(Self { memory, path: path.to_path_buf() })
+    }
+
+    fn save(&self) -> Result<(), FlowError> {
+        let entries: Vec<Value> = self.memory.entries()?.iter().map(StoredEntry::to_json).collect();
+        if let Some(parent) = self.path.parent() {
+            fs::create_dir_all(parent)
+                .map_err(|error| unavailable(format!("Cannot create {}: {}", parent.display(), error)))?;
+        }
+        let text = serde_json::to_string_pretty(&Value::Array(entries)).unwrap_or_default();
+        fs::write(&self.path, text).map_err(|error| unavailable(format!("Cannot write {}: {}", self.path.display(), error)))
+    }
+}
+
+impl ObjectStore for FileObjectStore {
+    fn contains(&self, key: &str) -> Result<bool, FlowError> {
+        self.memory.contains(key)
+    }
+
+    fn retrieve(&self, key: &str) -> Result<Option<Value>, FlowError> {
+        self.memory.retrieve(key)
+    }
+
+    fn store(&self, key: &str, value: Value) -> Result<(), FlowError> {
+        self.memory.store(key, value)?;
+        self.save()
+    }
+
+    fn store_if_absent(&self, key: &str, value: Value) -> Result<bool, FlowError> {
+        let stored = self.memory.store_if_absent(key, value)?;
+        if stored {
+            self.save()?;
+        }
+        Ok(stored)
+    }
+
+    fn remove(&self, key: &str) -> Result<bool, FlowError> {
+        let removed = self.memory.remove(key)?;
+        if removed {
+            self.save()?;
+        }
+        Ok(removed)
+    }
+
+    fn entries(&self) -> Result<Vec<StoredEntry>, FlowError> {
+        self.memory.entries()
+    }
+
+    fn clear(&self) -> Result<(), FlowError> {
+        self.memory.clear()?;
+        self.save()
+    }
+}
+
+pub fn open_store(settings: StoreSettings) -> Result<Arc<dyn ObjectStore>, FlowError> {
+    match settings.file.clone() {
+        Some(path) => Ok(Arc::new(FileObjectStore::open(&path, settings)?)),
+        None => Ok(Arc::new(InMemoryObjectStore::new(settings))),
+    }
+}
+
+// One store per name, opened on first use with the settings given then
+#[derive(Default)]
+pub struct ObjectStoreRegistry {
+    stores: Mutex<BTreeMap<String, Arc<dyn ObjectStore>>>,
+}
+
+impl ObjectStoreRegistry {
+    pub fn get(&self, name: &str, settings: StoreSettings) -> Result<Arc<dyn ObjectStore>, FlowError> {
+        let mut stores = self.stores.lock().map_err(|_| unavailable("The object store registry is unavailable"))?;
+        if let Some(store) = stores.get(name) {
+            return Ok(store.clone());
+        }
+        let store = open_store(settings)?;
+        stores.insert(name.to_string(), store.clone());
+        Ok(store)
+    }
+
+    // Open stores by name
+    pub fn stores(&self) -> Vec<(String, Arc<dyn ObjectStore>)> {
+        self.stores.lock()
+            .map(|stores| stores.iter().map(|(name, store)| (name.clone(), store.clone())).collect())
+            .unwrap_or_default()
+    }
+}
+
+// Object stores of a generated service, shared by all of its flows
+pub fn shared_store(name: &str, settings: StoreSettings) -> Result<Arc<dyn ObjectStore>, FlowError> {
+    static REGISTRY: OnceLock<ObjectStoreRegistry> = OnceLock::new();
+    REGISTRY.get_or_init(ObjectStoreRegistry::default).get(name, settings)
+}
+
+// Cache scope: a stored result for the key replaces the payload and MIME type; otherwise `run`
+// produces the message and its payload and MIME type are stored for the next time
+pub fn cached(store: &dyn ObjectStore, key: &str, mut message: Message, run: impl FnOnce(Message) -> Result<Message, FlowError>) -> Result<Message, FlowError> {
+    if let Some(Value::Object(hit)) = store.retrieve(key)? {
+        message.payload = hit.get("payload").cloned().unwrap_or(Value::Null);
+        if let Some(mime_type) = hit.get("mimeType").and_then(Value::as_str) {
+            message.mime_type = mime_type.to_string();
+        }
+        return Ok(message);
+    }
+    let message = run(message)?;
+    let mut result = Map::new();
+    result.insert("payload".to_string(), message.payload.clone());
+    result.insert("mimeType".to_string(), Value::String(message.mime_type.clone()));
+    store.store(key, Value::Object(result))?;
+    Ok(message)
+}
+
+// Idempotent Message Validator: lets a message through the first time its id is seen and fails
+// with `RUNTIME:DUPLICATE_MESSAGE` while the id is in the store
+pub fn validate_unique(store: &dyn ObjectStore, id: &str) -> Result<(), FlowError> {
+    if store.store_if_absent(id, Value::String(id.to_string()))? {
+        Ok(())
+    } else {
+        Err(FlowError::new("RUNTIME:DUPLICATE_MESSAGE", format!("Message '{}' was already processed", id)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::thread;
+    use serde_json::json;
+    use super::*;
+
+    fn settings(ttl_ms: u64, max_entries: usize) -> StoreSettings {
+        StoreSettings::new("InMemory", ttl_ms, max_entries, PathBuf::new())
+    }
+
+    fn keys(store: &dyn ObjectStore) -> Vec<String> {
+        store.entries().unwrap().into_iter().map(|entry| entry.key).collect()
+    }
+
+    #[test]
+    fn entries_expire_after_the_time_to_live() {
+        let store = InMemoryObjectStore::new(settings(50, 0));
+        store.store("a", json!(1)).unwrap();
+        let entry = &store.entries().unwrap()[0];
+        assert_eq!(entry.expires_at, Some(entry.stored_at + Duration::milliseconds(50)));
+        assert_eq!(store.retrieve("a").unwrap(), Some(json!(1)));
+        thread::sleep(std::time::Duration::from_millis(80));
+        assert!(!store.contains("a").unwrap());
+        // An expired key can be stored again
+        assert!(store.store_if_absent("a", json!(2)).unwrap());
+    }
+
+    #[test]
+    fn full_stores_evict_the_oldest_entries() {
+        let store = InMemoryObjectStore::new(settings(0, 2));
+        for key in ["a", "b", "c"] {
+            store.store(key, json!(key)).unwrap();
+        }
+        assert_eq!(keys(&store), vec!["b", "c"]);
+        // Storing a key again makes it the newest
+        store.store("b", json!("again")).unwrap();
+        store.store("d", json!("d")).unwrap();
+        assert_eq!(keys(&store), vec!["b", "d"]);
+        assert_eq!(store.retrieve("b").unwrap(), Some(json!("again")));
+    }
+
+    #[test]
+    fn file_stores_keep_their_entries_across_openings() {
+        let path = std::env::temp_dir().join(format!("object-store-test-{}", std::process::id())).join("orders.json");
+        let _ = fs::remove_file(&path);
+        let settings = StoreSettings::new("File", 0, 0, path.clone());
+        let store = FileObjectStore::open(&path, settings.clone()).unwrap();
+        store.store("a", json!({ "id": 1 })).unwrap();
+        store.store("b", json!(2)).unwrap();
+        assert!(store.remove("b").unwrap());
+        let reopened = FileObjectStore::open(&path, settings.clone()).unwrap();
+        // Times are kept to the millisecond
+        let entries = reopened.entries().unwrap();
+        assert_eq!((entries.len(), entries[0].key.as_str(), &entries[0].value), (1, "a", &json!({ "id": 1 })));
+        assert_eq!(entries[0].stored_at.timestamp_millis(), store.entries().unwrap()[0].stored_at.timestamp_millis());
+        reopened.clear().unwrap();
+        assert!(FileObjectStore::open(&path, settings).unwrap().entries().unwrap().is_empty());
+        let _ = fs::remove_dir_all(path.parent().unwrap());
+    }
+
+    #[test]
+    fn unreadable_store_files_are_reported() {
+        let path = std::env::temp_dir().join(format!("object-store-invalid-{}.json", std::process::id()));
+        fs::write(&path, "not json").unwrap();
+        let error = open_store(StoreSettings::new("File", 0, 0, path.clone())).err().unwrap();
+        assert_eq!(error.error_type, "OS:STORE_NOT_AVAILABLE");
+        let _ = fs::remove_file(&path);
+    }
+
+    #[test]
+    fn cache_hits_skip_the_components() {
+        let store = InMemoryObjectStore::new(StoreSettings::default());
+        let miss = cached(&store, "k", Message::new(json!("in")), |mut message| {
+            message.payload = json!("computed");
+            message.mime_type = "text/plain".to_string();
+            Ok(message)
+        });
+        assert_eq!(miss.unwrap().payload, json!("computed"));
+        let hit = cached(&store, "k", Message::new(json!("in")), |_| panic!("Cached results are not computed again")).unwrap();
+        assert_eq!((hit.payload, hit.mime_type.as_str()), (json!("computed"), "text/plain"));
+    }
+
+    #[test]
+    fn repeated_message_ids_are_duplicates() {
+        let store = InMemoryObjectStore::new(StoreSettings::default());
+        assert!(validate_unique(&store, "42").is_ok());
+        assert_eq!(validate_unique(&store, "42").unwrap_err().error_type, "RUNTIME:DUPLICATE_MESSAGE");
+    }
+}
@@ -0,0 +1,230 @@
+use std::cmp::Ordering;
+use serde_json::{Map, Number, Value};
+use super::message::FlowError;
+
+// Operations behind the expression language. Both the interpreter and generated code call
+// into these functions, which keeps the two execution paths behaving the same way.
+
+fn expression_error(description: String) -> FlowError {
+    FlowError::new("EXPRESSION", description)
+}
+
+fn number(value: f64) -> Value {
+    // Keep integral results integral so `1 + 1` prints as `2` and not `2.0`
+    if value.fract() == 0.0 && value.abs() < i64::MAX as f64 {
+        Value::Number(Number::from(value as i64))
+    } else {
+        Number::from_f64(value).map(Value::Number).unwrap_or(Value::Null)
+    }
+}
+
+pub fn truthy(value: &Value) -> bool {
+    match value {
+        Value::Null => false,
+        Value::Bool(value) => *value,
+        Value::Number(value) => value.as_f64().map(|value| value != 0.0).unwrap_or(false),
+        Value::String(value) => !value.is_empty(),
+        Value::Array(_) | Value::Object(_) => true,
+    }
+}
+
+// Text form of a value: strings without quotes, everything else as JSON
+pub fn to_text(value: &Value) -> String {
+    match value {
+        Value::String(value) => value.clone(),
+        Value::Null => String::new(),
+        other => other.to_string(),
+    }
+}
+
+pub fn equals(left: &Value, right: &Value) -> bool {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64() == right.as_f64(),
+        _ => left == right,
+    }
+}
+
+pub fn compare(left: &Value, right: &Value) -> Option<Ordering> {
+    match (left, right) {
+        (Value::Number(left), Value::Number(right)) => left.as_f64()?.partial_cmp(&right.as_f64()?),
+        (Value::String(left), Value::String(right)) => Some(left.cmp(right)),
+        (Value::Bool(left), Value::Bool(right)) => Some(left.cmp(right)),
+        _ => None,
+    }
+}
+
+pub fn binary(operator: &str, left: &Value, right: &Value) -> Result<Value, FlowError> {
+    match operator {
+        "==" => Ok(Value::Bool(equals(left, right))),
+        "!=" => Ok(Value::Bool(!equals(left, right))),
+        "<" | "<=" | ">" | ">=" => {
+            let ordering = compare(left, right).ok_or_else(|| {
+                expression_error(format!("Cannot compare {} with {}", left, right))
+            })?;
+            Ok(Value::Bool(match operator {
+                "<" => ordering == Ordering::Less,
+                "<=" => ordering != Ordering::Greater,
+                ">" => ordering == Ordering::Greater,
+                _ => ordering != Ordering::Less,
+            }))
+        }
+        "++" => Ok(concat(left, right)),
+        "+" | "-" | "*" | "/" | "%" => {
+            if operator == "+" && (left.is_string() || right.is_string()) {
+                return Ok(Value::String(to_text(left) + &to_text(right)));
+            }
+            let (l, r) = match (left.as_f64(), right.as_f64()) {
+                (Some(l), Some(r)) => (l, r),
+                _ => return Err(expression_error(format!("Operator '{}' needs numbers, got {} and {}", operator, left, right))),
+            };
+            match operator {
+                "+" => Ok(number(l + r)),
+                "-" => Ok(number(l - r)),
+                "*" => Ok(number(l * r)),
+                _ if r == 0.0 => Err(expression_error("Division by zero".to_string())),
+                "/" => Ok(number(l / r)),
+                _ => Ok(number(l % r)),
+            }
+        }
+        _ => Err(expression_error(format!("Unknown operator '{}'", operator))),
+    }
+}
+
+pub fn unary(operator: &str, value: &Value) -> Result<Value, FlowError> {
+    match operator {
+        "not" => Ok(Value::Bool(!truthy(value))),
+        "-" => value.as_f64()
+            .map(|value| number(-value))
+            .ok_or_else(|| expression_error(format!("Cannot negate {}", value))),
+        _ => Err(expression_error(format!("Unknown operator '{}'", operator))),
+    }
+}
+
+// `++`: joins strings and arrays, merges objects (right side wins)
+pub fn concat(left: &Value, right: &Value) -> Value {
+    match (left, right) {
+        (Value::Array(left), Value::Array(right)) => Value::Array(left.iter().chain(right.iter()).cloned().collect()),
+        (Value::Object(left), Value::Object(right)) => {
+            let mut merged = left.clone();
+            for (key, value) in right {
+                merged.insert(key.clone(), value.clone());
+            }
+            Value::Object(merged)
+        }
+        _ => Value::String(to_text(left) + &to_text(right)),
+    }
+}
+
+// `value.key`; missing keys and non-objects select null
+pub fn select(value: &Value, key: &str) -> Value {
+    match value {
+        Value::Object(map) => map.get(key).cloned().unwrap_or(Value::Null),
+        _ => Value::Null,
+    }
+}
+
+// `value[index]`; negative array indexes count from the end
+pub fn index(value: &Value, index: &Value) -> Value {
+    match (value, index) {
+        (Value::Array(items), Value::Number(position)) => {
+            let position = position.as_f64().unwrap_or(0.0) as i64;
+            let position = if position < 0 { items.len() as i64 + position } else { position };
+            items.get(position as usize).cloned().unwrap_or(Value::Null)
+        }
+        (Value::String(text), Value::Number(position)) => text.chars()
+            .nth(position.as_f64().unwrap_or(0.0) as usize)
+            .map(|c| Value::String(c.to_string()))
+            .unwrap_or(Value::Null),
+        (_, Value::String(key)) => select(value, key),
+        _ => Value::Null,
+    }
+}
+
+fn argument(name: &str, args: &[Value], position: usize) -> Result<Value, FlowError> {
+    args.get(position).cloned().ok_or_else(|| {
+        expression_error(format!("Function '{}' expects at least {} argument(s)", name, position + 1))
+    })
+}
+
+// Built-in functions of the expression language
+pub fn call(name: &str, args: Vec<Value>) -> Result<Value, FlowError> {
+    let first = argument(name, &args, 0)?;
+    match name {
+        "upper" => Ok(Value::String(to_text(&first).to_uppercase())),
+        "lower" => Ok(Value::String(to_text(&first).to_lowercase())),
+        "trim" => Ok(Value::String(to_text(&first).trim().to_string())),
+        "toString" => Ok(Value::String(to_text(&first))),
+        "toNumber" => to_text(&first).trim().parse::<f64>()
+            .map(number)
+            .map_err(|_| expression_error(format!("Cannot convert {} to a number", first))),
+        "sizeOf" => Ok(Value::from(match &first {
+            Value::Array(items) => items.len(),
+            Value::Object(map) => map.len(),
+            Value::String(text) => text.chars().count(),
+            Value::Null => 0,
+            _ => 1,
+        })),
+        "isEmpty" => Ok(Value::Bool(match &first {
+            Value::Array(items) => items.is_empty(),
+            Value::Object(map) => map.is_empty(),
+            Value::String(text) => text.is_empty(),
+            Value::Null => true,
+            _ => false,
+        })),
+        "contains" => {
+            let needle = argument(name, &args, 1)?;
+            Ok(Value::Bool(match &first {
+                Value::Array(items) => items.iter().any(|item| equals(item, &needle)),
+                Value::Object(map) => map.contains_key(&to_text(&needle)),
+                other => to_text(other).contains(&to_text(&needle)),
+            }))
+        }
+        "splitBy" => {
+            let separator = to_text(&argument(name, &args, 1)?);
+            Ok(Value::Array(to_text(&first).split(separator.as_str()).map(|part| Value::String(part.to_string())).collect()))
+        }
+        "joinBy" => {
+            let separator = to_text(&argument(name, &args, 1)?);
+            match first {
+                Value::Array(items) => Ok(Value::String(items.iter().map(to_text).collect::<Vec<_>>().join(&separator))),
+                other => Ok(Value::String(to_text(&other))),
+            }
+        }
+        "keys" => Ok(match first {
+            Value::Object(map) => Value::Array(map.keys().map(|key| Value::String(key.clone())).collect()),
+            _ => Value::Array(Vec::new()),
+        }),
+        _ => Err(expression_error(format!("Unknown function '{}'", name))),
+    }
+}
+
+// Builds an object from key/value pairs, used by object literals
+pub fn object(entries: Vec<(&str, Value)>) -> Value {
+    let mut map = Map::new();
+    for (key, value) in entries {
+        map.insert(key.to_string(), value);
+    }
+    Value::Object(map)
+}
+
+// Decodes raw bytes into a payload: JSON for JSON MIME types, text otherwise
+pub fn decode_payload(bytes: &[u8], mime_type: &str) -> Value {
+    if bytes.is_empty() {
+        return Value::Null;
+    }
+    if mime_type.contains("json") {
+        if let Ok(value) = serde_json::from_slice(bytes) {
+            return value;
+        }
+    }
+    Value::String(String::from_utf8_lossy(bytes).into_owned())
+}
+
+// Encodes a payload into bytes and the MIME type that describes them
+pub fn encode_payload(value: &Value) -> (Vec<u8>, &'static str) {
+    match value {
+        Value::Null => (Vec::new(), "text/plain"),
+        Value::String(text) => (text.clone().into_bytes(), "text/plain"),
+        other => (other.to_string().into_bytes(), "application/json"),
+    }
+}
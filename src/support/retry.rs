@@ -0,0 +1,215 @@
+// Retries for operations against flaky backends: retry policies that connector operations
+// reference, and the Until Successful scope. Delays follow the policy's backoff, spread by jitter.
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::thread;
+use std::time::Duration;
+use super::message::FlowError;
+
+pub const BACKOFF_STRATEGIES: &[&str] = &["Fixed", "Exponential"];
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backoff {
+    // The same delay before every retry
+    Fixed,
+    // The delay grows by the multiplier after every retry, up to the maximum delay
+    Exponential,
+}
+
+impl Backoff {
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("Exponential") {
+            Backoff::Exponential
+        } else {
+            Backoff::Fixed
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Backoff::Fixed => "Fixed",
+            Backoff::Exponential => "Exponential",
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    pub backoff: Backoff,
+    pub max_retries: u32,
+    pub delay: Duration,
+    pub max_delay: Duration,
+    pub multiplier: f64,
+    // Fraction of each delay by which it randomly varies either way, between 0 and 1
+    pub jitter: f64,
+    // Error types worth retrying; `HTTP:*` matches a whole namespace and an empty list every error
+    pub retryable_errors: Vec<String>,
+}
+
+impl RetryPolicy {
+    // `retryable_errors` is a comma separated list of error types
+    pub fn new(backoff: &str, max_retries: u32, delay_ms: u64, max_delay_ms: u64, multiplier: f64, jitter: f64, retryable_errors: &str) -> Self {
+        Self {
+            backoff: Backoff::from_name(backoff),
+            max_retries,
+            delay: Duration::from_millis(delay_ms),
+            max_delay: Duration::from_millis(max_delay_ms.max(delay_ms)),
+            multiplier: multiplier.max(1.0),
+            jitter: jitter.clamp(0.0, 1.0),
+            retryable_errors: retryable_errors.split(',')
+                .map(|error_type| error_type.trim().to_string())
+                .filter(|error_type| !error_type.is_empty())
+                .collect(),
+        }
+    }
+
+    pub fn is_retryable(&self, error: &FlowError) -> bool {
+        self.retryable_errors.is_empty() || self.retryable_errors.iter().any(|error_type| match error_type.strip_suffix(":*") {
+            Some(namespace) => error.error_type.split(':').next() == Some(namespace),
+            None => *error_type == error.error_type,
+        })
+    }
+
+    // Delay before retry number `retry`, counting from 1
+    pub fn delay_before(&self, retry: u32) -> Duration {
+        let base = match self.backoff {
+            Backoff::Fixed => self.delay,
+            Backoff::Exponential => {
+                let factor = self.multiplier.powi(retry.saturating_sub(1).min(64) as i32);
+                Duration::from_secs_f64((self.delay.as_secs_f64() * factor).min(self.max_delay.as_secs_f64()))
+            }
+        };
+        if self.jitter == 0.0 {
+            return base;
+        }
+        // A random factor in [1 - jitter, 1 + jitter]
+        let random = RandomState::new().build_hasher().finish() as f64 / u64::MAX as f64;
+        base.mul_f64(1.0 + self.jitter * (2.0 * random - 1.0))
+    }
+}
+
+// Runs `operation` until it succeeds, retrying the errors the policy deems retryable; the last
+// error is returned once the retries run out or an error is not retryable. Each retry is
+// described to `on_retry` before its delay.
+pub fn with_policy<T>(
+    policy: &RetryPolicy,
+    mut on_retry: impl FnMut(String),
+    mut operation: impl FnMut() -> Result<T, FlowError>,
+) -> Result<T, FlowError> {
+    let mut retry = 0;
+    loop {
+        match operation() {
+            Err(error) if retry < policy.max_retries && policy.is_retryable(&error) => {
+                retry += 1;
+                let delay = policy.delay_before(retry);
+                on_retry(format!("Retry {}/{} in {} ms after {}", retry, policy.max_retries, delay.as_millis(), error));
+                thread::sleep(delay);
+            }
+            result => return result,
+        }
+    }
+}
+
+// Until Successful: retries on any error after a fixed delay, described to `on_retry`; when the
+// retries run out the operation fails with `RETRY:RETRY_EXHAUSTED`, describing the last error
+pub fn until_successful<T>(
+    max_retries: u32,
+    delay_ms: u64,
+    mut on_retry: impl FnMut(String),
+    mut operation: impl FnMut() -> Result<T, FlowError>,
+) -> Result<T, FlowError> {
+    let mut retry = 0;
+    loop {
+        match operation() {
+            Ok(result) => return Ok(result),
+            Err(error) if retry < max_retries => {
+                retry += 1;
+                on_retry(format!("Until Successful retry {}/{} in {} ms after {}", retry, max_retries, delay_ms, error));
+                thread::sleep(Duration::from_millis(delay_ms));
+            }
+            Err(error) => {
+                return Err(FlowError::new(
+                    "RETRY:RETRY_EXHAUSTED",
+                    format!("Failed after {} retries: {}", max_retries, error),
+                ))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn failing(error_types: &'static [&'static str]) -> impl FnMut() -> Result<u32, FlowError> {
+        let mut attempt = 0;
+        move || {
+            attempt += 1;
+            match error_types.get(attempt as usize - 1) {
+                Some(error_type) => Err(FlowError::new(error_type, format!("attempt {}", attempt))),
+                None => Ok(attempt),
+            }
+        }
+    }
+
+    #[test]
+    fn exponential_delays_grow_up_to_the_maximum() {
+        let policy = RetryPolicy::new("exponential", 5, 100, 1000, 3.0, 0.0, "");
+        let delays: Vec<u128> = (1..=4).map(|retry| policy.delay_before(retry).as_millis()).collect();
+        assert_eq!(delays, vec![100, 300, 900, 1000]);
+        let fixed = RetryPolicy::new("Fixed", 5, 100, 1000, 3.0, 0.0, "");
+        assert_eq!(fixed.delay_before(4), Duration::from_millis(100));
+    }
+
+    #[test]
+    fn jitter_stays_within_its_fraction() {
+        let policy = RetryPolicy::new("Fixed", 5, 1000, 1000, 1.0, 0.2, "");
+        for _ in 0..50 {
+            let delay = policy.delay_before(1).as_millis();
+            assert!((800..=1200).contains(&delay), "{}", delay);
+        }
+    }
+
+    #[test]
+    fn error_types_match_exactly_or_by_namespace() {
+        let policy = RetryPolicy::new("Fixed", 1, 0, 0, 1.0, 0.0, "HTTP:*, DB:CONNECTIVITY");
+        assert!(policy.is_retryable(&FlowError::new("HTTP:TIMEOUT", "")));
+        assert!(policy.is_retryable(&FlowError::new("DB:CONNECTIVITY", "")));
+        assert!(!policy.is_retryable(&FlowError::new("DB:QUERY", "")));
+        assert!(!policy.is_retryable(&FlowError::new("HTTPS:TIMEOUT", "")));
+        assert!(RetryPolicy::new("Fixed", 1, 0, 0, 1.0, 0.0, " , ").is_retryable(&FlowError::new("ANY", "")));
+    }
+
+    #[test]
+    fn policies_retry_until_success_and_report_each_retry() {
+        let policy = RetryPolicy::new("Fixed", 3, 1, 1, 1.0, 0.0, "");
+        let mut retries = Vec::new();
+        let result = with_policy(&policy, |retry| retries.push(retry), failing(&["HTTP:TIMEOUT", "HTTP:TIMEOUT"]));
+        assert_eq!(result.unwrap(), 3);
+        assert_eq!(retries, vec![
+            "Retry 1/3 in 1 ms after HTTP:TIMEOUT: attempt 1".to_string(),
+            "Retry 2/3 in 1 ms after HTTP:TIMEOUT: attempt 2".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn policies_give_up_with_the_operations_own_error() {
+        let policy = RetryPolicy::new("Fixed", 1, 1, 1, 1.0, 0.0, "HTTP:*");
+        let error = with_policy(&policy, |_| {}, failing(&["HTTP:TIMEOUT", "HTTP:TIMEOUT"])).unwrap_err();
+        assert_eq!(error.description, "attempt 2");
+        // Errors that are not retryable fail at once
+        let mut retries = 0;
+        let error = with_policy(&policy, |_| retries += 1, failing(&["DB:QUERY"])).unwrap_err();
+        assert_eq!((error.error_type.as_str(), retries), ("DB:QUERY", 0));
+    }
+
+    #[test]
+    fn until_successful_retries_any_error_then_reports_exhaustion() {
+        let mut retries = 0;
+        assert_eq!(until_successful(2, 1, |_| retries += 1, failing(&["A", "B"])).unwrap(), 3);
+        assert_eq!(retries, 2);
+        let error = until_successful(1, 1, |_| {}, failing(&["A", "B"])).unwrap_err();
+        assert_eq!(error.error_type, "RETRY:RETRY_EXHAUSTED");
+        assert_eq!(error.description, "Failed after 1 retries: B: attempt 2");
+    }
+}
@@ -0,0 +1,284 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use chrono::{DateTime, Local, Utc};
+use chrono_tz::Tz;
+
+// When a Scheduler source fires: at a fixed frequency, or on a cron expression in a timezone
+pub enum Schedule {
+    FixedFrequency { interval: Duration, start_delay: Duration },
+    // Quartz style: `seconds minutes hours day-of-month month day-of-week [year]`; boxed, as a
+    // parsed expression is far larger than a fixed frequency
+    Cron { expression: Box<cron::Schedule>, timezone: Option<Tz> },
+}
+
+fn time_unit_millis(time_unit: &str) -> Result<u64, String> {
+    match time_unit {
+        "MILLISECONDS" => Ok(1),
+        "SECONDS" => Ok(1_000),
+        "MINUTES" => Ok(60_000),
+        "HOURS" => Ok(3_600_000),
+        "DAYS" => Ok(86_400_000),
+        other => Err(format!("Unknown time unit '{}'", other)),
+    }
+}
+
+impl Schedule {
+    pub fn fixed_frequency(frequency: &str, start_delay: &str, time_unit: &str) -> Result<Self, String> {
+        let unit = time_unit_millis(time_unit)?;
+        let frequency: u64 = frequency.trim().parse().map_err(|_| format!("Frequency '{}' is not a whole number", frequency))?;
+        let start_delay: u64 = if start_delay.trim().is_empty() {
+            0
+        } else {
+            start_delay.trim().parse().map_err(|_| format!("Start delay '{}' is not a whole number", start_delay))?
+        };
+        if frequency == 0 {
+            return Err("Frequency must be greater than zero".to_string());
+        }
+        // Larger spans than milliseconds in an i64 cannot be added to a fire time
+        let millis = |value: u64, name: &str| {
+            value.checked_mul(unit).filter(|millis| *millis <= i64::MAX as u64).map(Duration::from_millis).ok_or_else(|| {
+                format!("{} '{}' {} is too large", name, value, time_unit.to_lowercase())
+            })
+        };
+        Ok(Schedule::FixedFrequency {
+            interval: millis(frequency, "Frequency")?,
+            start_delay: millis(start_delay, "Start delay")?,
+        })
+    }
+
+    // An empty timezone uses the machine's local time
+    pub fn cron(expression: &str, timezone: &str) -> Result<Self, String> {
+        let expression = cron::Schedule::from_str(expression.trim())
+            .map_err(|error| format!("Invalid cron expression '{}': {}", expression, error))?;
+        let timezone = match timezone.trim() {
+            "" => None,
+            name => Some(Tz::from_str(name).map_err(|_| format!("Unknown timezone '{}'", name))?),
+        };
+        Ok(Schedule::Cron { expression: Box::new(expression), timezone })
+    }
+
+    // Up to `count` fire times strictly after `after`, for a scheduler started at `started`; fewer
+    // when the later ones are beyond the last representable time
+    pub fn fire_times(&self, started: DateTime<Utc>, after: DateTime<Utc>, count: usize) -> Vec<DateTime<Utc>> {
+        match self {
+            Schedule::FixedFrequency { interval, start_delay } => {
+                let interval = interval.as_millis() as i64;
+                let first = match started.checked_add_signed(chrono::Duration::milliseconds(start_delay.as_millis() as i64)) {
+                    Some(first) => first,
+                    None => return Vec::new(),
+                };
+                let skipped = if after < first {
+                    0
+                } else {
+                    (after - first).num_milliseconds() / interval + 1
+                };
+                (0..count as i64)
+                    .map_while(|index| {
+                        let offset = interval.checked_mul(skipped + index)?;
+                        first.checked_add_signed(chrono::Duration::try_milliseconds(offset)?)
+                    })
+                    .collect()
+            }
+            Schedule::Cron { expression, timezone: Some(timezone) } => expression
+                .after(&after.with_timezone(timezone))
+                .take(count)
+                .map(|time| time.with_timezone(&Utc))
+                .collect(),
+            Schedule::Cron { expression, timezone: None } => expression
+                .after(&after.with_timezone(&Local))
+                .take(count)
+                .map(|time| time.with_timezone(&Utc))
+                .collect(),
+        }
+    }
+
+    // Formats a fire time in the schedule's timezone
+    pub fn format_time(&self, time: DateTime<Utc>) -> String {
+        match self {
+            Schedule::Cron { timezone: Some(timezone), .. } => time.with_timezone(timezone).format("%Y-%m-%d %H:%M:%S %Z").to_string(),
+            _ => time.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S %:z").to_string(),
+        }
+    }
+}
+
+// Calls `on_fire` at every fire time until `running` turns false
+pub fn run_schedule(schedule: &Schedule, running: &AtomicBool, mut on_fire: impl FnMut()) {
+    let started = Utc::now();
+    let mut last = started - chrono::Duration::milliseconds(1);
+    while running.load(Ordering::SeqCst) {
+        let next = match schedule.fire_times(started, last, 1).first() {
+            Some(next) => *next,
+            None => return,
+        };
+        // Sleep in short steps so a stop request is noticed quickly
+        while let Ok(remaining) = (next - Utc::now()).to_std() {
+            if !running.load(Ordering::SeqCst) {
+                return;
+            }
+            thread::sleep(remaining.min(Duration::from_millis(100)));
+        }
+        if running.load(Ordering::SeqCst) {
+            on_fire();
+        }
+        last = next;
+    }
+}
+
+// What happens to a run that would exceed the flow's `max_concurrency`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum OverlapPolicy {
+    Skip,
+    Queue,
+}
+
+impl OverlapPolicy {
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("Queue") {
+            OverlapPolicy::Queue
+        } else {
+            OverlapPolicy::Skip
+        }
+    }
+}
+
+#[derive(Default)]
+struct LimiterState {
+    in_flight: usize,
+    queued: usize,
+}
+
+// Runs jobs on background threads, at most `max_concurrency` at a time
+pub struct RunLimiter {
+    max_concurrency: usize,
+    policy: OverlapPolicy,
+    state: Mutex<LimiterState>,
+}
+
+impl RunLimiter {
+    pub fn new(max_concurrency: usize, policy: OverlapPolicy) -> Arc<Self> {
+        Arc::new(Self {
+            max_concurrency: max_concurrency.max(1),
+            policy,
+            state: Mutex::new(LimiterState::default()),
+        })
+    }
+
+    // Starts `job` if a slot is free; otherwise queues or skips it. Returns false when skipped.
+    pub fn submit(self: &Arc<Self>, job: Arc<dyn Fn() + Send + Sync>) -> bool {
+        {
+            let mut state = self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.in_flight >= self.max_concurrency {
+                if self.policy == OverlapPolicy::Queue {
+                    state.queued += 1;
+                    return true;
+                }
+                return false;
+            }
+            state.in_flight += 1;
+        }
+        let limiter = self.clone();
+        thread::spawn(move || loop {
+            // A panicking run still gives its slot back; the panic hook has reported it
+            let _ = panic::catch_unwind(AssertUnwindSafe(|| job()));
+            // A finished run picks up a queued one before giving its slot back
+            let mut state = limiter.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+            if state.queued > 0 {
+                state.queued -= 1;
+            } else {
+                state.in_flight -= 1;
+                return;
+            }
+        });
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::mpsc;
+    use chrono::TimeZone;
+    use super::*;
+
+    fn at(hour: u32, minute: u32, second: u32) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2024, 1, 15, hour, minute, second).unwrap()
+    }
+
+    #[test]
+    fn fixed_frequencies_fire_after_the_start_delay() {
+        let schedule = Schedule::fixed_frequency("10", "5", "SECONDS").unwrap();
+        let started = at(12, 0, 0);
+        assert_eq!(schedule.fire_times(started, started, 3), vec![at(12, 0, 5), at(12, 0, 15), at(12, 0, 25)]);
+        // Strictly after `after`, also when it is a fire time itself
+        assert_eq!(schedule.fire_times(started, at(12, 0, 15), 2), vec![at(12, 0, 25), at(12, 0, 35)]);
+    }
+
+    #[test]
+    fn fixed_frequency_settings_are_checked() {
+        assert_eq!(Schedule::fixed_frequency("0", "", "SECONDS").err().unwrap(), "Frequency must be greater than zero");
+        assert!(Schedule::fixed_frequency("1.5", "", "SECONDS").is_err());
+        assert_eq!(Schedule::fixed_frequency("1", "", "WEEKS").err().unwrap(), "Unknown time unit 'WEEKS'");
+        let error = Schedule::fixed_frequency(&u64::MAX.to_string(), "", "DAYS").err().unwrap();
+        assert!(error.ends_with("is too large"), "{}", error);
+    }
+
+    #[test]
+    fn fire_times_stop_at_the_last_representable_time() {
+        let schedule = Schedule::fixed_frequency(&(i64::MAX as u64 / 4).to_string(), "", "MILLISECONDS").unwrap();
+        assert!(schedule.fire_times(at(12, 0, 0), at(12, 0, 0), 10).len() < 10);
+        let delayed = Schedule::fixed_frequency("1", &(i64::MAX as u64).to_string(), "MILLISECONDS").unwrap();
+        assert!(delayed.fire_times(at(12, 0, 0), at(12, 0, 0), 3).is_empty());
+    }
+
+    #[test]
+    fn cron_expressions_fire_in_their_timezone() {
+        let schedule = Schedule::cron("0 30 9 * * Mon-Fri", "Europe/Vienna").unwrap();
+        // Friday 2024-01-19 after the run, so the next ones are Monday and Tuesday
+        let fire_times = schedule.fire_times(at(0, 0, 0), Utc.with_ymd_and_hms(2024, 1, 19, 9, 0, 0).unwrap(), 2);
+        assert_eq!(fire_times, vec![
+            Utc.with_ymd_and_hms(2024, 1, 22, 8, 30, 0).unwrap(),
+            Utc.with_ymd_and_hms(2024, 1, 23, 8, 30, 0).unwrap(),
+        ]);
+        assert_eq!(schedule.format_time(fire_times[0]), "2024-01-22 09:30:00 CET");
+    }
+
+    #[test]
+    fn cron_settings_are_checked() {
+        assert!(Schedule::cron("not cron", "").err().unwrap().starts_with("Invalid cron expression 'not cron'"));
+        assert_eq!(Schedule::cron("0 * * * * *", "Mars/Olympus").err().unwrap(), "Unknown timezone 'Mars/Olympus'");
+    }
+
+    // A job that runs until the returned sender lets it finish
+    fn blocking_job(started: mpsc::Sender<()>) -> (Arc<dyn Fn() + Send + Sync>, mpsc::Sender<()>) {
+        let (release, released) = mpsc::channel::<()>();
+        let released = Mutex::new(released);
+        let job: Arc<dyn Fn() + Send + Sync> = Arc::new(move || {
+            started.send(()).unwrap();
+            let _ = released.lock().unwrap().recv();
+        });
+        (job, release)
+    }
+
+    #[test]
+    fn runs_beyond_max_concurrency_are_skipped_or_queued() {
+        let (started, runs) = mpsc::channel();
+        let (job, release) = blocking_job(started);
+        let skipping = RunLimiter::new(1, OverlapPolicy::Skip);
+        assert!(skipping.submit(job.clone()));
+        runs.recv().unwrap();
+        assert!(!skipping.submit(job.clone()));
+        release.send(()).unwrap();
+
+        let queueing = RunLimiter::new(1, OverlapPolicy::from_name("queue"));
+        assert!(queueing.submit(job.clone()));
+        runs.recv().unwrap();
+        assert!(queueing.submit(job));
+        release.send(()).unwrap();
+        // The queued run starts once the first one is done
+        runs.recv_timeout(Duration::from_secs(5)).unwrap();
+        release.send(()).unwrap();
+    }
+}
@@ -0,0 +1,316 @@
+use std::collections::VecDeque;
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{mpsc, Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use serde_json::Value;
+use super::message::{FlowError, Message};
+
+// Elements a For Each iterates: array items, object entries as single-key objects, nothing for
+// null, and any other value as a single element
+pub fn collection_items(collection: &Value) -> Vec<Value> {
+    match collection {
+        Value::Array(items) => items.clone(),
+        Value::Object(entries) => entries
+            .iter()
+            .map(|(key, value)| {
+                let mut entry = serde_json::Map::new();
+                entry.insert(key.clone(), value.clone());
+                Value::Object(entry)
+            })
+            .collect(),
+        Value::Null => Vec::new(),
+        other => vec![other.clone()],
+    }
+}
+
+// Groups elements into arrays of `batch_size`; a batch size of one or less keeps single elements
+pub fn batches(items: Vec<Value>, batch_size: usize) -> Vec<Value> {
+    if batch_size <= 1 {
+        return items;
+    }
+    items.chunks(batch_size).map(|batch| Value::Array(batch.to_vec())).collect()
+}
+
+enum RouteEvent {
+    Started(usize, Instant),
+    Finished(usize, Result<Message, FlowError>),
+}
+
+// Runs `route` on every message, at most `max_concurrency` at a time, and returns the results in
+// input order. `timeouts` holds the optional timeout of each route, counted from when it starts.
+// A route that runs past its timeout counts as failed and is no longer waited for; it is not
+// interrupted, so its worker is replaced to keep the other routes going. Failures are reported
+// together as one composite error.
+pub fn run_parallel<F>(messages: Vec<Message>, max_concurrency: usize, timeouts: Vec<Option<Duration>>, route: F) -> Result<Vec<Message>, FlowError>
+where
+    F: Fn(usize, Message) -> Result<Message, FlowError> + Send + Sync + 'static,
+{
+    let total = messages.len();
+    let pending: Arc<Mutex<Vec<Option<Message>>>> = Arc::new(Mutex::new(messages.into_iter().map(Some).collect()));
+    let next = Arc::new(AtomicUsize::new(0));
+    let route = Arc::new(route);
+    let (sender, receiver) = mpsc::channel();
+    let spawn_worker = || {
+        let (pending, next, route, sender) = (pending.clone(), next.clone(), route.clone(), sender.clone());
+        thread::spawn(move || loop {
+            let index = next.fetch_add(1, Ordering::SeqCst);
+            if index >= total {
+                return;
+            }
+            let message = pending.lock().ok().and_then(|mut pending| pending[index].take()).unwrap_or_default();
+            // The caller may have stopped listening after a timeout
+            if sender.send(RouteEvent::Started(index, Instant::now())).is_err() {
+                return;
+            }
+            let result = panic::catch_unwind(AssertUnwindSafe(|| route(index, message)))
+                .unwrap_or_else(|_| Err(FlowError::new("RUNTIME:PANIC", "Route panicked")));
+            if sender.send(RouteEvent::Finished(index, result)).is_err() {
+                return;
+            }
+        });
+    };
+    for _ in 0..max_concurrency.max(1).min(total) {
+        spawn_worker();
+    }
+
+    let mut results: Vec<Option<Result<Message, FlowError>>> = (0..total).map(|_| None).collect();
+    let mut deadlines: Vec<Option<Instant>> = vec![None; total];
+    let mut remaining = total;
+    while remaining > 0 {
+        let now = Instant::now();
+        let mut expired = 0;
+        for index in 0..total {
+            if results[index].is_none() && deadlines[index].is_some_and(|deadline| deadline <= now) {
+                let timeout = timeouts.get(index).copied().flatten().unwrap_or_default();
+                results[index] = Some(Err(FlowError::new("RUNTIME:TIMEOUT", format!("Route took longer than {} ms", timeout.as_millis()))));
+                remaining -= 1;
+                expired += 1;
+            }
+        }
+        if remaining == 0 {
+            break;
+        }
+        // Each worker stays stuck on its timed out route, so every one of them is replaced while
+        // routes are still waiting to start
+        let unstarted = total.saturating_sub(next.load(Ordering::SeqCst));
+        for _ in 0..expired.min(unstarted) {
+            spawn_worker();
+        }
+        let nearest = (0..total).filter(|index| results[*index].is_none()).filter_map(|index| deadlines[index]).min();
+        let event = match nearest {
+            Some(deadline) => match receiver.recv_timeout(deadline.saturating_duration_since(now)) {
+                Ok(event) => event,
+                Err(mpsc::RecvTimeoutError::Timeout) => continue,
+                Err(mpsc::RecvTimeoutError::Disconnected) => break,
+            },
+            None => match receiver.recv() {
+                Ok(event) => event,
+                Err(_) => break,
+            },
+        };
+        match event {
+            RouteEvent::Started(index, started) => {
+                deadlines[index] = timeouts.get(index).copied().flatten().map(|timeout| started + timeout);
+            }
+            RouteEvent::Finished(index, result) => {
+                if results[index].is_none() {
+                    results[index] = Some(result);
+                    remaining -= 1;
+                }
+            }
+        }
+    }
+
+    let mut succeeded = Vec::new();
+    let mut failures = Vec::new();
+    for (index, result) in results.into_iter().enumerate() {
+        match result {
+            Some(Ok(message)) => succeeded.push(message),
+            Some(Err(error)) => failures.push(format!("route {}: {}", index, error)),
+            None => failures.push(format!("route {}: did not run", index)),
+        }
+    }
+    if failures.is_empty() {
+        Ok(succeeded)
+    } else {
+        Err(FlowError::new(
+            "RUNTIME:COMPOSITE_ROUTING",
+            format!("{} of {} routes failed: {}", failures.len(), total, failures.join("; ")),
+        ))
+    }
+}
+
+// Scatter-Gather result: the payload becomes an object keyed by route index holding each route's
+// payload and attributes; variables set by the routes are merged in route order
+pub fn gather(mut message: Message, results: Vec<Message>) -> Message {
+    let mut aggregated = serde_json::Map::new();
+    for (index, result) in results.into_iter().enumerate() {
+        let mut route = serde_json::Map::new();
+        route.insert("payload".to_string(), result.payload);
+        route.insert("attributes".to_string(), Value::Object(result.attributes));
+        aggregated.insert(index.to_string(), Value::Object(route));
+        message.variables.extend(result.variables);
+    }
+    message.payload = Value::Object(aggregated);
+    message.mime_type = "application/json".to_string();
+    message
+}
+
+// Work started by an Async scope; it runs apart from the caller, which never sees its outcome
+pub type AsyncJob = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct AsyncState {
+    in_flight: usize,
+    waiting: VecDeque<AsyncJob>,
+}
+
+// Runs the jobs of one Async scope on background threads, at most `max_concurrency` at a time;
+// further jobs wait in submission order without holding up the caller
+pub struct AsyncQueue {
+    max_concurrency: usize,
+    state: Mutex<AsyncState>,
+}
+
+impl AsyncQueue {
+    pub fn new(max_concurrency: usize) -> Arc<Self> {
+        Arc::new(Self { max_concurrency: max_concurrency.max(1), state: Mutex::new(AsyncState::default()) })
+    }
+
+    pub fn submit(self: &Arc<Self>, job: AsyncJob) {
+        {
+            let mut state = match self.state.lock() {
+                Ok(state) => state,
+                Err(_) => return,
+            };
+            if state.in_flight >= self.max_concurrency {
+                state.waiting.push_back(job);
+                return;
+            }
+            state.in_flight += 1;
+        }
+        let queue = self.clone();
+        thread::spawn(move || {
+            let mut job = job;
+            loop {
+                // A panicking job must not take the slot with it. The panic hook has reported it
+                // already; jobs that log their panics catch them themselves.
+                let _ = panic::catch_unwind(AssertUnwindSafe(job));
+                let mut state = match queue.state.lock() {
+                    Ok(state) => state,
+                    Err(_) => return,
+                };
+                match state.waiting.pop_front() {
+                    Some(next) => job = next,
+                    None => {
+                        state.in_flight -= 1;
+                        return;
+                    }
+                }
+            }
+        });
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+    use super::*;
+
+    fn messages(payloads: &[Value]) -> Vec<Message> {
+        payloads.iter().cloned().map(Message::new).collect()
+    }
+
+    #[test]
+    fn collections_split_into_elements_and_batches() {
+        assert_eq!(collection_items(&json!([1, 2])), vec![json!(1), json!(2)]);
+        assert_eq!(collection_items(&json!({ "a": 1, "b": 2 })), vec![json!({ "a": 1 }), json!({ "b": 2 })]);
+        assert!(collection_items(&Value::Null).is_empty());
+        assert_eq!(collection_items(&json!("one")), vec![json!("one")]);
+        assert_eq!(batches(vec![json!(1), json!(2), json!(3)], 2), vec![json!([1, 2]), json!([3])]);
+        assert_eq!(batches(vec![json!(1), json!(2)], 1), vec![json!(1), json!(2)]);
+    }
+
+    #[test]
+    fn parallel_results_keep_the_input_order() {
+        let results = run_parallel(messages(&[json!(30), json!(0), json!(15)]), 3, vec![None; 3], |_, message| {
+            thread::sleep(Duration::from_millis(message.payload.as_u64().unwrap()));
+            Ok(message)
+        });
+        let payloads: Vec<Value> = results.unwrap().into_iter().map(|message| message.payload).collect();
+        assert_eq!(payloads, vec![json!(30), json!(0), json!(15)]);
+    }
+
+    #[test]
+    fn at_most_max_concurrency_routes_run_at_once() {
+        let running = Arc::new(AtomicUsize::new(0));
+        let most = Arc::new(AtomicUsize::new(0));
+        let (counter, peak) = (running.clone(), most.clone());
+        let results = run_parallel(vec![Message::default(); 8], 3, Vec::new(), move |_, message| {
+            peak.fetch_max(counter.fetch_add(1, Ordering::SeqCst) + 1, Ordering::SeqCst);
+            thread::sleep(Duration::from_millis(20));
+            counter.fetch_sub(1, Ordering::SeqCst);
+            Ok(message)
+        });
+        assert_eq!(results.unwrap().len(), 8);
+        assert!(most.load(Ordering::SeqCst) <= 3);
+    }
+
+    #[test]
+    fn failures_and_panics_are_reported_together() {
+        let error = run_parallel(messages(&[json!(0), json!(1), json!(2)]), 2, Vec::new(), |index, message| match index {
+            0 => Err(FlowError::new("TEST:FAILED", "first")),
+            1 => panic!("second"),
+            _ => Ok(message),
+        })
+        .unwrap_err();
+        assert_eq!(error.error_type, "RUNTIME:COMPOSITE_ROUTING");
+        assert!(error.description.starts_with("2 of 3 routes failed: route 0: TEST:FAILED"), "{}", error.description);
+        assert!(error.description.contains("route 1: RUNTIME:PANIC"), "{}", error.description);
+    }
+
+    #[test]
+    fn hung_routes_time_out_and_their_workers_are_replaced() {
+        let started = Instant::now();
+        let error = run_parallel(vec![Message::default(); 6], 2, vec![Some(Duration::from_millis(100)); 6], |index, message| {
+            thread::sleep(Duration::from_millis(if index < 2 { 2000 } else { 20 }));
+            Ok(message)
+        })
+        .unwrap_err();
+        // Both workers hang on the first two routes; the other four still run in time
+        assert!(started.elapsed() < Duration::from_millis(1000), "{:?}", started.elapsed());
+        assert!(error.description.starts_with("2 of 6 routes failed"), "{}", error.description);
+        assert!(error.description.contains("route 1: RUNTIME:TIMEOUT: Route took longer than 100 ms"), "{}", error.description);
+    }
+
+    #[test]
+    fn each_route_times_out_on_its_own_timeout_from_its_start() {
+        let timeouts = vec![None, Some(Duration::from_millis(50)), Some(Duration::from_millis(500))];
+        let error = run_parallel(vec![Message::default(); 3], 1, timeouts, |_, message| {
+            thread::sleep(Duration::from_millis(150));
+            Ok(message)
+        })
+        .unwrap_err();
+        // One worker runs the routes in turn; only the route with the short timeout is late
+        assert!(error.description.starts_with("1 of 3 routes failed: route 1: RUNTIME:TIMEOUT"), "{}", error.description);
+    }
+
+    #[test]
+    fn gathered_routes_are_keyed_by_index() {
+        let mut first = Message::new(json!("a"));
+        first.attributes.insert("status".to_string(), json!(200));
+        first.variables.insert("shared".to_string(), json!(1));
+        let mut second = Message::new(json!({ "b": true }));
+        second.variables.insert("shared".to_string(), json!(2));
+        let gathered = gather(Message::new(json!("input")), vec![first, second]);
+        assert_eq!(gathered.payload, json!({
+            "0": { "payload": "a", "attributes": { "status": 200 } },
+            "1": { "payload": { "b": true }, "attributes": {} },
+        }));
+        assert_eq!(gathered.mime_type, "application/json");
+        // Later routes win
+        assert_eq!(gathered.variables["shared"], json!(2));
+    }
+}
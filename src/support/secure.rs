@@ -0,0 +1,241 @@
+// Secure properties: values encrypted with AES-256-GCM and stored as `![pbkdf2:base64]`, where the
+// base64 text holds a random salt, the nonce and the ciphertext. Each value's key is derived from the
+// salt and a passphrase read from `SECURE_PROPERTIES_KEY`, or from the file named by
+// `SECURE_PROPERTIES_KEY_FILE`, with PBKDF2-HMAC-SHA256. Values written before salting, `![base64]`
+// with the nonce and the ciphertext keyed by the passphrase's SHA-256 digest, can still be decrypted.
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::sync::{Mutex, OnceLock};
+use aes_gcm::aead::rand_core::RngCore;
+use aes_gcm::aead::{Aead, AeadCore, KeyInit, OsRng};
+use aes_gcm::{Aes256Gcm, Key, Nonce};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use sha2::{Digest, Sha256};
+use super::message::FlowError;
+
+pub const KEY_VARIABLE: &str = "SECURE_PROPERTIES_KEY";
+pub const KEY_FILE_VARIABLE: &str = "SECURE_PROPERTIES_KEY_FILE";
+// Where generated services read their secure properties unless `SECURE_PROPERTIES_FILE` is set
+pub const FILE_VARIABLE: &str = "SECURE_PROPERTIES_FILE";
+pub const DEFAULT_FILE: &str = "properties/secure.properties";
+
+const NONCE_LENGTH: usize = 12;
+const SALT_LENGTH: usize = 16;
+#[cfg(not(test))]
+const KEY_ROUNDS: u32 = 600_000;
+// Unoptimized test builds would spend seconds on every key
+#[cfg(test)]
+const KEY_ROUNDS: u32 = 1_000;
+// Marks values whose key is salted
+const SALTED_PREFIX: &str = "pbkdf2:";
+
+// `key=value` lines; blank lines and lines starting with `#` are ignored
+pub fn parse_properties(text: &str) -> BTreeMap<String, String> {
+    text.lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| line.split_once('='))
+        .map(|(key, value)| (key.trim().to_string(), value.trim().to_string()))
+        .collect()
+}
+
+// Text of an encrypted value without its `![...]` wrapper
+pub fn encrypted_text(value: &str) -> Option<&str> {
+    value.trim().strip_prefix("![")?.strip_suffix(']')
+}
+
+pub struct Cipher {
+    passphrase: String,
+}
+
+// Keys already derived in this process, by passphrase and salt; every value has its own salt and a
+// derivation takes a noticeable time
+type DerivedKeys = HashMap<(String, Vec<u8>), Aes256Gcm>;
+
+fn derived_key(passphrase: &str, salt: &[u8]) -> Aes256Gcm {
+    static KEYS: OnceLock<Mutex<DerivedKeys>> = OnceLock::new();
+    let mut keys = KEYS.get_or_init(Default::default).lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    keys.entry((passphrase.to_string(), salt.to_vec()))
+        .or_insert_with(|| {
+            let mut key = [0u8; 32];
+            pbkdf2::pbkdf2_hmac::<Sha256>(passphrase.as_bytes(), salt, KEY_ROUNDS, &mut key);
+            Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&key))
+        })
+        .clone()
+}
+
+impl Cipher {
+    pub fn from_passphrase(passphrase: &str) -> Self {
+        Self { passphrase: passphrase.to_string() }
+    }
+
+    // Key from the environment variable, or else from the key file
+    pub fn from_environment() -> Result<Self, FlowError> {
+        if let Ok(passphrase) = std::env::var(KEY_VARIABLE) {
+            if !passphrase.is_empty() {
+                return Ok(Self::from_passphrase(&passphrase));
+            }
+        }
+        match std::env::var(KEY_FILE_VARIABLE) {
+            Ok(path) => fs::read_to_string(&path)
+                .map(|passphrase| Self::from_passphrase(passphrase.trim()))
+                .map_err(|error| FlowError::new("SECURE:KEY", format!("Cannot read the key file {}: {}", path, error))),
+            Err(_) => Err(FlowError::new(
+                "SECURE:KEY",
+                format!("No secure properties key: set {} or {}", KEY_VARIABLE, KEY_FILE_VARIABLE),
+            )),
+        }
+    }
+
+    pub fn encrypt(&self, plain: &str) -> String {
+        let mut salt = [0u8; SALT_LENGTH];
+        OsRng.fill_bytes(&mut salt);
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let ciphertext = derived_key(&self.passphrase, &salt).encrypt(&nonce, plain.as_bytes())
+            .expect("AES-GCM encryption does not fail");
+        let mut bytes = salt.to_vec();
+        bytes.extend(nonce);
+        bytes.extend(ciphertext);
+        format!("![{}{}]", SALTED_PREFIX, STANDARD.encode(bytes))
+    }
+
+    // Accepts values with or without their `![...]` wrapper
+    pub fn decrypt(&self, value: &str) -> Result<String, FlowError> {
+        let text = encrypted_text(value).unwrap_or(value.trim());
+        let invalid = || FlowError::new("SECURE:DECRYPT", "Value cannot be decrypted with this key");
+        let (cipher, bytes) = match text.strip_prefix(SALTED_PREFIX) {
+            Some(text) => {
+                let bytes = STANDARD.decode(text).map_err(|_| invalid())?;
+                if bytes.len() <= SALT_LENGTH {
+                    return Err(invalid());
+                }
+                (derived_key(&self.passphrase, &bytes[..SALT_LENGTH]), bytes[SALT_LENGTH..].to_vec())
+            }
+            None => {
+                let digest = Sha256::digest(self.passphrase.as_bytes());
+                (Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&digest)), STANDARD.decode(text).map_err(|_| invalid())?)
+            }
+        };
+        if bytes.len() <= NONCE_LENGTH {
+            return Err(invalid());
+        }
+        let (nonce, ciphertext) = bytes.split_at(NONCE_LENGTH);
+        let plain = cipher.decrypt(Nonce::from_slice(nonce), ciphertext).map_err(|_| invalid())?;
+        String::from_utf8(plain).map_err(|_| invalid())
+    }
+}
+
+// Encrypted values of one secure property file, decrypted on use
+pub struct SecureProperties {
+    values: BTreeMap<String, String>,
+}
+
+impl SecureProperties {
+    pub fn parse(text: &str) -> Self {
+        Self { values: parse_properties(text) }
+    }
+
+    pub fn contains(&self, name: &str) -> bool {
+        self.values.contains_key(name)
+    }
+
+    pub fn get(&self, name: &str) -> Result<String, FlowError> {
+        let value = self.values.get(name)
+            .ok_or_else(|| FlowError::new("SECURE:UNDEFINED", format!("Secure property '{}' is not defined", name)))?;
+        if encrypted_text(value).is_none() {
+            return Err(FlowError::new("SECURE:DECRYPT", format!("Secure property '{}' is not encrypted", name)));
+        }
+        Cipher::from_environment()?.decrypt(value)
+            .map_err(|error| FlowError::new(&error.error_type, format!("Secure property '{}': {}", name, error.description)))
+    }
+}
+
+// Replaces every `${secure::name}` in `text` with the value `lookup` returns for `name`
+pub fn replace_placeholders<F>(text: &str, mut lookup: F) -> Result<String, FlowError>
+where
+    F: FnMut(&str) -> Result<String, FlowError>,
+{
+    let mut resolved = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("${secure::") {
+        let end = rest[start..].find('}')
+            .ok_or_else(|| FlowError::new("SECURE:UNDEFINED", format!("Unclosed placeholder in '{}'", text)))? + start;
+        resolved.push_str(&rest[..start]);
+        resolved.push_str(&lookup(rest[start + 10..end].trim())?);
+        rest = &rest[end + 1..];
+    }
+    resolved.push_str(rest);
+    Ok(resolved)
+}
+
+// Secure properties of a generated service, read once from `SECURE_PROPERTIES_FILE`
+fn service_properties() -> &'static Result<SecureProperties, FlowError> {
+    static PROPERTIES: OnceLock<Result<SecureProperties, FlowError>> = OnceLock::new();
+    PROPERTIES.get_or_init(|| {
+        let path = std::env::var(FILE_VARIABLE).unwrap_or_else(|_| DEFAULT_FILE.to_string());
+        fs::read_to_string(&path)
+            .map(|text| SecureProperties::parse(&text))
+            .map_err(|error| FlowError::new("SECURE:UNDEFINED", format!("Cannot read {}: {}", path, error)))
+    })
+}
+
+// Resolves the secure placeholders of a generated service's text at the time it is used
+pub fn resolve(text: &str) -> Result<String, FlowError> {
+    let properties = service_properties().as_ref().map_err(Clone::clone)?;
+    replace_placeholders(text, |name| properties.get(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypted_values_decrypt_with_the_same_passphrase() {
+        let cipher = Cipher::from_passphrase("correct horse");
+        let encrypted = cipher.encrypt("s3cret");
+        assert!(encrypted.starts_with("![pbkdf2:") && encrypted.ends_with(']'), "{}", encrypted);
+        assert_eq!(cipher.decrypt(&encrypted).unwrap(), "s3cret");
+        // Unwrapped text decrypts as well
+        assert_eq!(cipher.decrypt(encrypted_text(&encrypted).unwrap()).unwrap(), "s3cret");
+    }
+
+    #[test]
+    fn every_encryption_has_its_own_salt_and_nonce() {
+        let cipher = Cipher::from_passphrase("correct horse");
+        assert_ne!(cipher.encrypt("same"), cipher.encrypt("same"));
+    }
+
+    #[test]
+    fn another_passphrase_or_a_changed_value_does_not_decrypt() {
+        let encrypted = Cipher::from_passphrase("correct horse").encrypt("s3cret");
+        let error = Cipher::from_passphrase("battery staple").decrypt(&encrypted).unwrap_err();
+        assert_eq!(error.error_type, "SECURE:DECRYPT");
+        let mut bytes = STANDARD.decode(encrypted_text(&encrypted).unwrap().trim_start_matches(SALTED_PREFIX)).unwrap();
+        *bytes.last_mut().unwrap() ^= 1;
+        let tampered = format!("![{}{}]", SALTED_PREFIX, STANDARD.encode(bytes));
+        assert!(Cipher::from_passphrase("correct horse").decrypt(&tampered).is_err());
+        assert!(Cipher::from_passphrase("correct horse").decrypt("![pbkdf2:AAAA]").is_err());
+    }
+
+    #[test]
+    fn unsalted_values_from_before_salting_still_decrypt() {
+        // Written the way values were before salting: nonce and ciphertext keyed by SHA-256
+        let digest = Sha256::digest("correct horse".as_bytes());
+        let legacy = Aes256Gcm::new(Key::<Aes256Gcm>::from_slice(&digest));
+        let nonce = Aes256Gcm::generate_nonce(&mut OsRng);
+        let mut bytes = nonce.to_vec();
+        bytes.extend(legacy.encrypt(&nonce, "old secret".as_bytes()).unwrap());
+        let value = format!("![{}]", STANDARD.encode(bytes));
+        assert_eq!(Cipher::from_passphrase("correct horse").decrypt(&value).unwrap(), "old secret");
+        assert!(Cipher::from_passphrase("battery staple").decrypt(&value).is_err());
+    }
+
+    #[test]
+    fn placeholders_are_replaced_by_their_lookup() {
+        let resolved = replace_placeholders("user=${secure::db.user};password=${secure:: db.password }", |name| Ok(name.to_uppercase()));
+        assert_eq!(resolved.unwrap(), "user=DB.USER;password=DB.PASSWORD");
+        let error = replace_placeholders("${secure::open", |name| Ok(name.to_string())).unwrap_err();
+        assert_eq!(error.error_type, "SECURE:UNDEFINED");
+    }
+}
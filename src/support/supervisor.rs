@@ -0,0 +1,110 @@
+use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use super::message::FlowError;
+
+// What a run does when its flow already has `max_concurrency` runs in flight
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backpressure {
+    // Wait for a free slot
+    Queue,
+    // Fail at once with `RUNTIME:BACKPRESSURE`
+    Reject,
+}
+
+impl Backpressure {
+    pub fn from_name(name: &str) -> Self {
+        if name.eq_ignore_ascii_case("Reject") {
+            Backpressure::Reject
+        } else {
+            Backpressure::Queue
+        }
+    }
+}
+
+// Live state of a flow
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct FlowState {
+    pub running: bool,
+    pub in_flight: usize,
+    pub queued: usize,
+}
+
+// Admits the runs of one flow: none while it is stopped, at most `max_concurrency` at a time
+pub struct FlowControl {
+    name: String,
+    max_concurrency: usize,
+    backpressure: Backpressure,
+    state: Mutex<FlowState>,
+    slot_freed: Condvar,
+}
+
+// Held for the duration of a run; frees the slot when dropped
+pub struct Permit(Arc<FlowControl>);
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        self.0.locked().in_flight -= 1;
+        self.0.slot_freed.notify_one();
+    }
+}
+
+impl FlowControl {
+    pub fn new(name: &str, max_concurrency: usize, backpressure: Backpressure, running: bool) -> Arc<Self> {
+        Arc::new(Self {
+            name: name.to_string(),
+            max_concurrency: max_concurrency.max(1),
+            backpressure,
+            state: Mutex::new(FlowState { running, ..Default::default() }),
+            slot_freed: Condvar::new(),
+        })
+    }
+
+    // The counters stay consistent whatever panicked while holding the lock, so a poisoned lock
+    // is used as is rather than failing every later run
+    fn locked(&self) -> MutexGuard<'_, FlowState> {
+        self.state.lock().unwrap_or_else(|poisoned| poisoned.into_inner())
+    }
+
+    pub fn start(&self) {
+        self.locked().running = true;
+    }
+
+    // Runs in flight finish; queued runs are rejected
+    pub fn stop(&self) {
+        self.locked().running = false;
+        self.slot_freed.notify_all();
+    }
+
+    pub fn state(&self) -> FlowState {
+        *self.locked()
+    }
+
+    fn stopped_error(&self) -> FlowError {
+        FlowError::new("RUNTIME:FLOW_STOPPED", format!("Flow '{}' is stopped", self.name))
+    }
+
+    // Waits for a slot, or fails when the flow is stopped or rejects runs beyond its limit
+    pub fn acquire(self: &Arc<Self>) -> Result<Permit, FlowError> {
+        let mut state = self.locked();
+        if !state.running {
+            return Err(self.stopped_error());
+        }
+        if state.in_flight >= self.max_concurrency {
+            if self.backpressure == Backpressure::Reject {
+                return Err(FlowError::new(
+                    "RUNTIME:BACKPRESSURE",
+                    format!("Flow '{}' already runs {} messages", self.name, self.max_concurrency),
+                ));
+            }
+            state.queued += 1;
+            while state.running && state.in_flight >= self.max_concurrency {
+                state = self.slot_freed.wait(state).unwrap_or_else(|poisoned| poisoned.into_inner());
+            }
+            state.queued -= 1;
+            if !state.running {
+                return Err(self.stopped_error());
+            }
+        }
+        state.in_flight += 1;
+        Ok(Permit(self.clone()))
+    }
+}